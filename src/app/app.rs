@@ -11,30 +11,60 @@ use ratatui::{
 };
 
 use super::{
-    asm::assembly_line::AssemblyLine,
+    asm::{
+        annotation::Annotations,
+        assembly_line::{AssemblyLine, AssemblyViewRow},
+        code_region::MarkedRange,
+        function::FunctionIndex,
+        xref::XrefIndex,
+    },
+    bookmarks::Bookmarks,
     data::Data,
     files::filesystem::FileSystem,
     frame_info::{FrameInfo, InfoViewFrameInfo},
     help::HelpLine,
+    history::navigation::Navigation,
     info_mode::InfoMode,
     log::{logger::Logger, NotificationLevel},
     plugins::plugin_manager::PluginManager,
     popup::popup_state::PopupState,
     settings::{color_settings::ColorSettings, Settings},
+    templates::template_manager::TemplateManager,
     widgets::logo::Logo,
 };
 
-use crate::{args::Args, get_app_context, headers::Header};
+use crate::{
+    args::Args,
+    get_app_context,
+    headers::{target::Target, Header},
+};
 
 pub struct App {
     pub(super) plugin_manager: PluginManager,
+    pub(super) template_manager: TemplateManager,
     pub(super) filesystem: FileSystem,
     pub(super) header: Header,
+    /// The byte ranges that are parsed to build `header`, see [`Header::get_structural_ranges`].
+    pub(super) header_ranges: Vec<(usize, usize)>,
     pub(super) logger: Logger,
     pub(super) help_list: Vec<HelpLine>,
     pub(super) data: Data,
     pub(super) assembly_offsets: Vec<usize>,
     pub(super) assembly_instructions: Vec<AssemblyLine>,
+    pub(super) marked_ranges: Vec<MarkedRange>,
+    pub(super) annotations: Annotations,
+    pub(super) bookmarks: Bookmarks,
+    /// The architecture chosen by the user for files without a header.
+    pub(super) target: Option<Target>,
+    /// The architecture given on the command line, it is used by every opened file
+    /// and it takes precedence over the target saved in the project.
+    pub(super) cli_target: Option<Target>,
+    /// Set when the annotations, the bookmarks, the marks or the target change,
+    /// they are saved in the project file.
+    pub(super) project_dirty: bool,
+    pub(super) xrefs: XrefIndex,
+    pub(super) functions: FunctionIndex,
+    pub(super) navigation: Navigation,
     pub(super) text_last_searched_string: String,
     pub(super) info_mode: InfoMode,
     pub(super) scroll: usize,
@@ -139,10 +169,21 @@ impl App {
             FileSystem::new_local(&args.path).map_err(|e| e.to_string())?
         };
         let screen_size = Self::get_size(terminal)?;
+        let target = match &args.arch {
+            Some(arch) => Some(Target::new(
+                arch,
+                args.mode.as_deref(),
+                args.endian.as_deref(),
+                args.base.as_deref(),
+            )?),
+            None => None,
+        };
 
         let mut app = App {
             filesystem,
             screen_size,
+            target,
+            cli_target: target,
             help_list: Self::help_list(&settings.key),
             settings,
             logger,
@@ -161,6 +202,18 @@ impl App {
             }
         };
 
+        app.template_manager =
+            match TemplateManager::load(args.templates.as_deref(), &mut app.logger) {
+                Ok(templates) => templates,
+                Err(e) => {
+                    app.log(
+                        NotificationLevel::Error,
+                        &format!("Error loading templates: {e}"),
+                    );
+                    TemplateManager::default()
+                }
+            };
+
         if app.filesystem.is_file(app.filesystem.pwd()) {
             let path = app.filesystem.pwd().to_string();
             app.open_file(&path, terminal).map_err(|e| e.to_string())?;
@@ -253,11 +306,10 @@ impl App {
                         info_view_frame_info = InfoViewFrameInfo::AssemblyView {
                             scroll: assembly_start_index,
                         };
-                        let assembly_end_index = (assembly_start_index + f.area().height as usize
-                            - 2)
-                        .min(self.assembly_instructions.len());
-                        let assembly_subview_lines =
-                            &self.assembly_instructions[assembly_start_index..assembly_end_index];
+                        let assembly_subview_rows = self.get_assembly_view_rows(
+                            assembly_start_index,
+                            f.area().height as usize - 2,
+                        );
                         let mut assembly_subview = Text::default();
                         let address_min_width = self
                             .assembly_instructions
@@ -266,13 +318,37 @@ impl App {
                             .unwrap_or(1);
                         assembly_subview
                             .lines
-                            .extend(assembly_subview_lines.iter().map(|x| {
-                                x.to_line(
-                                    &self.settings.color,
-                                    self.get_cursor_position().global_byte_index,
-                                    &self.header,
-                                    address_min_width,
-                                )
+                            .extend(assembly_subview_rows.iter().map(|row| match row {
+                                AssemblyViewRow::Line(index) => {
+                                    self.assembly_instructions[*index].to_line(
+                                        &self.settings.color,
+                                        self.get_cursor_position().global_byte_index,
+                                        &self.header,
+                                        self.data.bytes(),
+                                        &self.annotations,
+                                        &self.xrefs,
+                                        address_min_width,
+                                    )
+                                }
+                                AssemblyViewRow::FunctionStart(index)
+                                | AssemblyViewRow::FunctionEnd(index) => {
+                                    let line = &self.assembly_instructions[*index];
+                                    let is_start = matches!(row, AssemblyViewRow::FunctionStart(_));
+                                    let function = if is_start {
+                                        self.functions.starting_at(line.file_address())
+                                    } else {
+                                        self.functions
+                                            .ending_at(line.file_address() + line.len() as u64)
+                                    }
+                                    .expect("The row is shown only if the function exists");
+                                    Self::function_separator_to_line(
+                                        &self.settings.color,
+                                        function,
+                                        is_start,
+                                        &self.xrefs,
+                                        address_min_width,
+                                    )
+                                }
                             }));
                         ratatui::widgets::Paragraph::new(assembly_subview).block(
                             Block::default()
@@ -280,6 +356,24 @@ impl App {
                                 .borders(Borders::TOP | Borders::RIGHT),
                         )
                     }
+                    InfoMode::Graph => {
+                        let (graph_lines, graph_rows) =
+                            self.get_graph_view(info_view_rect.width.saturating_sub(1) as usize);
+                        let graph_start_index =
+                            self.get_graph_view_scroll(&graph_rows, f.area().height as usize - 2);
+                        info_view_frame_info = InfoViewFrameInfo::GraphView {
+                            scroll: graph_start_index,
+                        };
+                        let mut graph_subview = Text::default();
+                        graph_subview
+                            .lines
+                            .extend(graph_lines.into_iter().skip(graph_start_index));
+                        ratatui::widgets::Paragraph::new(graph_subview).block(
+                            Block::default()
+                                .title("Graph View")
+                                .borders(Borders::TOP | Borders::RIGHT),
+                        )
+                    }
                 };
 
                 f.render_widget(address_block, address_rect);
@@ -288,6 +382,21 @@ impl App {
             }
             f.render_widget(status_block, status_rect);
             f.render_stateful_widget(scrollbar, scrollbar_rect, &mut scrollbar_state);
+            for bookmark in self.bookmarks.bookmarks() {
+                let Some(row) = (bookmark.file_address as usize * scrollbar_rect.height as usize)
+                    .checked_div(total_amount)
+                else {
+                    break;
+                };
+                let row = (row as u16).min(scrollbar_rect.height.saturating_sub(1));
+                if let Some(cell) = f
+                    .buffer_mut()
+                    .cell_mut((scrollbar_rect.x, scrollbar_rect.y + row))
+                {
+                    cell.set_symbol("─")
+                        .set_style(self.settings.color.scrollbar_bookmark);
+                }
+            }
 
             let mut this_frame_info = FrameInfo {
                 popup: None,
@@ -376,13 +485,24 @@ impl Default for App {
     fn default() -> Self {
         App {
             plugin_manager: PluginManager::default(),
+            template_manager: TemplateManager::default(),
             filesystem: FileSystem::default(),
             header: Header::None,
+            header_ranges: Vec::new(),
             logger: Logger::default(),
             help_list: Self::help_list(&Settings::default().key),
             data: Data::default(),
             assembly_offsets: Vec::new(),
             assembly_instructions: Vec::new(),
+            marked_ranges: Vec::new(),
+            annotations: Annotations::default(),
+            bookmarks: Bookmarks::default(),
+            target: None,
+            cli_target: None,
+            project_dirty: false,
+            xrefs: XrefIndex::default(),
+            functions: FunctionIndex::default(),
+            navigation: Navigation::default(),
             text_last_searched_string: String::new(),
             info_mode: InfoMode::Text,
             scroll: 0,
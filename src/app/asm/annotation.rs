@@ -0,0 +1,207 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::headers::Header;
+
+/// The labels and the comments added by the user, keyed by file address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    labels: BTreeMap<u64, String>,
+    comments: BTreeMap<u64, String>,
+    /// The symbols of the header with the labels added, keyed by virtual address.
+    /// `None` when there are no labels and the symbols of the header are used as they are.
+    symbols: Option<HashMap<u64, String>>,
+}
+
+impl Annotations {
+    /// Returns the symbols of the header together with the labels, a label replaces
+    /// the symbol at the same address.
+    pub fn symbols<'a>(&'a self, header: &'a Header) -> Option<&'a HashMap<u64, String>> {
+        self.symbols.as_ref().or(header.get_symbols())
+    }
+
+    /// Adds the labels to the symbols of the header, it must be called every time
+    /// the labels or the header change.
+    pub fn update_symbols(&mut self, header: &Header) {
+        if self.labels.is_empty() {
+            self.symbols = None;
+            return;
+        }
+        let mut symbols = header.get_symbols().cloned().unwrap_or_default();
+        for (file_address, label) in self.labels.iter() {
+            symbols.insert(Self::virtual_address(header, *file_address), label.clone());
+        }
+        self.symbols = Some(symbols);
+    }
+
+    /// Without a header the virtual addresses are the file addresses.
+    pub fn virtual_address(header: &Header, file_address: u64) -> u64 {
+        match header {
+            Header::None => file_address,
+            _ => header
+                .physical_to_virtual_address(file_address)
+                .unwrap_or(file_address),
+        }
+    }
+
+    pub fn label(&self, file_address: u64) -> Option<&str> {
+        self.labels.get(&file_address).map(String::as_str)
+    }
+
+    /// Returns the file address of the label called `name`.
+    pub fn label_address(&self, name: &str) -> Option<u64> {
+        self.labels
+            .iter()
+            .find(|(_, label)| label.as_str() == name)
+            .map(|(address, _)| *address)
+    }
+
+    /// Sets the label at `file_address`, an empty label removes it.
+    /// A label can't contain whitespace nor be used at another address.
+    pub fn set_label(&mut self, file_address: u64, label: &str) -> Result<(), String> {
+        let label = label.trim();
+        if label.is_empty() {
+            self.labels.remove(&file_address);
+            return Ok(());
+        }
+        if label.contains(char::is_whitespace) {
+            return Err(format!("Invalid label: \"{}\"", label));
+        }
+        if let Some(address) = self
+            .label_address(label)
+            .filter(|address| *address != file_address)
+        {
+            return Err(format!("Label {} is already used at {:#X}", label, address));
+        }
+        self.labels.insert(file_address, label.to_string());
+        Ok(())
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.labels
+            .iter()
+            .map(|(address, label)| (*address, label.as_str()))
+    }
+
+    pub fn comment(&self, file_address: u64) -> Option<&str> {
+        self.comments.get(&file_address).map(String::as_str)
+    }
+
+    /// Sets the comment at `file_address`, an empty comment removes it.
+    pub fn set_comment(&mut self, file_address: u64, comment: &str) {
+        let comment = comment.trim();
+        if comment.is_empty() {
+            self.comments.remove(&file_address);
+        } else {
+            self.comments.insert(file_address, comment.to_string());
+        }
+    }
+
+    pub fn comments(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.comments
+            .iter()
+            .map(|(address, comment)| (*address, comment.as_str()))
+    }
+
+    /// Returns the addresses of the comments between `start` included and `end` excluded.
+    pub fn comments_in(&self, start: u64, end: u64) -> impl Iterator<Item = u64> + '_ {
+        self.comments.range(start..end).map(|(address, _)| *address)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::{asm::assembly_line::AssemblyLine, App};
+
+    use super::*;
+
+    #[test]
+    fn test_annotations() {
+        let mut annotations = Annotations::default();
+        assert!(annotations.symbols(&Header::None).is_none());
+        annotations.set_label(0x10, "loop").unwrap();
+        assert!(annotations.set_label(0x20, "loop").is_err());
+        assert!(annotations.set_label(0x20, "two words").is_err());
+        annotations.update_symbols(&Header::None);
+        assert_eq!(
+            annotations.symbols(&Header::None).unwrap().get(&0x10),
+            Some(&"loop".to_string())
+        );
+        assert_eq!(annotations.label_address("loop"), Some(0x10));
+        annotations.set_label(0x10, "").unwrap();
+        annotations.update_symbols(&Header::None);
+        assert!(annotations.symbols(&Header::None).is_none());
+
+        annotations.set_comment(0x4, " counter ");
+        assert_eq!(annotations.comment(0x4), Some("counter"));
+        assert_eq!(annotations.comments_in(0, 4).count(), 0);
+        assert_eq!(annotations.comments_in(4, 8).collect::<Vec<_>>(), vec![4]);
+        annotations.set_comment(0x4, "");
+        assert!(annotations.comment(0x4).is_none());
+    }
+
+    #[test]
+    fn test_labels_in_assembly() {
+        // jmp 0x10; nop...
+        let mut bytes = vec![0xEB, 0x0E];
+        bytes.extend([0x90; 15]);
+        let mut app = App::mockup(bytes);
+        app.set_label(0x10, "target");
+        app.set_comment(0, "skip the nops");
+        let line = app.assembly_instructions[app.assembly_offsets[0]]
+            .to_line(
+                &app.settings.color,
+                0,
+                &app.header,
+                app.data.bytes(),
+                &app.annotations,
+                &app.xrefs,
+                1,
+            )
+            .to_string();
+        assert!(line.contains("jmp target"));
+        assert!(line.ends_with("; skip the nops"));
+        let line = app.assembly_instructions[app.assembly_offsets[0x10]]
+            .to_line(
+                &app.settings.color,
+                0,
+                &app.header,
+                app.data.bytes(),
+                &app.annotations,
+                &app.xrefs,
+                1,
+            )
+            .to_string();
+        assert!(line.contains("<target>"));
+
+        app.jump_to_symbol("target");
+        assert_eq!(app.get_cursor_position().global_byte_index, 0x10);
+        assert_eq!(app.find_symbols("targ"), vec![(0x10, "target".to_string())]);
+    }
+
+    #[test]
+    fn test_rename_label() {
+        // call 0x10; ret; nop...
+        let mut bytes = vec![0xE8, 0x0B, 0x00, 0x00, 0x00, 0xC3];
+        bytes.extend([0x90; 11]);
+        let mut app = App::mockup(bytes);
+        // only the lines that refer to the label are decoded again
+        for label in ["first", "second", ""] {
+            app.set_label(0x10, label);
+            let lines = app.assembly_instructions.clone();
+            let functions = app.functions.clone();
+            app.disassemble();
+            assert_eq!(app.assembly_instructions, lines, "{}", label);
+            assert_eq!(app.functions, functions, "{}", label);
+        }
+        let AssemblyLine::Instruction(call) = &app.assembly_instructions[app.assembly_offsets[0]]
+        else {
+            panic!("not an instruction");
+        };
+        assert_eq!(call.instruction.to_string(), "call 0x10");
+        assert_eq!(app.functions.starting_at(0x10).unwrap().name, "sub_10");
+    }
+}
@@ -1,17 +1,33 @@
+use std::collections::HashMap;
+
 use ratatui::text::{Line, Span};
 
 use crate::{
     app::{
-        instruction::Instruction, log::NotificationLevel, settings::color_settings::ColorSettings,
+        instruction::Instruction,
+        log::NotificationLevel,
+        popup::popup_state::PopupState,
+        settings::{app_settings::X86Syntax, color_settings::ColorSettings},
         App,
     },
-    asm::assembler::assemble,
+    asm::assembler::{assemble, resolve_names},
+    fuzzer::fuzzy_search_in_place,
     get_app_context,
-    headers::{section::Section, Header},
+    headers::{target::Target, Header},
 };
 
 use super::{
-    assembly_line::AssemblyLine, instruction_tag::InstructionTag, section_tag::SectionTag,
+    annotation::Annotations,
+    assembly_line::{AssemblyLine, AssemblyViewRow},
+    code_region::{CodeRegion, MarkedRange, RangeKind},
+    decoders::Decoders,
+    flow::Flow,
+    function::{Function, FunctionIndex},
+    instruction_tag::InstructionTag,
+    pc_relative::{data_preview, resolve_page_offsets},
+    recursive_descent::{recursive_descent, CodeRange},
+    section_tag::SectionTag,
+    xref::{XrefIndex, XrefKind},
 };
 
 impl App {
@@ -19,7 +35,7 @@ impl App {
         if filter.is_empty() {
             return Vec::new();
         }
-        let symbol_table = self.header.get_symbols();
+        let symbol_table = self.annotations.symbols(&self.header);
         if let Some(symbol_table) = symbol_table {
             let mut symbols: Vec<(u64, String)> = symbol_table
                 .iter()
@@ -33,14 +49,18 @@ impl App {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn instruction_to_line(
         color_settings: &ColorSettings,
         instruction: &InstructionTag,
         selected: bool,
         header: &Header,
+        bytes: &[u8],
+        annotations: &Annotations,
+        xrefs: &XrefIndex,
         address_min_width: usize,
     ) -> Line<'static> {
-        let symbol_table = header.get_symbols();
+        let symbol_table = annotations.symbols(header);
         let mut line = Line::default();
         line.spans.push(Span::styled(
             format!("{:>address_min_width$X}", instruction.file_address),
@@ -64,6 +84,31 @@ impl App {
             .push(Span::styled(mnemonic.to_string(), mnemonic_style));
         line.spans.push(Span::raw(" "));
         line.spans.push(Span::raw(args.to_string()));
+        if let Some(reference) = instruction.instruction.reference() {
+            line.spans.push(Span::styled(
+                format!(" -> {:#X}", reference),
+                color_settings.assembly_virtual_address,
+            ));
+            if let Some(symbol) = symbol_table
+                .and_then(|symbol_table| symbol_table.get(&reference))
+                .filter(|symbol| !args.contains(symbol.as_str()))
+            {
+                line.spans.push(Span::styled(
+                    format!(" <{}>", symbol),
+                    color_settings.assembly_symbol,
+                ));
+            }
+            // the content of an import slot is filled by the loader, the name says more
+            let is_import_slot = header.is_import_slot(reference);
+            if let Some(preview) =
+                data_preview(bytes, header, reference).filter(|_| !is_import_slot)
+            {
+                line.spans.push(Span::styled(
+                    format!(" {}", preview),
+                    color_settings.assembly_comment,
+                ));
+            }
+        }
         if let Some(symbol_table) = symbol_table {
             if let Some(symbol) = symbol_table.get(&instruction.instruction.ip()) {
                 line.spans.push(Span::raw(" "));
@@ -71,6 +116,17 @@ impl App {
                     format!("<{}>", symbol),
                     color_settings.assembly_symbol,
                 ));
+                let xref_count = xrefs.count(instruction.instruction.ip());
+                if xref_count > 0 {
+                    line.spans.push(Span::styled(
+                        format!(
+                            " ({} xref{})",
+                            xref_count,
+                            if xref_count > 1 { "s" } else { "" }
+                        ),
+                        color_settings.assembly_virtual_address,
+                    ));
+                }
             }
         }
         if instruction.instruction.ip() == header.entry_point() {
@@ -84,135 +140,285 @@ impl App {
             format!(" @{:X}", instruction.instruction.ip()),
             color_settings.assembly_virtual_address,
         ));
+        if let Some(comment) = annotations.comment(instruction.file_address) {
+            line.spans.push(Span::styled(
+                format!(" ; {}", comment),
+                color_settings.assembly_comment,
+            ));
+        }
 
         line
     }
 
+    /// The line shown before the first instruction or after the last instruction of a function.
+    pub(in crate::app) fn function_separator_to_line(
+        color_settings: &ColorSettings,
+        function: &Function,
+        is_start: bool,
+        xrefs: &XrefIndex,
+        address_min_width: usize,
+    ) -> Line<'static> {
+        let mut line = Line::default();
+        if is_start {
+            line.spans.push(Span::styled(
+                format!("{:>address_min_width$X}", function.file_address),
+                color_settings.assembly_address,
+            ));
+            line.spans.push(Span::raw(" "));
+            line.spans.push(Span::styled(
+                format!("┌─ {} ({}B)", function.name, function.size),
+                color_settings.assembly_symbol,
+            ));
+            let xref_count = xrefs.count(function.virtual_address);
+            if xref_count > 0 {
+                line.spans.push(Span::styled(
+                    format!(
+                        " ({} xref{})",
+                        xref_count,
+                        if xref_count > 1 { "s" } else { "" }
+                    ),
+                    color_settings.assembly_virtual_address,
+                ));
+            }
+            line.spans.push(Span::styled(
+                format!(" @{:X}", function.virtual_address),
+                color_settings.assembly_virtual_address,
+            ));
+        } else {
+            line.spans
+                .push(Span::raw(" ".repeat(address_min_width + 1)));
+            line.spans.push(Span::styled(
+                format!("└─ end of {}", function.name),
+                color_settings.assembly_symbol,
+            ));
+        }
+        line
+    }
+
+    /// Disassembles every code range of the file. Unless `linear_sweep` is set, only the instructions
+    /// reachable from the entry point, the symbols and the start of the code ranges are decoded,
+    /// the other bytes are shown as data directives.
+    /// The references found in the code and in the data sections are collected in the returned index.
     pub(in crate::app) fn sections_from_bytes(
         bytes: &[u8],
         header: &Header,
-    ) -> (Vec<usize>, Vec<AssemblyLine>) {
+        symbols: Option<&HashMap<u64, String>>,
+        marks: &[MarkedRange],
+        linear_sweep: bool,
+        syntax: X86Syntax,
+    ) -> (Vec<usize>, Vec<AssemblyLine>, XrefIndex) {
         let mut line_offsets = vec![0; bytes.len()];
         let mut lines = Vec::new();
-        let mut sections = header.get_sections();
-        if sections.is_empty() {
-            sections.push(Section {
-                name: ".text".to_string(),
-                virtual_address: 0,
-                file_offset: 0,
-                size: bytes.len() as u64,
-            });
-        }
+        let mut xrefs = XrefIndex::default();
+        xrefs.add_pointers(bytes, header, 0, bytes.len());
 
-        let mut current_byte = 0;
-        for section in sections {
-            if section.file_offset > current_byte as u64 {
-                lines.push(AssemblyLine::SectionTag(SectionTag {
-                    name: "Unknown".to_string(),
-                    file_address: current_byte as u64,
-                    virtual_address: 0,
-                    size: section.file_offset as usize - current_byte,
-                }));
-                for _ in 0..section.file_offset as usize - current_byte {
-                    line_offsets[current_byte] = lines.len() - 1;
-                    current_byte += 1;
-                }
-            }
-            // if there are any overlapping sections, this should fix it
-            current_byte = section.file_offset as usize;
-            match section.name.as_str() {
-                ".text" | "__text" => {
-                    lines.push(AssemblyLine::SectionTag(SectionTag {
-                        name: section.name.clone(),
-                        file_address: section.file_offset,
-                        virtual_address: section.virtual_address,
-                        size: section.size as usize,
-                    }));
-                    let (offsets, instructions) = Self::assembly_from_section(
-                        bytes,
-                        header,
-                        section.virtual_address as usize,
-                        current_byte,
-                        section.size as usize,
-                        lines.len(),
-                    );
-                    line_offsets.splice(
-                        section.file_offset as usize
-                            ..section.file_offset as usize + section.size as usize,
-                        offsets,
-                    );
-                    lines.extend(instructions);
-                    current_byte += section.size as usize;
-                }
-                name => {
-                    lines.push(AssemblyLine::SectionTag(SectionTag {
-                        name: name.to_string(),
-                        file_address: section.file_offset,
-                        virtual_address: section.virtual_address,
-                        size: section.size as usize,
-                    }));
-                    for _ in 0..section.size as usize {
-                        line_offsets[current_byte] = lines.len() - 1;
-                        current_byte += 1;
-                    }
-                }
-            }
-        }
-        if current_byte < bytes.len() {
+        let regions = CodeRegion::from_file(bytes.len(), header, marks);
+        let mut reachable = if linear_sweep {
+            None
+        } else {
+            let code_ranges = regions
+                .iter()
+                .flat_map(|region| {
+                    region
+                        .ranges
+                        .iter()
+                        .filter(|(_, _, kind)| *kind == RangeKind::Code)
+                        .map(|(start, end, _)| CodeRange {
+                            start: *start,
+                            end: *end,
+                            virtual_address: region.virtual_address_of(*start),
+                        })
+                })
+                .collect::<Vec<_>>();
+            Some(recursive_descent(
+                bytes,
+                header,
+                syntax,
+                symbols,
+                &code_ranges,
+            ))
+        };
+
+        for region in regions {
             lines.push(AssemblyLine::SectionTag(SectionTag {
-                name: "Unknown".to_string(),
-                file_address: current_byte as u64,
-                virtual_address: 0,
-                size: bytes.len() - current_byte,
+                name: region.name.clone(),
+                file_address: region.start as u64,
+                virtual_address: region.virtual_address,
+                size: region.end - region.start,
+                attributes: region.attributes.clone(),
             }));
-            let initial_current_byte = current_byte;
-            for _ in initial_current_byte..bytes.len() {
-                line_offsets[current_byte] = lines.len() - 1;
-                current_byte += 1;
+            for (i, (start, end, kind)) in region.ranges.iter().copied().enumerate() {
+                match kind {
+                    RangeKind::Code => {
+                        let (offsets, mut instructions) = match &mut reachable {
+                            Some(reachable) => Self::assembly_from_reachable(
+                                bytes,
+                                reachable,
+                                region.virtual_address_of(start),
+                                start,
+                                end,
+                                lines.len(),
+                                &mut xrefs,
+                            ),
+                            None => Self::assembly_from_section(
+                                bytes,
+                                header,
+                                syntax,
+                                symbols,
+                                region.virtual_address_of(start) as usize,
+                                start,
+                                end - start,
+                                lines.len(),
+                                &mut xrefs,
+                            ),
+                        };
+                        resolve_page_offsets(&mut instructions, &mut xrefs);
+                        line_offsets.splice(start..end, offsets);
+                        lines.extend(instructions);
+                    }
+                    RangeKind::Data => {
+                        // the data at the start of the region belongs to the section tag
+                        if i > 0 {
+                            lines.push(AssemblyLine::SectionTag(SectionTag {
+                                name: format!("{} ({})", region.name, kind),
+                                file_address: start as u64,
+                                virtual_address: region.virtual_address_of(start),
+                                size: end - start,
+                                attributes: None,
+                            }));
+                        }
+                        line_offsets[start..end].fill(lines.len() - 1);
+                    }
+                }
             }
         }
 
-        (line_offsets, lines)
+        (line_offsets, lines, xrefs)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(in crate::app) fn assembly_from_section(
         bytes: &[u8],
         header: &Header,
+        syntax: X86Syntax,
+        symbols: Option<&HashMap<u64, String>>,
         starting_ip: usize,
         starting_file_address: usize,
         section_size: usize,
         starting_sections: usize,
+        xrefs: &mut XrefIndex,
     ) -> (Vec<usize>, Vec<AssemblyLine>) {
         let mut line_offsets = vec![0; section_size];
         let mut instructions = Vec::new();
         let mut current_byte = 0;
-        let decoder = header.get_decoder().expect("Failed to create decoder");
-        let decoded = decoder
-            .disasm_all(
-                &bytes[starting_file_address..starting_file_address + section_size],
-                starting_ip as u64,
-            )
-            .expect("Failed to disassemble");
-        for instruction in decoded.iter() {
-            let instruction_tag = InstructionTag {
-                instruction: Instruction::new(instruction, header.get_symbols()),
-                file_address: current_byte as u64 + starting_file_address as u64,
+        let mut decoders = Decoders::new(header, syntax, true);
+        let starting_ip = starting_ip as u64;
+        // ARM and Thumb code is decoded separately
+        for (start, end, mode) in
+            header.cpu_mode_ranges(starting_ip, starting_ip + section_size as u64)
+        {
+            let file_address = starting_file_address + (start - starting_ip) as usize;
+            // without a decoder for the architecture the bytes are shown as data
+            let Ok(decoder) = decoders.get_for_mode(mode) else {
+                for offset in 0..(end - start) as usize {
+                    instructions.push(AssemblyLine::Instruction(InstructionTag {
+                        instruction: Instruction::data(
+                            start + offset as u64,
+                            &bytes[file_address + offset..file_address + offset + 1],
+                        ),
+                        file_address: current_byte as u64 + starting_file_address as u64,
+                    }));
+                    line_offsets[current_byte] = starting_sections + instructions.len() - 1;
+                    current_byte += 1;
+                }
+                continue;
             };
-            instructions.push(AssemblyLine::Instruction(instruction_tag));
-            for _ in 0..instruction.len() {
-                line_offsets[current_byte] = starting_sections + instructions.len() - 1;
-                current_byte += 1;
+            let decoded = decoder
+                .disasm_all(
+                    &bytes[file_address..file_address + (end - start) as usize],
+                    start,
+                )
+                .expect("Failed to disassemble");
+            for instruction in decoded.iter() {
+                let flow = Flow::of(decoder, instruction);
+                let instruction_tag = InstructionTag {
+                    instruction: Instruction::new(instruction, symbols)
+                        .with_reference(flow.memory_reference),
+                    file_address: current_byte as u64 + starting_file_address as u64,
+                };
+                xrefs.add_flow(instruction_tag.file_address, instruction.address(), &flow);
+                instructions.push(AssemblyLine::Instruction(instruction_tag));
+                for _ in 0..instruction.len() {
+                    line_offsets[current_byte] = starting_sections + instructions.len() - 1;
+                    current_byte += 1;
+                }
             }
         }
         (line_offsets, instructions)
     }
 
+    /// Lays out the instructions found by the recursive descent between `start` and `end`,
+    /// every byte that is not part of any instruction is shown as a data directive.
+    fn assembly_from_reachable(
+        bytes: &[u8],
+        reachable: &mut HashMap<usize, (Instruction, Flow)>,
+        starting_ip: u64,
+        start: usize,
+        end: usize,
+        starting_sections: usize,
+        xrefs: &mut XrefIndex,
+    ) -> (Vec<usize>, Vec<AssemblyLine>) {
+        let mut line_offsets = Vec::with_capacity(end - start);
+        let mut lines = Vec::new();
+        let mut current_byte = start;
+        while current_byte < end {
+            let instruction = match reachable.remove(&current_byte) {
+                Some((instruction, flow)) if current_byte + instruction.len() <= end => {
+                    xrefs.add_flow(current_byte as u64, instruction.ip(), &flow);
+                    instruction
+                }
+                _ => Instruction::data(
+                    starting_ip + (current_byte - start) as u64,
+                    &bytes[current_byte..current_byte + 1],
+                ),
+            };
+            let len = instruction.len();
+            lines.push(AssemblyLine::Instruction(InstructionTag {
+                instruction,
+                file_address: current_byte as u64,
+            }));
+            line_offsets.extend(std::iter::repeat_n(
+                starting_sections + lines.len() - 1,
+                len,
+            ));
+            current_byte += len;
+        }
+        (line_offsets, lines)
+    }
+
+    /// Assembles `assembly` at `starting_virtual_address`, the labels and the symbols of the file
+    /// can be used as addresses.
     pub(in crate::app) fn bytes_from_assembly(
         &self,
         assembly: &str,
         starting_virtual_address: u64,
     ) -> Result<Vec<u8>, String> {
-        let bytes = assemble(assembly, starting_virtual_address, &self.header);
+        let assembly = resolve_names(assembly, &self.header, |name| {
+            self.annotations
+                .label_address(name)
+                .map(|address| {
+                    self.header
+                        .physical_to_virtual_address(address)
+                        .unwrap_or(address)
+                })
+                .or_else(|| self.header.symbol_to_address(name))
+        });
+        let bytes = assemble(
+            &assembly,
+            starting_virtual_address,
+            &self.header,
+            self.settings.app.x86_syntax,
+        );
         match bytes {
             Ok(bytes) => Ok(bytes),
             Err(e) => Err(e.to_string()),
@@ -242,30 +448,171 @@ impl App {
             app_context.offset = offset;
             self.plugin_manager.on_edit(&mut bytes, &mut app_context);
 
+            let len = bytes.len();
+            let relocations = self.relocations_overwritten(offset as u64, len as u64);
+            if !relocations.is_empty() {
+                self.log(
+                    NotificationLevel::Warning,
+                    &format!(
+                        "The patch overwrites bytes rewritten by the loader: {}.",
+                        relocations.join(", ")
+                    ),
+                );
+            }
             let modified_bytes = self.data.push_change(offset, bytes);
 
-            self.edit_assembly(modified_bytes + instruction_offset);
+            self.update_after_edit(offset, len, modified_bytes + instruction_offset);
+        }
+    }
+
+    /// Names the relocations that a patch of `len` bytes at `file_address` would overwrite,
+    /// by symbol or by virtual address.
+    pub(in crate::app) fn relocations_overwritten(
+        &self,
+        file_address: u64,
+        len: u64,
+    ) -> Vec<String> {
+        self.header
+            .relocations_in(file_address, len)
+            .map(|relocation| match &relocation.symbol {
+                Some(symbol) => symbol.clone(),
+                None => format!("{:#X}", relocation.virtual_address),
+            })
+            .collect()
+    }
+
+    /// The file address and the virtual address where a patch starts: the start of the current
+    /// instruction, or the cursor outside of the code.
+    fn patch_start(&self) -> Option<(u64, u64)> {
+        match self.get_current_instruction()? {
+            AssemblyLine::Instruction(instruction) => {
+                Some((instruction.file_address, instruction.instruction.ip()))
+            }
+            AssemblyLine::SectionTag(_) => {
+                let file_address = self.get_cursor_position().global_byte_index as u64;
+                let virtual_address = self
+                    .header
+                    .physical_to_virtual_address(file_address)
+                    .unwrap_or(file_address);
+                Some((file_address, virtual_address))
+            }
         }
     }
 
+    /// Assembles a patch at the current instruction. If `fill_with_nops` is set, the patch is padded
+    /// with NOPs up to the end of the last instruction that it overwrites.
+    pub(in crate::app) fn assemble_patch(&self, assembly: &str) -> Result<Vec<u8>, String> {
+        let Some((file_address, virtual_address)) = self.patch_start() else {
+            return Ok(Vec::new());
+        };
+        let mut bytes = self.bytes_from_assembly(assembly, virtual_address)?;
+        if self.settings.app.fill_with_nops && !bytes.is_empty() {
+            let end = file_address + bytes.len() as u64;
+            let boundary = self.instruction_boundary(end);
+            if boundary > end {
+                let nop = self.bytes_from_assembly("nop", virtual_address + bytes.len() as u64)?;
+                if !nop.is_empty() {
+                    bytes.extend(nop.repeat((boundary - end) as usize / nop.len()));
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// The end of the instruction that contains the byte before `end`,
+    /// `end` if that byte is not in an instruction.
+    fn instruction_boundary(&self, end: u64) -> u64 {
+        if end == 0 || end as usize > self.assembly_offsets.len() {
+            return end;
+        }
+        match self.get_instruction_at(end as usize - 1) {
+            AssemblyLine::Instruction(instruction) if !instruction.instruction.is_data() => {
+                end.max(instruction.file_address + instruction.instruction.len() as u64)
+            }
+            _ => end,
+        }
+    }
+
+    /// Counts the instructions after the one at `file_address` that are overwritten,
+    /// even partially, by `len` bytes.
+    pub(in crate::app) fn instructions_overwritten(&self, file_address: u64, len: u64) -> usize {
+        let end = ((file_address + len) as usize).min(self.assembly_offsets.len());
+        if len == 0 || file_address as usize >= end {
+            return 0;
+        }
+        let first = self.assembly_offsets[file_address as usize];
+        let last = self.assembly_offsets[end - 1];
+        self.assembly_instructions[first + 1..=last.max(first)]
+            .iter()
+            .filter(|line| matches!(line, AssemblyLine::Instruction(_)))
+            .count()
+    }
+
     pub(in crate::app) fn patch(&mut self, assembly: &str) {
-        if let Some(current_instruction) = self.get_current_instruction() {
-            let current_virtual_address =
-                if let AssemblyLine::Instruction(instruction) = current_instruction {
-                    instruction.instruction.ip()
-                } else {
-                    self.get_cursor_position().global_byte_index as u64
-                };
-            let bytes = self.bytes_from_assembly(assembly, current_virtual_address);
-            match bytes {
-                Ok(bytes) => self.patch_bytes(&bytes, true),
-                Err(e) => {
-                    self.log(NotificationLevel::Error, &e);
+        let Some((file_address, _)) = self.patch_start() else {
+            return;
+        };
+        match self.assemble_patch(assembly) {
+            Ok(bytes) => {
+                let overwritten = self.instructions_overwritten(file_address, bytes.len() as u64);
+                if overwritten > 0 {
+                    self.log(
+                        NotificationLevel::Warning,
+                        &format!(
+                            "The patch overwrites {} following instruction{}.",
+                            overwritten,
+                            if overwritten > 1 { "s" } else { "" }
+                        ),
+                    );
                 }
+                self.patch_bytes(&bytes, true);
             }
+            Err(e) => {
+                self.log(NotificationLevel::Error, &e);
+            }
+        }
+    }
+
+    /// Returns the rows of the assembly view that show the assembly line at `index`,
+    /// a function separator can come before or after the line.
+    pub(in crate::app) fn get_assembly_rows_of_line(&self, index: usize) -> Vec<AssemblyViewRow> {
+        let mut rows = Vec::with_capacity(1);
+        let line = &self.assembly_instructions[index];
+        let is_instruction = matches!(line, AssemblyLine::Instruction(_));
+        if is_instruction && self.functions.starting_at(line.file_address()).is_some() {
+            rows.push(AssemblyViewRow::FunctionStart(index));
+        }
+        rows.push(AssemblyViewRow::Line(index));
+        if is_instruction
+            && self
+                .functions
+                .ending_at(line.file_address() + line.len() as u64)
+                .is_some()
+        {
+            rows.push(AssemblyViewRow::FunctionEnd(index));
         }
+        rows
     }
 
+    /// Returns at most `count` rows of the assembly view starting from the assembly line `start`.
+    pub(in crate::app) fn get_assembly_view_rows(
+        &self,
+        start: usize,
+        count: usize,
+    ) -> Vec<AssemblyViewRow> {
+        let mut rows = Vec::with_capacity(count);
+        for index in start..self.assembly_instructions.len() {
+            if rows.len() >= count {
+                break;
+            }
+            rows.extend(self.get_assembly_rows_of_line(index));
+        }
+        rows.truncate(count);
+        rows
+    }
+
+    /// Returns the index of the first assembly line shown in the assembly view,
+    /// the line at the cursor is kept at the center of the view.
     pub(in crate::app) fn get_assembly_view_scroll(&self) -> usize {
         let cursor_position = self.get_cursor_position();
         let current_ip = cursor_position
@@ -273,14 +620,32 @@ impl App {
             .min(self.assembly_offsets.len() - 1);
         let current_scroll = self.assembly_offsets[current_ip];
 
-        let visible_lines = self.screen_size.1 - self.vertical_margin;
+        let visible_lines = (self.screen_size.1 - self.vertical_margin) as usize;
         let center_of_view = visible_lines / 2;
-        let view_scroll = (current_scroll as isize - center_of_view as isize).clamp(
-            0,
-            (self.assembly_instructions.len() as isize - visible_lines as isize).max(0),
-        );
+        let mut view_scroll = current_scroll;
+        let mut rows_before = self
+            .get_assembly_rows_of_line(current_scroll)
+            .iter()
+            .position(|row| matches!(row, AssemblyViewRow::Line(_)))
+            .unwrap_or_default();
+        while view_scroll > 0 {
+            let rows = self.get_assembly_rows_of_line(view_scroll - 1).len();
+            if rows_before + rows > center_of_view {
+                break;
+            }
+            rows_before += rows;
+            view_scroll -= 1;
+        }
+        // the view is filled up to the last line
+        let mut rows_after = self
+            .get_assembly_view_rows(view_scroll, visible_lines)
+            .len();
+        while view_scroll > 0 && rows_after < visible_lines {
+            view_scroll -= 1;
+            rows_after += self.get_assembly_rows_of_line(view_scroll).len();
+        }
 
-        view_scroll as usize
+        view_scroll
     }
 
     pub(in crate::app) fn get_current_instruction(&self) -> Option<&AssemblyLine> {
@@ -302,24 +667,30 @@ impl App {
         if let Some(current_instruction) = current_instruction {
             let from_byte = current_instruction.file_address() as usize;
             let virtual_address = current_instruction.virtual_address();
-            let text_section = self.header.get_text_section();
-            let (is_inside_text_section, maximum_code_byte) =
-                if let Some(text_section) = text_section {
-                    (
-                        from_byte >= text_section.file_offset as usize
-                            && from_byte
-                                < text_section.file_offset as usize + text_section.size as usize,
-                        text_section.file_offset as usize + text_section.size as usize,
-                    )
-                } else {
-                    (true, self.data.len())
-                };
-            if !is_inside_text_section {
+            let maximum_code_byte = match self.get_code_range(from_byte) {
+                Some((_, end)) => end,
+                None => return,
+            };
+            // the data directives found by the recursive descent are not decoded again
+            let maximum_code_byte = if self.settings.app.linear_sweep {
+                maximum_code_byte
+            } else if current_instruction.is_data() {
+                self.edit_data_directives(from_byte, modifyied_bytes);
                 return;
-            }
-            let decoder = self.header.get_decoder().expect("Failed to create decoder");
+            } else {
+                self.assembly_instructions[self.assembly_offsets[from_byte]..]
+                    .iter()
+                    .find(|line| {
+                        line.is_data() || line.file_address() as usize >= maximum_code_byte
+                    })
+                    .map(|line| line.file_address() as usize)
+                    .unwrap_or(maximum_code_byte)
+                    .min(maximum_code_byte)
+            };
+            let mut decoders = Decoders::new(&self.header, self.settings.app.x86_syntax, true);
             let mut offsets = Vec::new();
             let mut instructions = Vec::new();
+            let mut flows = Vec::new();
             let mut to_byte = self.data.len();
 
             let from_instruction = self.assembly_offsets[from_byte];
@@ -332,6 +703,9 @@ impl App {
                     break;
                 }
                 let bytes = &self.data.bytes()[current_byte..maximum_code_byte];
+                let Ok(decoder) = decoders.get(virtual_address + ip_offset) else {
+                    break;
+                };
                 let decoded = decoder
                     .disasm_count(bytes, virtual_address + ip_offset, 1)
                     .expect("Failed to disassemble");
@@ -341,8 +715,13 @@ impl App {
                 let instruction = decoded.iter().next().unwrap();
                 ip_offset += instruction.len() as u64;
                 let old_instruction = self.get_instruction_at(current_byte);
+                let flow = Flow::of(decoder, instruction);
                 let instruction_tag = InstructionTag {
-                    instruction: Instruction::new(instruction, self.header.get_symbols()),
+                    instruction: Instruction::new(
+                        instruction,
+                        self.annotations.symbols(&self.header),
+                    )
+                    .with_reference(flow.memory_reference),
                     file_address: current_byte as u64,
                 };
                 let new_assembly_line = AssemblyLine::Instruction(instruction_tag.clone());
@@ -353,6 +732,7 @@ impl App {
                     break;
                 }
                 instructions.push(new_assembly_line);
+                flows.push((current_byte as u64, instruction.address(), flow));
                 for _ in 0..instruction.len() {
                     offsets.push(from_instruction + instructions.len() - 1);
                     current_byte += 1;
@@ -381,6 +761,13 @@ impl App {
 
             let delta = new_instruction_count as isize - original_instruction_count as isize;
 
+            self.xrefs
+                .remove_from(from_byte, to_byte, |kind| kind != XrefKind::Pointer);
+            for (file_address, virtual_address, flow) in flows.iter() {
+                self.xrefs.add_flow(*file_address, *virtual_address, flow);
+            }
+            resolve_page_offsets(&mut instructions, &mut self.xrefs);
+
             self.assembly_offsets.splice(from_byte..to_byte, offsets);
             if delta != 0 {
                 for offset in self.assembly_offsets.iter_mut().skip(to_byte) {
@@ -417,10 +804,368 @@ impl App {
 
             self.assembly_instructions
                 .splice(from_instruction..to_instruction, instructions);
+            self.detect_functions_in_section(from_instruction);
+        }
+    }
+
+    /// Finds the functions again in the section of the line `line`, after it was edited.
+    fn detect_functions_in_section(&mut self, line: usize) {
+        let is_section = |line: &AssemblyLine| matches!(line, AssemblyLine::SectionTag(_));
+        let start = self.assembly_instructions[..=line]
+            .iter()
+            .rposition(is_section)
+            .unwrap_or(0);
+        let end = self.assembly_instructions[line..]
+            .iter()
+            .position(is_section)
+            .map_or(self.assembly_instructions.len(), |end| line + end);
+        let functions = FunctionIndex::detect(
+            &self.header,
+            self.annotations.symbols(&self.header),
+            &self.assembly_instructions[start..end],
+            &self.xrefs,
+        );
+        let start_file_address = self.assembly_instructions[start].file_address();
+        let end_file_address = self
+            .assembly_instructions
+            .get(end)
+            .map_or(self.data.len() as u64, |line| line.file_address());
+        self.functions
+            .replace_range(start_file_address, end_file_address, functions);
+    }
+
+    /// Finds the functions again from the current disassembly.
+    pub(in crate::app) fn detect_functions(&mut self) {
+        self.functions = FunctionIndex::detect(
+            &self.header,
+            self.annotations.symbols(&self.header),
+            &self.assembly_instructions,
+            &self.xrefs,
+        );
+    }
+
+    /// Returns the virtual address of the instruction at the cursor,
+    /// or the virtual address of the cursor if it is not on an instruction.
+    pub(in crate::app) fn get_cursor_virtual_address(&self) -> u64 {
+        match self.get_current_instruction() {
+            Some(AssemblyLine::Instruction(instruction)) => instruction.instruction.ip(),
+            _ => {
+                let offset = self.get_cursor_position().global_byte_index as u64;
+                self.header
+                    .physical_to_virtual_address(offset)
+                    .unwrap_or(offset)
+            }
+        }
+    }
+
+    /// Jumps to the target of the branch or call at the cursor,
+    /// or to the address of its memory operand.
+    pub(in crate::app) fn follow_instruction(&mut self) {
+        let Some(AssemblyLine::Instruction(instruction)) = self.get_current_instruction() else {
+            self.log(
+                NotificationLevel::Warning,
+                "There is no instruction to follow.",
+            );
+            return;
+        };
+        let start = instruction.file_address as usize;
+        let end = start + instruction.instruction.len();
+        let virtual_address = instruction.instruction.ip();
+        // the syntax doesn't change the flow of the instructions
+        let mut decoders = Decoders::new(&self.header, X86Syntax::default(), false);
+        let flow = match decoders.get(virtual_address) {
+            Ok(decoder) => decoder
+                .disasm_count(&self.data.bytes()[start..end], virtual_address, 1)
+                .ok()
+                .and_then(|decoded| {
+                    decoded
+                        .iter()
+                        .next()
+                        .map(|decoded| Flow::of(decoder, decoded))
+                }),
+            Err(e) => {
+                self.log(
+                    NotificationLevel::Error,
+                    &format!("Failed to create decoder: {}", e),
+                );
+                return;
+            }
+        };
+        match flow.and_then(|flow| flow.target.or(flow.memory_reference)) {
+            Some(target) => {
+                self.log(
+                    NotificationLevel::Debug,
+                    &format!("Following to virtual address: {:#X}", target),
+                );
+                // without a header the code is disassembled at the file offsets
+                let is_virtual = !matches!(self.header, Header::None);
+                self.jump_to(target as usize, is_virtual);
+            }
+            None => self.log(
+                NotificationLevel::Warning,
+                "The current instruction has no target to follow.",
+            ),
+        }
+    }
+
+    pub(in crate::app) fn find_functions(&self, filter: &str) -> Vec<Function> {
+        let mut functions = self.functions.functions().to_vec();
+        if !filter.is_empty() {
+            fuzzy_search_in_place(filter, &mut functions);
+        }
+        functions
+    }
+
+    pub(in crate::app) fn request_popup_functions(&mut self) {
+        if self.functions.is_empty() {
+            self.log(NotificationLevel::Warning, "No functions found.");
+        } else {
+            self.popup = Some(PopupState::Functions {
+                filter: String::new(),
+                cursor: 0,
+                results: self.find_functions(""),
+                scroll: 0,
+            });
         }
     }
 
+    pub(in crate::app) fn request_popup_xrefs(&mut self) {
+        let address = self.get_cursor_virtual_address();
+        if self.xrefs.count(address) == 0 {
+            self.log(
+                NotificationLevel::Info,
+                &format!("No references to {:#X}.", address),
+            );
+        } else {
+            self.popup = Some(PopupState::Xrefs { address, scroll: 0 });
+        }
+    }
+
+    /// Returns the file address of the instruction at the cursor,
+    /// or the file address of the cursor if it is not on an instruction.
+    pub(in crate::app) fn get_cursor_annotation_address(&self) -> u64 {
+        match self.get_current_instruction() {
+            Some(AssemblyLine::Instruction(instruction)) => instruction.file_address,
+            _ => self.get_cursor_position().global_byte_index as u64,
+        }
+    }
+
+    pub(in crate::app) fn request_popup_label(&mut self) {
+        let address = self.get_cursor_annotation_address();
+        let label = self
+            .annotations
+            .label(address)
+            .unwrap_or_default()
+            .to_string();
+        self.popup = Some(PopupState::Label {
+            address,
+            cursor: label.chars().count(),
+            label,
+        });
+    }
+
+    pub(in crate::app) fn request_popup_comment(&mut self) {
+        let address = self.get_cursor_annotation_address();
+        let comment = self
+            .annotations
+            .comment(address)
+            .unwrap_or_default()
+            .to_string();
+        self.popup = Some(PopupState::Comment {
+            address,
+            cursor: comment.chars().count(),
+            comment,
+        });
+    }
+
+    /// Sets the label at the file `address`, an empty label removes it.
+    /// The instructions that refer to the address are decoded again to show the label in the operands.
+    pub(in crate::app) fn set_label(&mut self, address: u64, label: &str) {
+        if let Err(e) = self.annotations.set_label(address, label) {
+            self.log(NotificationLevel::Error, &e);
+            return;
+        }
+        self.project_dirty = true;
+        self.annotations.update_symbols(&self.header);
+
+        let virtual_address = Annotations::virtual_address(&self.header, address);
+        let symbols = self.annotations.symbols(&self.header);
+        let mut decoders = Decoders::new(&self.header, self.settings.app.x86_syntax, false);
+        for xref in self.xrefs.get(virtual_address) {
+            if xref.kind == XrefKind::Pointer {
+                continue;
+            }
+            let Some(AssemblyLine::Instruction(tag)) = self
+                .assembly_offsets
+                .get(xref.file_address as usize)
+                .and_then(|line| self.assembly_instructions.get_mut(*line))
+            else {
+                continue;
+            };
+            let start = tag.file_address as usize;
+            let bytes = &self.data.bytes()[start..start + tag.instruction.len()];
+            let Ok(decoder) = decoders.get(tag.instruction.ip()) else {
+                continue;
+            };
+            if let Some(instruction) = decoder
+                .disasm_count(bytes, tag.instruction.ip(), 1)
+                .ok()
+                .and_then(|decoded| {
+                    decoded
+                        .iter()
+                        .next()
+                        .map(|instruction| Instruction::new(instruction, symbols))
+                })
+            {
+                tag.instruction = instruction.with_reference(tag.instruction.reference());
+            }
+        }
+        let name = symbols
+            .and_then(|symbols| symbols.get(&virtual_address))
+            .cloned()
+            .unwrap_or_else(|| format!("sub_{:X}", virtual_address));
+        self.functions.rename(address, name);
+    }
+
+    /// Sets the comment at the file `address`, an empty comment removes it.
+    pub(in crate::app) fn set_comment(&mut self, address: u64, comment: &str) {
+        self.annotations.set_comment(address, comment);
+        self.project_dirty = true;
+    }
+
+    /// Shows the new bytes of the data directives that contain the `len` bytes from `offset`.
+    fn edit_data_directives(&mut self, offset: usize, len: usize) {
+        let first = self.assembly_offsets[offset];
+        for line in self.assembly_instructions[first..].iter_mut() {
+            match line {
+                AssemblyLine::Instruction(tag)
+                    if tag.instruction.is_data() && (tag.file_address as usize) < offset + len =>
+                {
+                    let start = tag.file_address as usize;
+                    tag.instruction = Instruction::data(
+                        tag.instruction.ip(),
+                        &self.data.bytes()[start..start + tag.instruction.len()],
+                    );
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Returns the start and the end of the code range that contains `offset`, if any.
+    pub(in crate::app) fn get_code_range(&self, offset: usize) -> Option<(usize, usize)> {
+        CodeRegion::from_file(self.data.len(), &self.header, &self.marked_ranges)
+            .into_iter()
+            .flat_map(|region| region.ranges)
+            .find(|(start, end, kind)| {
+                *kind == RangeKind::Code && *start <= offset && offset < *end
+            })
+            .map(|(start, end, _)| (start, end))
+    }
+
+    /// Disassembles the whole file again.
+    pub(in crate::app) fn disassemble(&mut self) {
+        (
+            self.assembly_offsets,
+            self.assembly_instructions,
+            self.xrefs,
+        ) = Self::sections_from_bytes(
+            self.data.bytes(),
+            &self.header,
+            self.annotations.symbols(&self.header),
+            &self.marked_ranges,
+            self.settings.app.linear_sweep,
+            self.settings.app.x86_syntax,
+        );
+        self.detect_functions();
+    }
+
+    /// Parses the header again and disassembles the file with the new header.
+    /// The cursor, the scroll and the history are left untouched.
+    pub(in crate::app) fn reparse_header(&mut self) {
+        self.header = self.parse_header();
+        self.header_ranges = self.header.get_structural_ranges(self.data.bytes());
+        self.annotations.update_symbols(&self.header);
+        self.disassemble();
+    }
+
+    /// Marks `size` bytes from `start` as code or data and disassembles the file again.
+    /// `size` can be either decimal or hexadecimal with the `0x` prefix.
+    pub(in crate::app) fn mark_range(&mut self, start: usize, size: &str, kind: RangeKind) {
+        let size = size.trim();
+        let parsed = match size.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16),
+            None => size.parse::<usize>(),
+        };
+        let size = match parsed {
+            Ok(size) if size > 0 => size,
+            _ => {
+                self.log(NotificationLevel::Error, &format!("Invalid size: {}", size));
+                return;
+            }
+        };
+        let end = start.saturating_add(size).min(self.data.len());
+        self.marked_ranges.push(MarkedRange { start, end, kind });
+        self.project_dirty = true;
+        self.disassemble();
+        self.log(
+            NotificationLevel::Info,
+            &format!("Marked {:#X}..{:#X} as {}", start, end, kind),
+        );
+    }
+
+    /// Removes the code and data marks that contain `offset` and disassembles the file again.
+    pub(in crate::app) fn unmark_range(&mut self, offset: usize) {
+        let count = self.marked_ranges.len();
+        self.marked_ranges
+            .retain(|mark| !(mark.start <= offset && offset < mark.end));
+        if self.marked_ranges.len() == count {
+            self.log(NotificationLevel::Warning, "No marks at the cursor.");
+        } else {
+            self.project_dirty = true;
+            self.disassemble();
+        }
+    }
+
+    pub(in crate::app) fn is_header_range(&self, offset: usize, len: usize) -> bool {
+        let first = self
+            .header_ranges
+            .partition_point(|(start, size)| start + size <= offset);
+        self.header_ranges
+            .get(first)
+            .is_some_and(|(start, _)| *start < offset + len.max(1))
+    }
+
+    /// Updates the header and the disassembly after `len` bytes were changed at `offset`.
+    /// If the change touches the header, the whole file is parsed again,
+    /// otherwise only the instructions that were modified are disassembled again.
+    pub(in crate::app) fn update_after_edit(
+        &mut self,
+        offset: usize,
+        len: usize,
+        modified_bytes: usize,
+    ) {
+        if self.is_header_range(offset, len) {
+            self.reparse_header();
+        } else {
+            self.edit_assembly(modified_bytes);
+            self.xrefs
+                .update_pointers(self.data.bytes(), &self.header, offset, len);
+        }
+    }
+
+    /// Parses the header of the file, if there is none the target chosen by the user is used.
     pub(in crate::app) fn parse_header(&mut self) -> Header {
+        match self.parse_file_header() {
+            Header::None => match &self.target {
+                Some(target) => Header::CustomHeader(target.to_header(self.data.len())),
+                None => Header::None,
+            },
+            header => header,
+        }
+    }
+
+    fn parse_file_header(&mut self) -> Header {
         let mut app_context = get_app_context!(self);
         match self.plugin_manager.try_parse_header(&mut app_context) {
             Some(header) => Header::CustomHeader(header),
@@ -429,12 +1174,50 @@ impl App {
             }
         }
     }
+
+    pub(in crate::app) fn request_popup_target(&mut self) {
+        let target = self
+            .target
+            .map(|target| target.to_string())
+            .unwrap_or_default();
+        self.popup = Some(PopupState::Target {
+            cursor: target.chars().count(),
+            target,
+        });
+    }
+
+    /// Sets the target used for files without a header and disassembles the file again,
+    /// an empty string removes it.
+    pub(in crate::app) fn set_target(&mut self, target: &str) {
+        let target = if target.trim().is_empty() {
+            None
+        } else {
+            match target.parse::<Target>() {
+                Ok(target) => Some(target),
+                Err(e) => {
+                    self.log(NotificationLevel::Error, &e);
+                    return;
+                }
+            }
+        };
+        self.target = target;
+        self.project_dirty = true;
+        self.reparse_header();
+        if self.target.is_some() && self.parse_file_header() != Header::None {
+            self.log(
+                NotificationLevel::Warning,
+                "The file has a header, the architecture is taken from it.",
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::vec;
 
+    use object::Architecture;
+
     use super::*;
     #[test]
     fn test_assembly_line() {
@@ -447,6 +1230,7 @@ mod test {
                 operands: "rax, rbx".to_string(),
                 virtual_address,
                 bytes: vec![0x48, 0x89, 0xd8],
+                reference: None,
             },
             file_address,
         });
@@ -454,6 +1238,9 @@ mod test {
             &ColorSettings::get_default_dark_theme(),
             0,
             &Header::None,
+            &[],
+            &Annotations::default(),
+            &XrefIndex::default(),
             0,
         );
 
@@ -488,17 +1275,26 @@ mod test {
             file_address,
             virtual_address,
             size: section_size,
+            attributes: Some("r-x code".to_string()),
         });
 
         let line = al.to_line(
             &ColorSettings::get_default_dark_theme(),
             0,
             &Header::None,
+            &[],
+            &Annotations::default(),
+            &XrefIndex::default(),
             0,
         );
 
         let contains_section_name = line.spans.iter().any(|span| span.content.contains(".text"));
         assert!(contains_section_name);
+        let contains_attributes = line
+            .spans
+            .iter()
+            .any(|span| span.content.contains("r-x code"));
+        assert!(contains_attributes);
         let contains_virtual_address = line
             .spans
             .iter()
@@ -625,4 +1421,343 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_executable_sections() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let app = App::mockup(data);
+        for address in [0x18000, 0x18020, 0x1ac554] {
+            assert!(
+                matches!(
+                    app.get_instruction_at(address),
+                    AssemblyLine::Instruction(_)
+                ),
+                "{:#X} should be disassembled",
+                address
+            );
+        }
+        assert!(matches!(
+            app.get_instruction_at(0x1ac570),
+            AssemblyLine::SectionTag(_)
+        ));
+    }
+
+    #[test]
+    fn test_analysis_syntax() {
+        let mut app = App::mockup(std::fs::read("test/elf.bin").unwrap());
+        let analysis = |app: &App| {
+            let code = app
+                .assembly_instructions
+                .iter()
+                .map(|line| (line.file_address(), line.is_data()))
+                .collect::<Vec<_>>();
+            (code, app.xrefs.clone(), app.functions.functions().to_vec())
+        };
+        let intel = analysis(&app);
+        assert!(intel.1.targets(XrefKind::Call).count() > 0);
+        // the branches and the prologues are found in every syntax
+        for syntax in [X86Syntax::Masm, X86Syntax::Att] {
+            app.settings.app.x86_syntax = syntax;
+            app.disassemble();
+            let analysis = analysis(&app);
+            assert!(analysis.0 == intel.0, "{:?}", syntax);
+            assert!(analysis.1 == intel.1, "{:?}", syntax);
+            assert!(analysis.2 == intel.2, "{:?}", syntax);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_architecture() {
+        let mut data = std::fs::read("test/elf.bin").unwrap();
+        // e_machine = EM_AVR, Capstone has no AVR decoder
+        data[0x12..0x14].copy_from_slice(&0x53u16.to_le_bytes());
+        let app = App::mockup(data);
+        assert_eq!(app.header.architecture(), Architecture::Avr);
+        for address in [0x18000, 0x18020, 0x1ac554] {
+            assert!(
+                app.get_instruction_at(address).is_data(),
+                "{:#X} should be shown as data",
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn test_mark_range() {
+        let mut app = App::mockup(vec![0x90; 8]);
+        app.mark_range(2, "0x3", RangeKind::Data);
+        assert!(matches!(
+            app.get_instruction_at(1),
+            AssemblyLine::Instruction(_)
+        ));
+        match app.get_instruction_at(3) {
+            AssemblyLine::SectionTag(tag) => {
+                assert_eq!((tag.file_address, tag.size), (2, 3));
+            }
+            _ => panic!("Expected a section tag."),
+        }
+        assert_eq!(app.get_code_range(6), Some((5, 8)));
+        assert_eq!(app.get_code_range(3), None);
+
+        app.mark_range(3, "1", RangeKind::Code);
+        assert!(matches!(
+            app.get_instruction_at(3),
+            AssemblyLine::Instruction(_)
+        ));
+        app.mark_range(0, "zero", RangeKind::Code);
+        assert_eq!(app.marked_ranges.len(), 2);
+
+        app.unmark_range(2);
+        assert_eq!(app.marked_ranges.len(), 1);
+        app.unmark_range(3);
+        assert!(app.marked_ranges.is_empty());
+        assert_eq!(app.get_code_range(3), Some((0, 8)));
+    }
+
+    #[test]
+    fn test_recursive_descent_data() {
+        // jmp +2; 2 bytes of data; nop
+        let mut app = App::mockup(vec![0xEB, 0x02, 0x48, 0x89, 0x90]);
+        assert!(!app.get_instruction_at(0).is_data());
+        assert!(app.get_instruction_at(2).is_data());
+        assert!(app.get_instruction_at(3).is_data());
+        assert!(!app.get_instruction_at(4).is_data());
+
+        // editing the data doesn't turn it into code
+        app.jump_to(2, false);
+        app.patch_bytes(&[0x90], false);
+        match app.get_instruction_at(2) {
+            AssemblyLine::Instruction(tag) => {
+                assert_eq!(tag.instruction.to_string(), ".byte 0x90")
+            }
+            _ => panic!("Expected a data directive."),
+        }
+
+        // editing the code doesn't decode the data that follows it
+        app.jump_to(0, false);
+        app.patch("nop; nop");
+        assert!(app.get_instruction_at(2).is_data());
+
+        app.settings.app.linear_sweep = true;
+        app.disassemble();
+        assert!(!app.get_instruction_at(2).is_data());
+    }
+
+    #[test]
+    fn test_xrefs() {
+        // call 0xA; jmp 0xA; nop; nop; nop; ret
+        let mut app = App::mockup(vec![
+            0xE8, 0x05, 0x00, 0x00, 0x00, 0xEB, 0x03, 0x90, 0x90, 0x90, 0xC3,
+        ]);
+        let xrefs = app.xrefs.get(0xA);
+        assert_eq!(xrefs.len(), 2);
+        assert_eq!(xrefs[0].kind, XrefKind::Call);
+        assert_eq!(xrefs[1].kind, XrefKind::Jump);
+        assert_eq!(xrefs[1].file_address, 5);
+
+        app.jump_to(0xA, false);
+        app.request_popup_xrefs();
+        assert!(matches!(
+            app.popup,
+            Some(PopupState::Xrefs { address: 0xA, .. })
+        ));
+
+        // patching the jump removes its reference
+        app.popup = None;
+        app.jump_to(5, false);
+        app.patch("nop; nop");
+        assert_eq!(app.xrefs.count(0xA), 1);
+    }
+
+    #[test]
+    fn test_pc_relative_reference() {
+        // lea rax, [rip + 0x10]; nop...
+        let mut bytes = vec![0x48, 0x8D, 0x05, 0x10, 0x00, 0x00, 0x00];
+        bytes.extend([0x90; 0x19]);
+        let mut app = App::mockup(bytes);
+        app.set_label(0x17, "value");
+        let line = app.assembly_instructions[app.assembly_offsets[0]]
+            .to_line(
+                &app.settings.color,
+                0,
+                &app.header,
+                app.data.bytes(),
+                &app.annotations,
+                &app.xrefs,
+                1,
+            )
+            .to_string();
+        assert!(line.contains("lea rax, [rip + 0x10] -> 0x17 <value>"));
+    }
+
+    #[test]
+    fn test_follow() {
+        // call 0xA; jmp 0xA; nop; nop; nop; ret
+        let mut app = App::mockup(vec![
+            0xE8, 0x05, 0x00, 0x00, 0x00, 0xEB, 0x03, 0x90, 0x90, 0x90, 0xC3,
+        ]);
+        app.jump_to(5, false);
+        app.follow_instruction();
+        assert_eq!(app.get_cursor_position().global_byte_index, 0xA);
+        app.navigate_back();
+        assert_eq!(app.get_cursor_position().global_byte_index, 5);
+        app.navigate_back();
+        assert_eq!(app.get_cursor_position().global_byte_index, 0);
+        app.navigate_forward();
+        app.navigate_forward();
+        assert_eq!(app.get_cursor_position().global_byte_index, 0xA);
+
+        // an instruction without a target doesn't move the cursor
+        app.follow_instruction();
+        assert_eq!(app.get_cursor_position().global_byte_index, 0xA);
+    }
+
+    #[test]
+    fn test_target() {
+        // nop; bx lr in Thumb
+        let mut app = App::mockup(vec![0x00, 0xBF, 0x70, 0x47]);
+        app.set_target("arm thumb little 0x8000");
+        let AssemblyLine::Instruction(instruction) = app.get_instruction_at(2) else {
+            panic!("The target should be disassembled");
+        };
+        assert_eq!(instruction.instruction.to_string().trim(), "bx lr");
+        assert_eq!(instruction.instruction.ip(), 0x8002);
+        app.jump_to(0, false);
+        app.patch("nop");
+        assert_eq!(app.data.bytes()[0..2], [0x00, 0xBF]);
+        assert!(matches!(app.header, Header::CustomHeader(_)));
+
+        // jr $ra in big endian MIPS
+        app.data.push_change(0, vec![0x03, 0xE0, 0x00, 0x08]);
+        app.set_target("mips big");
+        let AssemblyLine::Instruction(instruction) = app.get_instruction_at(0) else {
+            panic!("The target should be disassembled");
+        };
+        assert_eq!(instruction.instruction.to_string().trim(), "jr $ra");
+
+        app.set_target("");
+        assert_eq!(app.header, Header::None);
+        app.set_target("avr");
+        assert_eq!(app.header, Header::None);
+    }
+
+    #[test]
+    fn test_read_only_assembly() {
+        // nop; rts in M68k, that can't be assembled
+        let mut app = App::mockup(vec![0x4E, 0x71, 0x4E, 0x75]);
+        app.set_target("m68k");
+        let text = |app: &App, offset: usize| match app.get_instruction_at(offset) {
+            AssemblyLine::Instruction(tag) => tag.instruction.to_string().trim().to_string(),
+            AssemblyLine::SectionTag(_) => String::new(),
+        };
+        assert_eq!(text(&app, 0), "nop");
+        assert_eq!(text(&app, 2), "rts");
+
+        app.request_popup_patch();
+        assert!(matches!(
+            &app.popup,
+            Some(PopupState::Patch { preview: Err(reason), .. }) if reason.contains("read-only")
+        ));
+        app.popup = None;
+        app.jump_to(0, false);
+        app.patch("rts");
+        assert_eq!(app.data.bytes()[0..2], [0x4E, 0x71]);
+
+        // the bytes can still be edited
+        app.patch_bytes(&[0x4E, 0x75], true);
+        assert_eq!(text(&app, 0), "rts");
+    }
+
+    #[test]
+    fn test_arm_thumb() {
+        // _start: mov r0, #0; bx lr
+        // thumb: movs r0, #1; bx lr
+        for path in ["test/elf_arm_thumb.bin", "test/elf_arm_thumb_nomap.bin"] {
+            for linear_sweep in [false, true] {
+                let mut app = App::mockup(std::fs::read(path).unwrap());
+                app.settings.app.linear_sweep = linear_sweep;
+                app.disassemble();
+                let text = |app: &App, offset: usize| match app.get_instruction_at(offset) {
+                    AssemblyLine::Instruction(tag) => {
+                        tag.instruction.to_string().trim().to_string()
+                    }
+                    AssemblyLine::SectionTag(_) => String::new(),
+                };
+                assert_eq!(text(&app, 0x60), "mov r0, #0", "{}", path);
+                assert_eq!(text(&app, 0x64), "bx lr", "{}", path);
+                assert_eq!(text(&app, 0x68), "movs r0, #1", "{}", path);
+                assert_eq!(text(&app, 0x6A), "bx lr", "{}", path);
+
+                // the patch is assembled in the mode of the instruction it replaces
+                app.jump_to(0x68, false);
+                app.patch("movs r0, #2");
+                assert_eq!(app.data.bytes()[0x68..0x6A], [0x02, 0x20]);
+                assert_eq!(text(&app, 0x68), "movs r0, #2");
+                assert_eq!(text(&app, 0x6A), "bx lr");
+                app.jump_to(0x60, false);
+                app.patch("mov r0, #2");
+                assert_eq!(app.data.bytes()[0x60..0x64], [0x02, 0x00, 0xA0, 0xE3]);
+                assert_eq!(text(&app, 0x64), "bx lr");
+            }
+        }
+    }
+
+    #[test]
+    fn test_patch_relocation() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let mut app = App::mockup(data);
+        // the first entry of .init_array is rewritten by the loader
+        assert_eq!(app.relocations_overwritten(0x22BB0C, 2), vec!["0x22CB08"]);
+        assert!(app.relocations_overwritten(0x22BB00, 8).is_empty());
+        app.jump_to(0x22BB0C, false);
+        let preview = app
+            .get_patch_preview(&app.settings.color, &Ok(vec![0x90, 0x90]))
+            .to_string();
+        assert!(preview.contains("Relocated: 0x22CB08"));
+        app.patch_bytes(&[0x90, 0x90], false);
+        assert_eq!(app.data.bytes()[0x22BB0C..0x22BB0E], [0x90, 0x90]);
+        let last = &app.logger[app.logger.len() - 1];
+        assert_eq!(last.level, NotificationLevel::Warning);
+        assert!(last.message.contains("0x22CB08"));
+    }
+
+    #[test]
+    fn test_multiline_patch() {
+        // mov eax, 1; mov ebx, 2; ret
+        let code = vec![0xB8, 1, 0, 0, 0, 0xBB, 2, 0, 0, 0, 0xC3];
+        let mut app = App::mockup(code.clone());
+        let text = |app: &App, offset: usize| match app.get_instruction_at(offset) {
+            AssemblyLine::Instruction(tag) => tag.instruction.to_string().trim().to_string(),
+            AssemblyLine::SectionTag(_) => String::new(),
+        };
+
+        // the stale bytes of the old instruction are left without padding
+        app.jump_to(0, false);
+        app.patch("xor eax, eax");
+        assert_eq!(app.data.bytes()[0..5], [0x31, 0xC0, 0, 0, 0]);
+        app.undo();
+        app.settings.app.fill_with_nops = true;
+        app.patch("xor eax, eax");
+        assert_eq!(app.data.bytes()[0..5], [0x31, 0xC0, 0x90, 0x90, 0x90]);
+        assert_eq!(text(&app, 5), "mov ebx, 2");
+        app.undo();
+
+        // local labels, and the labels of the file
+        app.patch("jmp 1f\n1: nop");
+        assert_eq!(app.data.bytes()[0..5], [0xEB, 0x00, 0x90, 0x90, 0x90]);
+        app.undo();
+        app.annotations.set_label(0xA, "done").unwrap();
+        app.patch("jmp done");
+        assert_eq!(app.data.bytes()[0..5], [0xEB, 0x08, 0x90, 0x90, 0x90]);
+        app.undo();
+        assert_eq!(app.data.bytes(), code.as_slice());
+
+        // a longer patch is padded to the end of the last instruction that it overwrites
+        let logs = app.logger.len();
+        assert_eq!(app.instructions_overwritten(0, 6), 1);
+        app.patch("mov eax, 1\ninc eax");
+        assert_eq!(app.data.bytes()[5..10], [0xFF, 0xC0, 0x90, 0x90, 0x90]);
+        assert_eq!(text(&app, 10), "ret");
+        assert!(app.logger[logs].message.contains("1 following instruction"));
+    }
 }
@@ -5,7 +5,29 @@ use crate::{
     headers::Header,
 };
 
-use super::{instruction_tag::InstructionTag, section_tag::SectionTag};
+use super::{
+    annotation::Annotations, instruction_tag::InstructionTag, section_tag::SectionTag,
+    xref::XrefIndex,
+};
+
+/// A row of the assembly view, the separators of the functions are not assembly lines.
+/// Every variant holds the index of an assembly line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssemblyViewRow {
+    Line(usize),
+    FunctionStart(usize),
+    FunctionEnd(usize),
+}
+
+impl AssemblyViewRow {
+    pub fn line_index(&self) -> usize {
+        match self {
+            AssemblyViewRow::Line(index)
+            | AssemblyViewRow::FunctionStart(index)
+            | AssemblyViewRow::FunctionEnd(index) => *index,
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AssemblyLine {
@@ -35,6 +57,14 @@ impl AssemblyLine {
         }
     }
 
+    /// Returns true if the line is a data directive.
+    pub fn is_data(&self) -> bool {
+        match self {
+            AssemblyLine::Instruction(instruction) => instruction.instruction.is_data(),
+            AssemblyLine::SectionTag(_) => false,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             AssemblyLine::Instruction(instruction) => instruction.instruction.is_empty(),
@@ -42,11 +72,15 @@ impl AssemblyLine {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn to_line(
         &self,
         color_settings: &ColorSettings,
         current_byte_index: usize,
         header: &Header,
+        bytes: &[u8],
+        annotations: &Annotations,
+        xrefs: &XrefIndex,
         address_min_width: usize,
     ) -> Line {
         match self {
@@ -59,6 +93,9 @@ impl AssemblyLine {
                     instruction,
                     selected,
                     header,
+                    bytes,
+                    annotations,
+                    xrefs,
                     address_min_width,
                 )
             }
@@ -80,6 +117,12 @@ impl AssemblyLine {
                     format!("[{} ({}B)]", section.name, section.size),
                     color_settings.assembly_section,
                 ));
+                if let Some(attributes) = &section.attributes {
+                    line.spans.push(Span::styled(
+                        format!(" {}", attributes),
+                        color_settings.assembly_section,
+                    ));
+                }
                 line.spans.push(Span::styled(
                     format!(" @{:X}", section.virtual_address),
                     color_settings.assembly_virtual_address,
@@ -0,0 +1,220 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::headers::{section::Section, Header};
+
+/// How the bytes of a range are shown in the assembly view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RangeKind {
+    Code,
+    Data,
+}
+
+impl Display for RangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangeKind::Code => write!(f, "code"),
+            RangeKind::Data => write!(f, "data"),
+        }
+    }
+}
+
+/// A range of bytes that was marked as code or data by the user.
+/// It takes precedence over the permissions of the sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkedRange {
+    pub start: usize,
+    pub end: usize,
+    pub kind: RangeKind,
+}
+
+/// A section, or a gap between sections, split into code and data ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeRegion {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub virtual_address: u64,
+    /// The attributes of the section, `None` for the gaps between sections.
+    pub attributes: Option<String>,
+    /// Sorted ranges that cover the whole region, adjacent ranges have different kinds.
+    pub ranges: Vec<(usize, usize, RangeKind)>,
+}
+
+impl CodeRegion {
+    fn new(
+        name: String,
+        attributes: Option<String>,
+        start: usize,
+        end: usize,
+        virtual_address: u64,
+        kind: RangeKind,
+        marks: &[MarkedRange],
+    ) -> Self {
+        let mut boundaries = vec![start, end];
+        for mark in marks {
+            for boundary in [mark.start, mark.end] {
+                if boundary > start && boundary < end {
+                    boundaries.push(boundary);
+                }
+            }
+        }
+        boundaries.sort();
+        boundaries.dedup();
+
+        let mut ranges: Vec<(usize, usize, RangeKind)> = Vec::new();
+        for window in boundaries.windows(2) {
+            let (range_start, range_end) = (window[0], window[1]);
+            let range_kind = marks
+                .iter()
+                .rev()
+                .find(|mark| mark.start <= range_start && range_end <= mark.end)
+                .map(|mark| mark.kind)
+                .unwrap_or(kind);
+            match ranges.last_mut() {
+                Some((_, last_end, last_kind)) if *last_kind == range_kind => {
+                    *last_end = range_end;
+                }
+                _ => ranges.push((range_start, range_end, range_kind)),
+            }
+        }
+        Self {
+            name,
+            attributes,
+            start,
+            end,
+            virtual_address,
+            ranges,
+        }
+    }
+
+    pub fn virtual_address_of(&self, offset: usize) -> u64 {
+        self.virtual_address + (offset - self.start) as u64
+    }
+
+    /// Splits a file of `len` bytes into regions. Executable sections are code,
+    /// everything else is data unless it is marked otherwise in `marks`.
+    /// Later marks take precedence over earlier ones. The segments replace the stripped
+    /// section headers, a file without either is code.
+    pub fn from_file(len: usize, header: &Header, marks: &[MarkedRange]) -> Vec<CodeRegion> {
+        let mut sections = header.get_sections_or_segments();
+        if sections.is_empty() {
+            sections.push(Section::new(".text".to_string(), 0, 0, len as u64));
+        }
+        sections.sort_by_key(|section| section.file_offset);
+
+        let mut regions = Vec::new();
+        let push_gap = |regions: &mut Vec<CodeRegion>, start: usize, end: usize| {
+            let virtual_address = header
+                .physical_to_virtual_address(start as u64)
+                .unwrap_or(start as u64);
+            regions.push(CodeRegion::new(
+                "Unknown".to_string(),
+                None,
+                start,
+                end,
+                virtual_address,
+                RangeKind::Data,
+                marks,
+            ));
+        };
+        let mut current_byte = 0;
+        for section in sections {
+            let section_start = section.file_offset as usize;
+            let section_end = (section_start + section.size as usize).min(len);
+            if section_start > current_byte {
+                push_gap(&mut regions, current_byte, section_start.min(len));
+            }
+            // overlapping sections are clipped to the end of the previous one
+            let start = section_start.max(current_byte);
+            if start >= section_end {
+                continue;
+            }
+            let kind = if section.permissions.execute {
+                RangeKind::Code
+            } else {
+                RangeKind::Data
+            };
+            let attributes = Some(section.attributes());
+            regions.push(CodeRegion::new(
+                section.name,
+                attributes,
+                start,
+                section_end,
+                section.virtual_address + (start - section_start) as u64,
+                kind,
+                marks,
+            ));
+            current_byte = section_end;
+        }
+        if current_byte < len {
+            push_gap(&mut regions, current_byte, len);
+        }
+        regions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::files::filesystem::FileSystem;
+
+    use super::*;
+
+    #[test]
+    fn test_regions() {
+        let marks = [
+            MarkedRange {
+                start: 0x10,
+                end: 0x20,
+                kind: RangeKind::Data,
+            },
+            MarkedRange {
+                start: 0x18,
+                end: 0x1C,
+                kind: RangeKind::Code,
+            },
+        ];
+        let regions = CodeRegion::from_file(0x40, &Header::None, &marks);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(
+            regions[0].ranges,
+            vec![
+                (0, 0x10, RangeKind::Code),
+                (0x10, 0x18, RangeKind::Data),
+                (0x18, 0x1C, RangeKind::Code),
+                (0x1C, 0x20, RangeKind::Data),
+                (0x20, 0x40, RangeKind::Code),
+            ]
+        );
+        assert_eq!(regions[0].virtual_address_of(0x18), 0x18);
+    }
+
+    #[test]
+    fn test_regions_without_sections() {
+        let mut data = std::fs::read("test/elf.bin").unwrap();
+        // e_shoff, e_shnum and e_shstrndx of the ELF header
+        data[0x28..0x30].fill(0);
+        data[0x3C..0x40].fill(0);
+        let header = Header::parse_header(&data, "./elf.bin", &FileSystem::new_local(".").unwrap());
+        assert!(header.get_sections().is_empty());
+        let regions = CodeRegion::from_file(data.len(), &header, &[]);
+        let region_at = |offset: usize| {
+            regions
+                .iter()
+                .find(|region| region.start <= offset && offset < region.end)
+                .unwrap()
+        };
+        // the ELF header is mapped by the first segment, that is not executable
+        assert_eq!(region_at(0).name, "LOAD0");
+        assert_eq!(
+            region_at(0).ranges,
+            vec![(0, region_at(0).end, RangeKind::Data)]
+        );
+        let code = region_at(0x18000);
+        assert_eq!(code.name, "LOAD1");
+        assert_eq!(code.ranges[0].2, RangeKind::Code);
+        assert_eq!(code.virtual_address_of(0x18000), 0x18000);
+        assert_eq!(region_at(0x22BB08).virtual_address_of(0x22BB08), 0x22CB08);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{app::settings::app_settings::X86Syntax, headers::Header};
+
+use super::{assembly_line::AssemblyLine, decoders::Decoders, flow::Flow, function::Function};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Unconditional,
+    /// The branch of a conditional jump that is taken.
+    True,
+    /// The branch of a conditional jump that falls through.
+    False,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    /// The index of the destination block.
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// A sequence of instructions that is only entered from the first one and only left from the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The indices of the assembly lines of the block.
+    pub lines: Range<usize>,
+    pub file_address: u64,
+    pub virtual_address: u64,
+    pub size: u64,
+    pub edges: Vec<Edge>,
+}
+
+impl BasicBlock {
+    pub fn contains(&self, file_address: u64) -> bool {
+        self.file_address <= file_address && file_address < self.file_address + self.size
+    }
+}
+
+/// The basic blocks of a function sorted by address, data directives are not part of any block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl ControlFlowGraph {
+    /// Splits the instructions of `function` into basic blocks, `offsets` maps every byte
+    /// of the file to its assembly line. Indirect branches have no edges.
+    pub fn build(
+        function: &Function,
+        lines: &[AssemblyLine],
+        offsets: &[usize],
+        bytes: &[u8],
+        header: &Header,
+    ) -> Self {
+        let Some(first) = offsets.get(function.file_address as usize).copied() else {
+            return Self::default();
+        };
+        let end = function.end_file_address();
+        let last = lines[first..]
+            .iter()
+            .position(|line| {
+                line.file_address() >= end || matches!(line, AssemblyLine::SectionTag(_))
+            })
+            .map_or(lines.len(), |count| first + count);
+        // the syntax doesn't change the flow of the instructions
+        let mut decoders = Decoders::new(header, X86Syntax::default(), false);
+        if decoders.get(function.virtual_address).is_err() {
+            return Self::default();
+        }
+
+        // the flow of every instruction, `None` for the data directives
+        let flows = lines[first..last]
+            .iter()
+            .map(|line| match line {
+                AssemblyLine::Instruction(instruction) if !instruction.instruction.is_data() => {
+                    let start = instruction.file_address as usize;
+                    let decoder = decoders.get(instruction.instruction.ip()).ok()?;
+                    let flow = decoder
+                        .disasm_count(
+                            &bytes[start..start + instruction.instruction.len()],
+                            instruction.instruction.ip(),
+                            1,
+                        )
+                        .ok()
+                        .and_then(|decoded| {
+                            decoded
+                                .iter()
+                                .next()
+                                .map(|decoded| Flow::of(decoder, decoded))
+                        });
+                    Some(flow.unwrap_or(Flow {
+                        falls_through: true,
+                        ..Default::default()
+                    }))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let line_of_address = (first..last)
+            .filter(|index| flows[index - first].is_some())
+            .map(|index| (lines[index].virtual_address(), index))
+            .collect::<HashMap<_, _>>();
+        let target_line = |flow: &Flow| {
+            flow.target
+                .and_then(|target| line_of_address.get(&target).copied())
+        };
+
+        let mut leaders = vec![false; last - first];
+        for (i, flow) in flows.iter().enumerate() {
+            match flow {
+                Some(flow) => {
+                    if flow.is_jump {
+                        if let Some(target) = target_line(flow) {
+                            leaders[target - first] = true;
+                        }
+                    }
+                    if (flow.is_jump || flow.is_return || !flow.falls_through)
+                        && i + 1 < leaders.len()
+                    {
+                        leaders[i + 1] = true;
+                    }
+                }
+                None if i + 1 < leaders.len() => leaders[i + 1] = true,
+                None => {}
+            }
+        }
+
+        let mut blocks: Vec<BasicBlock> = Vec::new();
+        let mut block_of_line = HashMap::new();
+        for (i, flow) in flows.iter().enumerate() {
+            if flow.is_none() {
+                continue;
+            }
+            let index = first + i;
+            let line = &lines[index];
+            match blocks.last_mut() {
+                Some(block) if !leaders[i] && block.lines.end == index => {
+                    block.lines.end = index + 1;
+                    block.size += line.len() as u64;
+                }
+                _ => {
+                    block_of_line.insert(index, blocks.len());
+                    blocks.push(BasicBlock {
+                        lines: index..index + 1,
+                        file_address: line.file_address(),
+                        virtual_address: line.virtual_address(),
+                        size: line.len() as u64,
+                        edges: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        for block in blocks.iter_mut() {
+            let last_line = block.lines.end - 1;
+            let Some(flow) = flows[last_line - first] else {
+                continue;
+            };
+            let next = block_of_line.get(&(last_line + 1)).copied();
+            let mut edges = Vec::new();
+            if flow.is_jump {
+                if let Some(to) = target_line(&flow).and_then(|line| block_of_line.get(&line)) {
+                    edges.push(Edge {
+                        to: *to,
+                        kind: if flow.falls_through {
+                            EdgeKind::True
+                        } else {
+                            EdgeKind::Unconditional
+                        },
+                    });
+                }
+                if let (true, Some(next)) = (flow.falls_through, next) {
+                    edges.push(Edge {
+                        to: next,
+                        kind: EdgeKind::False,
+                    });
+                }
+            } else if let (true, false, Some(next)) = (flow.falls_through, flow.is_return, next) {
+                edges.push(Edge {
+                    to: next,
+                    kind: EdgeKind::Unconditional,
+                });
+            }
+            block.edges = edges;
+        }
+        Self { blocks }
+    }
+
+    /// Returns the index of the block that contains the byte at `file_address`.
+    pub fn block_containing(&self, file_address: u64) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.contains(file_address))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::App;
+
+    use super::*;
+
+    #[test]
+    fn test_control_flow_graph() {
+        // call f; ret
+        // f: push rbp; mov rbp, rsp; test eax, eax; je 0x12; inc eax; jmp 0x14; dec eax; pop rbp; ret
+        let app = App::mockup(vec![
+            0xE8, 0x01, 0x00, 0x00, 0x00, 0xC3, 0x55, 0x48, 0x89, 0xE5, 0x85, 0xC0, 0x74, 0x04,
+            0xFF, 0xC0, 0xEB, 0x02, 0xFF, 0xC8, 0x5D, 0xC3,
+        ]);
+        let function = app.functions.starting_at(6).unwrap();
+        let graph = ControlFlowGraph::build(
+            function,
+            &app.assembly_instructions,
+            &app.assembly_offsets,
+            app.data.bytes(),
+            &app.header,
+        );
+        let blocks = graph
+            .blocks
+            .iter()
+            .map(|block| (block.file_address, block.size))
+            .collect::<Vec<_>>();
+        assert_eq!(blocks, vec![(6, 8), (14, 4), (18, 2), (20, 2)]);
+        assert_eq!(
+            graph.blocks[0].edges,
+            vec![
+                Edge {
+                    to: 2,
+                    kind: EdgeKind::True
+                },
+                Edge {
+                    to: 1,
+                    kind: EdgeKind::False
+                }
+            ]
+        );
+        assert_eq!(
+            graph.blocks[1].edges,
+            vec![Edge {
+                to: 3,
+                kind: EdgeKind::Unconditional
+            }]
+        );
+        assert_eq!(
+            graph.blocks[2].edges,
+            vec![Edge {
+                to: 3,
+                kind: EdgeKind::Unconditional
+            }]
+        );
+        assert!(graph.blocks[3].edges.is_empty());
+        assert_eq!(graph.block_containing(19), Some(2));
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use capstone::{Capstone, CsResult};
+
+use crate::{
+    app::settings::app_settings::X86Syntax,
+    headers::{target::CpuMode, Header},
+};
+
+/// The decoders of the instruction sets used by a binary, every decoder is created
+/// the first time it is needed. 32-bit ARM binaries can mix ARM and Thumb code.
+pub struct Decoders<'a> {
+    header: &'a Header,
+    syntax: X86Syntax,
+    skipdata: bool,
+    decoders: HashMap<CpuMode, Capstone>,
+}
+
+impl<'a> Decoders<'a> {
+    /// With `skipdata` the bytes that are not an instruction are decoded as data directives.
+    pub fn new(header: &'a Header, syntax: X86Syntax, skipdata: bool) -> Self {
+        Self {
+            header,
+            syntax,
+            skipdata,
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Returns the decoder, with the details enabled, of the instruction set used at `virtual_address`.
+    pub fn get(&mut self, virtual_address: u64) -> CsResult<&Capstone> {
+        self.get_for_mode(self.header.cpu_mode_at(virtual_address))
+    }
+
+    pub fn get_for_mode(&mut self, mode: CpuMode) -> CsResult<&Capstone> {
+        if !self.decoders.contains_key(&mode) {
+            let mut decoder = self.header.get_decoder_for_mode(mode, self.syntax)?;
+            decoder.set_skipdata(self.skipdata)?;
+            decoder.set_detail(true)?;
+            self.decoders.insert(mode, decoder);
+        }
+        Ok(&self.decoders[&mode])
+    }
+}
@@ -0,0 +1,293 @@
+use capstone::{
+    arch::{
+        arm::{ArmInsnGroup, ArmOperandType, ArmReg},
+        arm64::Arm64OperandType,
+        x86::{X86Insn, X86OperandType, X86Reg},
+        ArchOperand,
+    },
+    Capstone, Insn, InsnGroupType, RegId, RegIdInt,
+};
+
+/// Mnemonics of the instructions that never continue to the next instruction,
+/// besides the returns. x86 instructions are matched by id, their mnemonics depend on the syntax.
+pub(super) const UNCONDITIONAL_JUMPS: &[&str] = &[
+    "jmp", "ljmp", "b", "br", "bx", "j", "jr", "ba", "bctr", "blr", "hlt", "ud2",
+];
+
+/// How an instruction changes the control flow and which addresses it refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flow {
+    pub is_jump: bool,
+    pub is_call: bool,
+    pub is_return: bool,
+    /// False if the next instruction is never executed after this one.
+    pub falls_through: bool,
+    /// The destination of a direct jump or call.
+    pub target: Option<u64>,
+    /// The address of a RIP/PC-relative memory operand.
+    pub memory_reference: Option<u64>,
+}
+
+impl Flow {
+    /// Analyzes an instruction decoded by `decoder`, which must have the details enabled.
+    pub fn of(decoder: &Capstone, instruction: &Insn) -> Self {
+        let Ok(detail) = decoder.insn_detail(instruction) else {
+            return Self {
+                falls_through: true,
+                ..Default::default()
+            };
+        };
+        let has_group = |group: InsnGroupType::Type| {
+            detail
+                .groups()
+                .iter()
+                .any(|id| id.0 as InsnGroupType::Type == group)
+        };
+        let is_jump = has_group(InsnGroupType::CS_GRP_JUMP);
+        let is_call = has_group(InsnGroupType::CS_GRP_CALL);
+        let is_return =
+            has_group(InsnGroupType::CS_GRP_RET) || has_group(InsnGroupType::CS_GRP_IRET);
+        let mnemonic = instruction.mnemonic().unwrap_or_default();
+        let operands = instruction.op_str().unwrap_or_default();
+        let arch_detail = detail.arch_detail();
+        let arch_operands = arch_detail.operands();
+        // the x86 text depends on the syntax (`call 401000h`, `jmpq *%rax`), the details don't
+        let is_x86 = arch_detail.x86().is_some();
+
+        let target = if !is_jump && !is_call {
+            None
+        } else if is_x86 {
+            arch_operands
+                .iter()
+                .rev()
+                .find_map(|operand| match operand {
+                    ArchOperand::X86Operand(operand) => match operand.op_type {
+                        X86OperandType::Imm(address) => Some(address as u64),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+        } else {
+            Self::immediate_address(operands)
+        };
+        let is_unconditional_jump = if is_x86 {
+            [
+                X86Insn::X86_INS_JMP,
+                X86Insn::X86_INS_LJMP,
+                X86Insn::X86_INS_HLT,
+                X86Insn::X86_INS_UD2,
+            ]
+            .iter()
+            .any(|id| instruction.id().0 == *id as u32)
+        } else {
+            UNCONDITIONAL_JUMPS.contains(&mnemonic)
+        };
+
+        let mut memory_reference = None;
+        // the PC of ARM code is 8 bytes ahead, the one of Thumb code 4 bytes,
+        // and it is aligned to 4 bytes when it is used as an address
+        let arm_pc = (instruction.address() & !3)
+            + if has_group(ArmInsnGroup::ARM_GRP_ARM as InsnGroupType::Type) {
+                8
+            } else {
+                4
+            };
+        // AArch64 loads from literal pools, `ldr x0, [x1], #8` is a load with a writeback
+        let is_aarch64_literal = matches!(mnemonic, "adr" | "adrp")
+            || (mnemonic.starts_with("ldr")
+                && !arch_operands.iter().any(|operand| {
+                    matches!(
+                        operand,
+                        ArchOperand::Arm64Operand(operand)
+                            if matches!(operand.op_type, Arm64OperandType::Mem(_))
+                    )
+                }));
+        // `adr r0, #0x10` and `add r0, pc, #0x10` are relative to the PC
+        let is_arm_pc_offset = mnemonic == "adr"
+            || (matches!(mnemonic, "add" | "sub")
+                && matches!(
+                    arch_operands.get(1),
+                    Some(ArchOperand::ArmOperand(operand))
+                        if operand.op_type == ArmOperandType::Reg(RegId(ArmReg::ARM_REG_PC as RegIdInt))
+                ));
+        for operand in arch_operands.iter() {
+            match operand {
+                ArchOperand::X86Operand(operand) => {
+                    if let X86OperandType::Mem(memory) = operand.op_type {
+                        let invalid = RegId(X86Reg::X86_REG_INVALID as RegIdInt);
+                        if memory.base() == RegId(X86Reg::X86_REG_RIP as RegIdInt) {
+                            memory_reference = Some(
+                                (instruction.address() + instruction.len() as u64)
+                                    .wrapping_add_signed(memory.disp()),
+                            );
+                        } else if memory.base() == invalid
+                            && memory.index() == invalid
+                            && memory.segment() == invalid
+                            && memory.disp() > 0
+                        {
+                            // absolute addresses, e.g. the IAT calls of 32-bit code: `call dword ptr [0x402000]`
+                            memory_reference = Some(memory.disp() as u64);
+                        }
+                    }
+                }
+                ArchOperand::Arm64Operand(operand) => {
+                    if let Arm64OperandType::Imm(address) = operand.op_type {
+                        if is_aarch64_literal {
+                            memory_reference = Some(address as u64);
+                        }
+                    }
+                }
+                ArchOperand::ArmOperand(operand) => match &operand.op_type {
+                    // literal loads, e.g. `ldr r0, [pc, #0x10]`
+                    ArmOperandType::Mem(memory)
+                        if memory.base() == RegId(ArmReg::ARM_REG_PC as RegIdInt)
+                            && memory.index() == RegId(ArmReg::ARM_REG_INVALID as RegIdInt) =>
+                    {
+                        let disp = memory.disp() as i64;
+                        let disp = if operand.subtracted && disp > 0 {
+                            -disp
+                        } else {
+                            disp
+                        };
+                        memory_reference = Some(arm_pc.wrapping_add_signed(disp));
+                    }
+                    ArmOperandType::Imm(offset) if is_arm_pc_offset => {
+                        let offset = *offset as i64;
+                        let offset = if (operand.subtracted || mnemonic == "sub") && offset > 0 {
+                            -offset
+                        } else {
+                            offset
+                        };
+                        memory_reference = Some(arm_pc.wrapping_add_signed(offset));
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        Self {
+            is_jump,
+            is_call,
+            is_return,
+            falls_through: !is_return && !is_unconditional_jump,
+            target,
+            memory_reference,
+        }
+    }
+
+    /// Returns the last operand if it is an immediate, e.g. `0x1000` or `#0x1000`.
+    pub fn immediate_address(operands: &str) -> Option<u64> {
+        let operand = operands.rsplit(", ").next()?.trim_start_matches('#');
+        match operand.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => operand.parse().ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use capstone::arch::{self, BuildsCapstone};
+
+    use super::*;
+
+    #[test]
+    fn test_flow() {
+        let mut decoder = Capstone::new()
+            .x86()
+            .mode(arch::x86::ArchMode::Mode64)
+            .build()
+            .unwrap();
+        decoder.set_detail(true).unwrap();
+        // jmp 0x1010; call 0x1000; lea rax, [rip + 0x10]; ret; jne 0x1000
+        let code = [
+            0xEB, 0x0E, 0xE8, 0xF9, 0xFF, 0xFF, 0xFF, 0x48, 0x8D, 0x05, 0x10, 0x00, 0x00, 0x00,
+            0xC3, 0x75, 0xEF,
+        ];
+        let decoded = decoder.disasm_all(&code, 0x1000).unwrap();
+        let flows = decoded
+            .iter()
+            .map(|instruction| Flow::of(&decoder, instruction))
+            .collect::<Vec<_>>();
+        assert!(flows[0].is_jump && !flows[0].falls_through);
+        assert_eq!(flows[0].target, Some(0x1010));
+        assert!(flows[1].is_call && flows[1].falls_through);
+        assert_eq!(flows[1].target, Some(0x1000));
+        assert_eq!(flows[2].memory_reference, Some(0x101E));
+        assert_eq!(flows[2].target, None);
+        assert!(flows[3].is_return && !flows[3].falls_through);
+        assert!(flows[4].is_jump && flows[4].falls_through);
+
+        let mut decoder = Capstone::new()
+            .x86()
+            .mode(arch::x86::ArchMode::Mode32)
+            .build()
+            .unwrap();
+        decoder.set_detail(true).unwrap();
+        // call dword ptr [0x402000]; mov eax, dword ptr fs:[0x30]
+        let code = [
+            0xFF, 0x15, 0x00, 0x20, 0x40, 0x00, 0x64, 0xA1, 0x30, 0x00, 0x00, 0x00,
+        ];
+        let decoded = decoder.disasm_all(&code, 0x401000).unwrap();
+        let flows = decoded
+            .iter()
+            .map(|instruction| Flow::of(&decoder, instruction))
+            .collect::<Vec<_>>();
+        assert!(flows[0].is_call);
+        assert_eq!(flows[0].memory_reference, Some(0x402000));
+        assert_eq!(flows[1].memory_reference, None);
+
+        let mut decoder = Capstone::new()
+            .arm64()
+            .mode(arch::arm64::ArchMode::Arm)
+            .build()
+            .unwrap();
+        decoder.set_detail(true).unwrap();
+        // adrp x0, 0x1041000; ldr x1, 0x1014; ldr x19, [sp], #0x10; ldrb w0, [x1], #1; ldr x2, [x1, #8]
+        let code = [
+            0x00, 0x82, 0x00, 0x90, 0x41, 0x00, 0x00, 0x58, 0xF3, 0x07, 0x41, 0xF8, 0x20, 0x14,
+            0x40, 0x38, 0x22, 0x04, 0x40, 0xF9,
+        ];
+        let decoded = decoder.disasm_all(&code, 0x1008).unwrap();
+        let references = decoded
+            .iter()
+            .map(|instruction| Flow::of(&decoder, instruction).memory_reference)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            references,
+            vec![Some(0x1041000), Some(0x1014), None, None, None]
+        );
+
+        let mut decoder = Capstone::new()
+            .arm()
+            .mode(arch::arm::ArchMode::Arm)
+            .build()
+            .unwrap();
+        decoder.set_detail(true).unwrap();
+        // ldr r0, [pc, #0x10]; add r1, pc, #0x20; ldr r2, [r3], #4
+        let code = [
+            0x10, 0x00, 0x9F, 0xE5, 0x20, 0x10, 0x8F, 0xE2, 0x04, 0x20, 0x93, 0xE4,
+        ];
+        let decoded = decoder.disasm_all(&code, 0x8000).unwrap();
+        let references = decoded
+            .iter()
+            .map(|instruction| Flow::of(&decoder, instruction).memory_reference)
+            .collect::<Vec<_>>();
+        assert_eq!(references, vec![Some(0x8018), Some(0x802C), None]);
+
+        let mut decoder = Capstone::new()
+            .arm()
+            .mode(arch::arm::ArchMode::Thumb)
+            .build()
+            .unwrap();
+        decoder.set_detail(true).unwrap();
+        // ldr r0, [pc, #8] at 0x8002, the PC is aligned down
+        let decoded = decoder.disasm_all(&[0x02, 0x48], 0x8002).unwrap();
+        let flow = Flow::of(&decoder, decoded.iter().next().unwrap());
+        assert_eq!(flow.memory_reference, Some(0x800C));
+
+        assert_eq!(Flow::immediate_address("x0, #0x10"), Some(0x10));
+        assert_eq!(Flow::immediate_address("qword ptr [rip + 0x10]"), None);
+    }
+}
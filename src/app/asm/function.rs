@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use object::Architecture;
+
+use crate::headers::Header;
+
+use super::{
+    assembly_line::AssemblyLine,
+    flow::UNCONDITIONAL_JUMPS,
+    xref::{XrefIndex, XrefKind},
+};
+
+/// The mnemonics of the instructions used to pad the space between functions.
+const PADDING: &[&str] = &["nop", "int3", "hlt", "ud2"];
+
+/// The AT&T syntax adds the operand size to these x86 mnemonics, e.g. `jmpq *%rax` or `nopw`.
+const SIZED_MNEMONICS: &[&str] = &["jmp", "ljmp", "ret", "nop"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub virtual_address: u64,
+    pub file_address: u64,
+    pub size: u64,
+}
+
+impl AsRef<str> for Function {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Function {
+    pub fn end_file_address(&self) -> u64 {
+        self.file_address + self.size
+    }
+}
+
+/// The functions found in the disassembly, sorted by file address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionIndex {
+    functions: Vec<Function>,
+    by_start: HashMap<u64, usize>,
+    by_end: HashMap<u64, usize>,
+}
+
+impl FunctionIndex {
+    /// Finds the functions from the symbols and the unwind tables of the header, the targets
+    /// of the calls and the usual prologues. The functions whose size is unknown end at the
+    /// start of the next function or where the code ends.
+    pub fn detect(
+        header: &Header,
+        symbols: Option<&HashMap<u64, String>>,
+        lines: &[AssemblyLine],
+        xrefs: &XrefIndex,
+    ) -> Self {
+        let mut sizes: HashMap<u64, u64> = HashMap::new();
+        for (address, size) in header.get_function_ranges() {
+            let known_size = sizes.entry(*address).or_default();
+            if *known_size == 0 {
+                *known_size = *size;
+            }
+        }
+        for address in xrefs.targets(XrefKind::Call) {
+            sizes.entry(address).or_default();
+        }
+        // without a header there is no entry point
+        if !matches!(header, Header::None) {
+            sizes.entry(header.entry_point()).or_default();
+        }
+
+        let is_x86 = matches!(
+            header.architecture(),
+            Architecture::Unknown
+                | Architecture::I386
+                | Architecture::X86_64
+                | Architecture::X86_64_X32
+        );
+        let mut line_of_address: HashMap<u64, usize> = HashMap::new();
+        let mut previous: Option<&AssemblyLine> = None;
+        for (index, line) in lines.iter().enumerate() {
+            if let AssemblyLine::Instruction(instruction) = line {
+                if !instruction.instruction.is_data() {
+                    line_of_address
+                        .entry(instruction.instruction.ip())
+                        .or_insert(index);
+                    if Self::is_after_end(previous, is_x86)
+                        && Self::is_prologue(line, lines.get(index + 1), is_x86)
+                    {
+                        sizes.entry(instruction.instruction.ip()).or_default();
+                    }
+                }
+            }
+            previous = Some(line);
+        }
+
+        let mut starts = sizes
+            .into_iter()
+            .filter_map(|(address, size)| {
+                line_of_address
+                    .get(&address)
+                    .map(|index| (*index, address, size))
+            })
+            .collect::<Vec<_>>();
+        starts.sort();
+
+        let mut index = Self::default();
+        for (i, (line_index, virtual_address, size)) in starts.iter().copied().enumerate() {
+            let next_start = starts.get(i + 1).map(|(next, _, _)| *next);
+            let size = if size > 0 {
+                size
+            } else {
+                lines[line_index..next_start.unwrap_or(lines.len())]
+                    .iter()
+                    .take_while(|line| {
+                        matches!(line, AssemblyLine::Instruction(_)) && !line.is_data()
+                    })
+                    .map(|line| line.len() as u64)
+                    .sum()
+            };
+            let name = symbols
+                .and_then(|symbols| symbols.get(&virtual_address))
+                .cloned()
+                .unwrap_or_else(|| format!("sub_{:X}", virtual_address));
+            index.push(Function {
+                name,
+                virtual_address,
+                file_address: lines[line_index].file_address(),
+                size,
+            });
+        }
+        index
+    }
+
+    /// Replaces the functions that start between the file addresses `start` and `end`
+    /// with `functions`, which must be in the same range.
+    pub fn replace_range(&mut self, start: u64, end: u64, functions: FunctionIndex) {
+        let first = self
+            .functions
+            .partition_point(|function| function.file_address < start);
+        let last = self
+            .functions
+            .partition_point(|function| function.file_address < end);
+        let mut all = std::mem::take(&mut self.functions);
+        all.splice(first..last, functions.functions);
+        *self = Self::default();
+        for function in all {
+            self.push(function);
+        }
+    }
+
+    fn push(&mut self, function: Function) {
+        self.by_start
+            .entry(function.file_address)
+            .or_insert(self.functions.len());
+        self.by_end
+            .entry(function.end_file_address())
+            .or_insert(self.functions.len());
+        self.functions.push(function);
+    }
+
+    /// Returns the mnemonic without the operand size of the AT&T syntax.
+    fn x86_mnemonic(mnemonic: &str) -> &str {
+        SIZED_MNEMONICS
+            .iter()
+            .find(|base| {
+                mnemonic
+                    .strip_prefix(**base)
+                    .is_some_and(|size| matches!(size, "b" | "w" | "l" | "q"))
+            })
+            .copied()
+            .unwrap_or(mnemonic)
+    }
+
+    /// True if a function can start after `previous`.
+    fn is_after_end(previous: Option<&AssemblyLine>, is_x86: bool) -> bool {
+        match previous {
+            Some(AssemblyLine::Instruction(instruction)) => {
+                let mnemonic = instruction.instruction.mnemonic();
+                let mnemonic = if is_x86 {
+                    Self::x86_mnemonic(mnemonic)
+                } else {
+                    mnemonic
+                };
+                instruction.instruction.is_data()
+                    || mnemonic.starts_with("ret")
+                    || UNCONDITIONAL_JUMPS.contains(&mnemonic)
+                    || PADDING.contains(&mnemonic)
+            }
+            Some(AssemblyLine::SectionTag(_)) | None => true,
+        }
+    }
+
+    fn is_prologue(line: &AssemblyLine, next: Option<&AssemblyLine>, is_x86: bool) -> bool {
+        let AssemblyLine::Instruction(instruction) = line else {
+            return false;
+        };
+        let mnemonic = instruction.instruction.mnemonic();
+        let operands = instruction.instruction.operands();
+        if is_x86 {
+            // push rbp; mov rbp, rsp, the bytes are the same in every syntax
+            let next = match next {
+                Some(AssemblyLine::Instruction(next)) => next.instruction.bytes.as_slice(),
+                _ => &[],
+            };
+            return matches!(mnemonic, "endbr64" | "endbr32")
+                || (instruction.instruction.bytes == [0x55]
+                    && matches!(
+                        next,
+                        [0x48, 0x89, 0xE5] | [0x48, 0x8B, 0xEC] | [0x89, 0xE5] | [0x8B, 0xEC]
+                    ));
+        }
+        match mnemonic {
+            "paciasp" => true,
+            // arm: push {..., lr}
+            "push" => operands.starts_with('{') && operands.contains("lr"),
+            // aarch64: stp x29, x30, [sp, #-16]!
+            "stp" => operands.starts_with("x29, x30, [sp"),
+            // mips: addiu $sp, $sp, -32
+            "addiu" | "daddiu" => operands.starts_with("$sp, $sp, -"),
+            _ => false,
+        }
+    }
+
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Function> {
+        self.functions.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    pub fn starting_at(&self, file_address: u64) -> Option<&Function> {
+        self.by_start
+            .get(&file_address)
+            .map(|index| &self.functions[*index])
+    }
+
+    /// Renames the function that starts at `file_address`, if any.
+    pub fn rename(&mut self, file_address: u64, name: String) {
+        if let Some(index) = self.by_start.get(&file_address) {
+            self.functions[*index].name = name;
+        }
+    }
+
+    pub fn ending_at(&self, file_address: u64) -> Option<&Function> {
+        self.by_end
+            .get(&file_address)
+            .map(|index| &self.functions[*index])
+    }
+
+    /// Returns the last function that starts at or before `file_address` and contains it.
+    pub fn containing(&self, file_address: u64) -> Option<&Function> {
+        let index = self
+            .functions
+            .partition_point(|function| function.file_address <= file_address);
+        self.functions[..index]
+            .iter()
+            .rev()
+            .find(|function| file_address < function.end_file_address())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::{asm::assembly_line::AssemblyViewRow, settings::app_settings::X86Syntax, App};
+
+    #[test]
+    fn test_detect_functions() {
+        // call f; ret; int3; f: push rbp; mov rbp, rsp; pop rbp; ret; g: endbr64; ret
+        let mut app = App::mockup(vec![
+            0xE8, 0x02, 0x00, 0x00, 0x00, 0xC3, 0xCC, 0x55, 0x48, 0x89, 0xE5, 0x5D, 0xC3, 0xF3,
+            0x0F, 0x1E, 0xFA, 0xC3,
+        ]);
+        let starts = |app: &App| {
+            app.functions
+                .functions()
+                .iter()
+                .map(|function| (function.file_address, function.size))
+                .collect::<Vec<_>>()
+        };
+        // g is never called, so it is not reached
+        assert_eq!(starts(&app), vec![(7, 6)]);
+
+        app.settings.app.linear_sweep = true;
+        app.disassemble();
+        // without a size the functions end where the next one starts
+        assert_eq!(starts(&app), vec![(7, 6), (13, 5)]);
+        assert_eq!(app.functions.get(0).unwrap().name, "sub_7");
+        assert_eq!(app.functions.containing(9).unwrap().file_address, 7);
+        assert_eq!(app.functions.ending_at(13).unwrap().file_address, 7);
+        assert!(app.functions.containing(18).is_none());
+
+        // the assembly view shows a separator before the first and after the last instruction
+        let first = app.assembly_offsets[7];
+        let last = app.assembly_offsets[12];
+        assert_eq!(
+            app.get_assembly_rows_of_line(first),
+            vec![
+                AssemblyViewRow::FunctionStart(first),
+                AssemblyViewRow::Line(first)
+            ]
+        );
+        assert_eq!(
+            app.get_assembly_rows_of_line(last),
+            vec![
+                AssemblyViewRow::Line(last),
+                AssemblyViewRow::FunctionEnd(last)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_prologues() {
+        // jmp rax; push rbp; mov rbp, rsp; pop rbp; ret
+        let mut app = App::mockup(vec![0xFF, 0xE0, 0x55, 0x48, 0x89, 0xE5, 0x5D, 0xC3]);
+        app.settings.app.linear_sweep = true;
+        // `jmpq *%rax`, `pushq %rbp` and `movq %rsp, %rbp` in the AT&T syntax
+        for syntax in [X86Syntax::Intel, X86Syntax::Masm, X86Syntax::Att] {
+            app.settings.app.x86_syntax = syntax;
+            app.disassemble();
+            let starts = app
+                .functions
+                .functions()
+                .iter()
+                .map(|function| function.file_address)
+                .collect::<Vec<_>>();
+            assert_eq!(starts, vec![2], "{:?}", syntax);
+        }
+    }
+
+    #[test]
+    fn test_detect_after_edit() {
+        // ret; nop; nop; nop; nop; nop; ret
+        let mut app = App::mockup(vec![0xC3, 0x90, 0x90, 0x90, 0x90, 0x90, 0xC3]);
+        app.settings.app.linear_sweep = true;
+        app.disassemble();
+        assert!(app.functions.is_empty());
+
+        app.move_cursor(2, 0, false);
+        app.patch("push rbp\nmov rbp, rsp");
+        assert_eq!(app.data.bytes()[1..5], [0x55, 0x48, 0x89, 0xE5]);
+        let starts = app
+            .functions
+            .functions()
+            .iter()
+            .map(|function| (function.file_address, function.size))
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![(1, 6)]);
+        let functions = app.functions.clone();
+        app.detect_functions();
+        assert_eq!(app.functions, functions);
+    }
+}
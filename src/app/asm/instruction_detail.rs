@@ -0,0 +1,391 @@
+use std::fmt::Display;
+
+use capstone::{
+    arch::{
+        arm::ArmOperandType, arm64::Arm64OperandType, mips::MipsOperand, ppc::PpcOperand,
+        riscv::RiscVOperand, sparc::SparcOperand, x86::X86OperandType, ArchOperand,
+    },
+    Capstone, Insn, RegAccessType, RegId,
+};
+
+use ratatui::text::{Line, Span};
+
+use crate::app::{
+    log::NotificationLevel, plugins::instruction_info::InstructionInfo,
+    popup::popup_state::PopupState, settings::color_settings::ColorSettings, App,
+};
+
+use super::{assembly_line::AssemblyLine, decoders::Decoders};
+
+/// The components of the effective address of a memory operand,
+/// `segment:[base + index * scale + displacement]`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MemoryOperand {
+    pub segment: Option<String>,
+    pub base: Option<String>,
+    pub index: Option<String>,
+    pub scale: i64,
+    pub displacement: i64,
+}
+
+impl Display for MemoryOperand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(segment) = &self.segment {
+            write!(f, "{}:", segment)?;
+        }
+        let mut terms = Vec::new();
+        if let Some(base) = &self.base {
+            terms.push(base.clone());
+        }
+        if let Some(index) = &self.index {
+            if self.scale > 1 {
+                terms.push(format!("{} * {}", index, self.scale));
+            } else {
+                terms.push(index.clone());
+            }
+        }
+        let mut address = terms.join(" + ");
+        if address.is_empty() || self.displacement > 0 {
+            if !address.is_empty() {
+                address.push_str(" + ");
+            }
+            address.push_str(&format!("{:#x}", self.displacement));
+        } else if self.displacement < 0 {
+            address.push_str(&format!(" - {:#x}", self.displacement.unsigned_abs()));
+        }
+        write!(f, "[{}]", address)
+    }
+}
+
+/// An operand as decoded by capstone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperandDetail {
+    Register(String),
+    Immediate(i64),
+    Memory(MemoryOperand),
+    /// The operands that only exist in some architectures, e.g. floating point immediates.
+    Other(String),
+}
+
+impl OperandDetail {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OperandDetail::Register(_) => "register",
+            OperandDetail::Immediate(_) => "immediate",
+            OperandDetail::Memory(_) => "memory",
+            OperandDetail::Other(_) => "other",
+        }
+    }
+}
+
+impl Display for OperandDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperandDetail::Register(register) => write!(f, "{}", register),
+            OperandDetail::Immediate(value) if *value < 0 => {
+                write!(f, "-{:#x}", value.unsigned_abs())
+            }
+            OperandDetail::Immediate(value) => write!(f, "{:#x}", value),
+            OperandDetail::Memory(memory) => write!(f, "{}", memory),
+            OperandDetail::Other(other) => write!(f, "{}", other),
+        }
+    }
+}
+
+/// The registers, groups and operands of an instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InstructionDetail {
+    pub registers_read: Vec<String>,
+    pub registers_written: Vec<String>,
+    pub groups: Vec<String>,
+    pub operands: Vec<OperandDetail>,
+}
+
+impl InstructionDetail {
+    /// Analyzes an instruction decoded by `decoder`, which must have the details enabled.
+    /// Besides the registers that are read and written implicitly, the registers of the
+    /// operands are sorted by access only for x86, where capstone knows it; the registers
+    /// used to compute an effective address are always read.
+    pub fn of(decoder: &Capstone, instruction: &Insn) -> Self {
+        let Ok(detail) = decoder.insn_detail(instruction) else {
+            return Self::default();
+        };
+        let mut read = detail.regs_read().to_vec();
+        let mut written = detail.regs_write().to_vec();
+        let name = |register: RegId| {
+            if register.0 == 0 {
+                None
+            } else {
+                decoder.reg_name(register)
+            }
+        };
+        let register =
+            |register: RegId| OperandDetail::Register(name(register).unwrap_or_default());
+        let mut address_registers = Vec::new();
+        let mut memory = |segment: RegId, base: RegId, index: RegId, scale: i64, disp: i64| {
+            address_registers.extend([segment, base, index].into_iter().filter(|r| r.0 != 0));
+            OperandDetail::Memory(MemoryOperand {
+                segment: name(segment),
+                base: name(base),
+                index: name(index),
+                scale,
+                displacement: disp,
+            })
+        };
+        let none = RegId(0);
+        let mut operands = Vec::new();
+        for operand in detail.arch_detail().operands() {
+            let operand = match operand {
+                ArchOperand::X86Operand(operand) => match operand.op_type {
+                    X86OperandType::Reg(id) => {
+                        match operand.access {
+                            Some(RegAccessType::ReadOnly) => read.push(id),
+                            Some(RegAccessType::WriteOnly) => written.push(id),
+                            Some(RegAccessType::ReadWrite) => {
+                                read.push(id);
+                                written.push(id);
+                            }
+                            None => {}
+                        }
+                        register(id)
+                    }
+                    X86OperandType::Imm(value) => OperandDetail::Immediate(value),
+                    X86OperandType::Mem(m) => {
+                        memory(m.segment(), m.base(), m.index(), m.scale() as i64, m.disp())
+                    }
+                    X86OperandType::Invalid => continue,
+                },
+                ArchOperand::ArmOperand(operand) => match operand.op_type {
+                    ArmOperandType::Reg(id) | ArmOperandType::SysReg(id) => register(id),
+                    ArmOperandType::Imm(value)
+                    | ArmOperandType::Cimm(value)
+                    | ArmOperandType::Pimm(value) => OperandDetail::Immediate(value as i64),
+                    ArmOperandType::Mem(m) => {
+                        memory(none, m.base(), m.index(), m.scale() as i64, m.disp() as i64)
+                    }
+                    ArmOperandType::Fp(value) => OperandDetail::Other(value.to_string()),
+                    ArmOperandType::Invalid => continue,
+                    other => OperandDetail::Other(format!("{:?}", other)),
+                },
+                ArchOperand::Arm64Operand(operand) => match operand.op_type {
+                    Arm64OperandType::Reg(id) => register(id),
+                    Arm64OperandType::Imm(value) | Arm64OperandType::Cimm(value) => {
+                        OperandDetail::Immediate(value)
+                    }
+                    Arm64OperandType::Mem(m) => {
+                        memory(none, m.base(), m.index(), 1, m.disp() as i64)
+                    }
+                    Arm64OperandType::Fp(value) => OperandDetail::Other(value.to_string()),
+                    Arm64OperandType::Invalid => continue,
+                    other => OperandDetail::Other(format!("{:?}", other)),
+                },
+                ArchOperand::MipsOperand(operand) => match operand {
+                    MipsOperand::Reg(id) => register(id),
+                    MipsOperand::Imm(value) => OperandDetail::Immediate(value),
+                    MipsOperand::Mem(m) => memory(none, m.base(), none, 1, m.disp()),
+                    MipsOperand::Invalid => continue,
+                },
+                ArchOperand::PpcOperand(operand) => match operand {
+                    PpcOperand::Reg(id) => register(id),
+                    PpcOperand::Imm(value) => OperandDetail::Immediate(value),
+                    PpcOperand::Mem(m) => memory(none, m.base(), none, 1, m.disp() as i64),
+                    PpcOperand::Crx(crx) => OperandDetail::Other(format!("{:?}", crx)),
+                    PpcOperand::Invalid => continue,
+                },
+                ArchOperand::RiscVOperand(operand) => match operand {
+                    RiscVOperand::Reg(id) => register(id),
+                    RiscVOperand::Imm(value) => OperandDetail::Immediate(value),
+                    RiscVOperand::Mem(m) => memory(none, m.base(), none, 1, m.disp()),
+                    RiscVOperand::Invalid => continue,
+                },
+                ArchOperand::SparcOperand(operand) => match operand {
+                    SparcOperand::Reg(id) => register(id),
+                    SparcOperand::Imm(value) => OperandDetail::Immediate(value),
+                    SparcOperand::Mem(m) => memory(none, m.base(), m.index(), 1, m.disp() as i64),
+                    SparcOperand::Invalid => continue,
+                },
+                other => OperandDetail::Other(format!("{:?}", other)),
+            };
+            operands.push(operand);
+        }
+        read.extend(address_registers);
+        let names = |registers: Vec<RegId>| {
+            let mut names: Vec<String> = Vec::new();
+            for register in registers.into_iter().filter_map(name) {
+                if !names.contains(&register) {
+                    names.push(register);
+                }
+            }
+            names
+        };
+        Self {
+            registers_read: names(read),
+            registers_written: names(written),
+            groups: detail
+                .groups()
+                .iter()
+                .filter_map(|group| decoder.group_name(*group))
+                .collect(),
+            operands,
+        }
+    }
+
+    pub fn to_lines(&self, color_settings: &ColorSettings) -> Vec<Line<'static>> {
+        let list = |name: &str, values: &[String]| {
+            let values = if values.is_empty() {
+                "none".to_string()
+            } else {
+                values.join(", ")
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<19}", name), color_settings.help_command),
+                Span::styled(values, color_settings.help_description),
+            ])
+            .left_aligned()
+        };
+        let mut lines = vec![
+            list("Registers read:", &self.registers_read),
+            list("Registers written:", &self.registers_written),
+            list("Groups:", &self.groups),
+            Line::styled("Operands:", color_settings.help_command).left_aligned(),
+        ];
+        for operand in &self.operands {
+            lines.push(
+                Line::from(vec![
+                    Span::styled(
+                        format!("  {:<10} ", operand.kind()),
+                        color_settings.menu_text,
+                    ),
+                    Span::styled(operand.to_string(), color_settings.help_description),
+                ])
+                .left_aligned(),
+            );
+            if let OperandDetail::Memory(memory) = operand {
+                let mut components = Vec::new();
+                if let Some(segment) = &memory.segment {
+                    components.push(format!("segment {}", segment));
+                }
+                if let Some(base) = &memory.base {
+                    components.push(format!("base {}", base));
+                }
+                if let Some(index) = &memory.index {
+                    components.push(format!("index {}", index));
+                    components.push(format!("scale {}", memory.scale));
+                }
+                components.push(format!(
+                    "displacement {}",
+                    OperandDetail::Immediate(memory.displacement)
+                ));
+                lines.push(
+                    Line::styled(
+                        format!("  {:<10} {}", "", components.join(", ")),
+                        color_settings.menu_text,
+                    )
+                    .left_aligned(),
+                );
+            }
+        }
+        if self.operands.is_empty() {
+            lines.push(Line::styled("  none", color_settings.help_description).left_aligned());
+        }
+        lines
+    }
+}
+
+impl App {
+    /// Decodes again the instruction of `line` to get its detail,
+    /// `None` for the section tags and the data directives.
+    pub(in crate::app) fn get_instruction_detail(
+        &self,
+        line: &AssemblyLine,
+    ) -> Option<InstructionDetail> {
+        let AssemblyLine::Instruction(instruction) = line else {
+            return None;
+        };
+        let instruction = &instruction.instruction;
+        if instruction.is_data() {
+            return None;
+        }
+        let mut decoders = Decoders::new(&self.header, self.settings.app.x86_syntax, false);
+        let decoder = decoders.get(instruction.ip()).ok()?;
+        let decoded = decoder
+            .disasm_count(&instruction.bytes, instruction.ip(), 1)
+            .ok()?;
+        let decoded = decoded.iter().next()?;
+        Some(InstructionDetail::of(decoder, decoded))
+    }
+
+    /// The current instruction as seen by the plugins.
+    pub(in crate::app) fn get_current_instruction_info(&self) -> Option<InstructionInfo> {
+        let line = self.get_current_instruction()?;
+        Some(InstructionInfo::from(line).with_detail(self.get_instruction_detail(line)))
+    }
+
+    pub(in crate::app) fn request_popup_instruction_detail(&mut self) {
+        match self
+            .get_current_instruction()
+            .and_then(|line| self.get_instruction_detail(line))
+        {
+            Some(detail) => {
+                self.popup = Some(PopupState::InstructionDetail { detail, scroll: 0 });
+            }
+            None => self.log(
+                NotificationLevel::Warning,
+                "There is no instruction at the cursor.",
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use capstone::arch::{self, BuildsCapstone};
+
+    use super::*;
+
+    #[test]
+    fn test_instruction_detail() {
+        let mut decoder = Capstone::new()
+            .x86()
+            .mode(arch::x86::ArchMode::Mode64)
+            .build()
+            .unwrap();
+        decoder.set_detail(true).unwrap();
+        // mov qword ptr fs:[rbx + rcx*8 - 0x10], rax; call 0x1000; add eax, 5
+        let code = [
+            0x64, 0x48, 0x89, 0x44, 0xCB, 0xF0, 0xE8, 0xF5, 0xFF, 0xFF, 0xFF, 0x83, 0xC0, 0x05,
+        ];
+        let decoded = decoder.disasm_all(&code, 0x1000).unwrap();
+        let details = decoded
+            .iter()
+            .map(|instruction| InstructionDetail::of(&decoder, instruction))
+            .collect::<Vec<_>>();
+
+        let memory = MemoryOperand {
+            segment: Some("fs".into()),
+            base: Some("rbx".into()),
+            index: Some("rcx".into()),
+            scale: 8,
+            displacement: -0x10,
+        };
+        assert_eq!(memory.to_string(), "fs:[rbx + rcx * 8 - 0x10]");
+        assert_eq!(
+            details[0].operands,
+            vec![
+                OperandDetail::Memory(memory),
+                OperandDetail::Register("rax".into())
+            ]
+        );
+        assert!(details[0].registers_read.contains(&"rax".to_string()));
+        assert!(details[0].registers_read.contains(&"rbx".to_string()));
+        assert!(details[0].registers_written.is_empty());
+
+        assert!(details[1].groups.contains(&"call".to_string()));
+        assert_eq!(details[1].operands, vec![OperandDetail::Immediate(0x1000)]);
+        assert!(details[1].registers_written.contains(&"rsp".to_string()));
+
+        assert!(details[2].registers_read.contains(&"eax".to_string()));
+        assert!(details[2].registers_written.contains(&"eax".to_string()));
+        assert!(details[2].registers_written.contains(&"rflags".to_string()));
+    }
+}
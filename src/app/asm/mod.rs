@@ -1,4 +1,14 @@
+pub mod annotation;
 pub mod assembly;
 pub mod assembly_line;
+pub mod code_region;
+pub mod control_flow;
+pub mod decoders;
+pub mod flow;
+pub mod function;
+pub mod instruction_detail;
 pub mod instruction_tag;
+pub mod pc_relative;
+pub mod recursive_descent;
 pub mod section_tag;
+pub mod xref;
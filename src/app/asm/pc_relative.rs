@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use object::Endianness;
+
+use crate::headers::{section::SectionKind, Header};
+
+use super::{
+    assembly_line::AssemblyLine,
+    flow::Flow,
+    xref::{Xref, XrefIndex, XrefKind},
+};
+
+/// The shortest run of printable characters shown as a string.
+const MIN_STRING_LENGTH: usize = 4;
+/// The longest string shown next to an instruction, longer strings are cut.
+const MAX_STRING_LENGTH: usize = 40;
+
+/// Pairs the AArch64 `adrp` instructions with the `add`, loads and stores that add the offset
+/// in the page, e.g. `adrp x0, 0x411000; add x0, x0, #0x123`, and sets the reference of the
+/// second instruction to the full address. The pairs are looked for in the order of `lines`,
+/// a branch or a line that is not an instruction forgets every page.
+pub fn resolve_page_offsets(lines: &mut [AssemblyLine], xrefs: &mut XrefIndex) {
+    let mut pages: HashMap<String, u64> = HashMap::new();
+    for line in lines.iter_mut() {
+        let AssemblyLine::Instruction(instruction_tag) = line else {
+            pages.clear();
+            continue;
+        };
+        let instruction = &mut instruction_tag.instruction;
+        let mnemonic = instruction.mnemonic().to_string();
+        if instruction.is_data() || mnemonic.starts_with('b') || mnemonic == "ret" {
+            pages.clear();
+            continue;
+        }
+        let operands = instruction.operands().to_string();
+        let operand_list = operands.split(", ").collect::<Vec<_>>();
+        let destination = operand_list[0];
+        if mnemonic == "adrp" {
+            match instruction.reference() {
+                Some(page) => pages.insert(destination.to_string(), page),
+                None => pages.remove(destination),
+            };
+            continue;
+        }
+
+        let is_store = mnemonic.starts_with("st");
+        let reference = if mnemonic == "add" && operand_list.len() == 3 {
+            pages
+                .get(operand_list[1])
+                .zip(Flow::immediate_address(operand_list[2]))
+                .map(|(page, offset)| page + offset)
+        } else if is_store || mnemonic.starts_with("ld") {
+            // `ldr x1, [x0, #0x10]`, the offset is omitted when it is 0
+            operands
+                .split_once('[')
+                .and_then(|(_, memory)| memory.strip_suffix(']'))
+                .and_then(|memory| {
+                    let (base, offset) = memory.split_once(", ").unwrap_or((memory, "0"));
+                    pages
+                        .get(base)
+                        .zip(Flow::immediate_address(offset))
+                        .map(|(page, offset)| page + offset)
+                })
+        } else {
+            None
+        };
+        if let Some(reference) = reference {
+            instruction.reference = Some(reference);
+            xrefs.add(
+                reference,
+                Xref {
+                    file_address: instruction_tag.file_address,
+                    virtual_address: instruction.ip(),
+                    kind: XrefKind::Read,
+                },
+            );
+        }
+        if !is_store {
+            pages.remove(destination);
+        }
+    }
+}
+
+/// Returns a preview of the data stored at `address`: the string that starts there,
+/// or the pointer-sized value. `None` if the address is not in a data section.
+pub fn data_preview(bytes: &[u8], header: &Header, address: u64) -> Option<String> {
+    let section = header.get_sections().into_iter().find(|section| {
+        section.virtual_address <= address && address < section.virtual_address + section.size
+    })?;
+    if section.permissions.execute || matches!(section.kind, SectionKind::Code | SectionKind::Bss) {
+        return None;
+    }
+    let start = (section.file_offset + address - section.virtual_address) as usize;
+    let end = ((section.file_offset + section.size) as usize).min(bytes.len());
+    if start >= end {
+        return None;
+    }
+    let data = &bytes[start..end];
+
+    let string_length = data
+        .iter()
+        .take_while(|byte| byte.is_ascii_graphic() || b" \t\n\r".contains(byte))
+        .count();
+    if string_length >= MIN_STRING_LENGTH {
+        let string = String::from_utf8_lossy(&data[..string_length.min(MAX_STRING_LENGTH)]);
+        let ellipsis = if string_length > MAX_STRING_LENGTH {
+            "..."
+        } else {
+            ""
+        };
+        return Some(format!("\"{}{}\"", string.escape_debug(), ellipsis));
+    }
+
+    let value = match (header.bitness() / 8, header.endianness()) {
+        (8, Endianness::Little) => u64::from_le_bytes(data.get(..8)?.try_into().ok()?),
+        (8, Endianness::Big) => u64::from_be_bytes(data.get(..8)?.try_into().ok()?),
+        (4, Endianness::Little) => u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as u64,
+        (4, Endianness::Big) => u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as u64,
+        _ => return None,
+    };
+    Some(format!("= {:#x}", value))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::{
+        asm::instruction_tag::InstructionTag, files::filesystem::FileSystem,
+        instruction::Instruction,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_page_offsets() {
+        let line = |virtual_address: u64, mnemonic: &str, operands: &str| {
+            AssemblyLine::Instruction(InstructionTag {
+                instruction: Instruction {
+                    mnemonic: mnemonic.to_string(),
+                    operands: operands.to_string(),
+                    virtual_address,
+                    bytes: vec![0; 4],
+                    reference: (mnemonic == "adrp")
+                        .then(|| Flow::immediate_address(operands))
+                        .flatten(),
+                },
+                file_address: virtual_address - 0x400000,
+            })
+        };
+        let mut lines = vec![
+            line(0x400000, "adrp", "x0, 0x411000"),
+            line(0x400004, "add", "x0, x0, #0x123"),
+            line(0x400008, "adrp", "x1, 0x412000"),
+            line(0x40000C, "ldr", "x2, [x1, #0x10]"),
+            line(0x400010, "str", "x2, [x1]"),
+            line(0x400014, "mov", "x1, x2"),
+            line(0x400018, "ldr", "x3, [x1, #8]"),
+        ];
+        let mut xrefs = XrefIndex::default();
+        resolve_page_offsets(&mut lines, &mut xrefs);
+        let references = lines
+            .iter()
+            .map(|line| match line {
+                AssemblyLine::Instruction(instruction) => instruction.instruction.reference(),
+                AssemblyLine::SectionTag(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            references,
+            vec![
+                Some(0x411000),
+                Some(0x411123),
+                Some(0x412000),
+                Some(0x412010),
+                Some(0x412000),
+                None,
+                None
+            ]
+        );
+        assert_eq!(xrefs.get(0x411123)[0].file_address, 0x4);
+        assert_eq!(xrefs.count(0x412000), 1);
+    }
+
+    #[test]
+    fn test_data_preview() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let header = Header::parse_header(&data, "./elf.bin", &FileSystem::new_local(".").unwrap());
+        assert_eq!(
+            data_preview(&data, &header, 0x1CE1B0),
+            Some("\"internal error: entered unreachable code...\"".to_string())
+        );
+        assert_eq!(
+            data_preview(&data, &header, 0x239CD8),
+            Some("= 0x0".to_string())
+        );
+        // code is not previewed
+        assert_eq!(data_preview(&data, &header, 0x18060), None);
+    }
+}
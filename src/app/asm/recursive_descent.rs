@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use crate::{
+    app::{instruction::Instruction, settings::app_settings::X86Syntax},
+    headers::Header,
+};
+
+use super::{decoders::Decoders, flow::Flow};
+
+/// A range of the file that contains code, `virtual_address` is the address of `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeRange {
+    pub start: usize,
+    pub end: usize,
+    pub virtual_address: u64,
+}
+
+impl CodeRange {
+    fn contains_virtual_address(&self, virtual_address: u64) -> bool {
+        virtual_address >= self.virtual_address
+            && virtual_address < self.virtual_address + (self.end - self.start) as u64
+    }
+}
+
+/// Finds the instructions that can be reached from the entry point, the symbols and the start
+/// of every code range by following branches and calls. Indirect branches are not followed.
+/// Returns the decoded instructions and their flow by file offset.
+pub fn recursive_descent(
+    bytes: &[u8],
+    header: &Header,
+    syntax: X86Syntax,
+    symbols: Option<&HashMap<u64, String>>,
+    ranges: &[CodeRange],
+) -> HashMap<usize, (Instruction, Flow)> {
+    let mut instructions = HashMap::new();
+    if ranges.is_empty() {
+        return instructions;
+    }
+    let mut decoders = Decoders::new(header, syntax, false);
+    let has_delay_slot = header.has_delay_slot();
+
+    let to_file_offset = |virtual_address: u64| {
+        ranges
+            .iter()
+            .find(|range| range.contains_virtual_address(virtual_address))
+            .map(|range| range.start + (virtual_address - range.virtual_address) as usize)
+    };
+    // every offset is paired with whether the flow continues after the instruction
+    let mut worklist: Vec<(usize, bool)> = ranges.iter().map(|range| (range.start, true)).collect();
+    if let Some(symbols) = symbols {
+        worklist.extend(
+            symbols
+                .keys()
+                .filter_map(|address| to_file_offset(*address))
+                .map(|offset| (offset, true)),
+        );
+    }
+    worklist.extend(to_file_offset(header.entry_point()).map(|offset| (offset, true)));
+
+    while let Some((offset, follow)) = worklist.pop() {
+        if instructions.contains_key(&offset) {
+            continue;
+        }
+        let Some(range) = ranges
+            .iter()
+            .find(|range| range.start <= offset && offset < range.end)
+        else {
+            continue;
+        };
+        let virtual_address = range.virtual_address + (offset - range.start) as u64;
+        let Ok(decoder) = decoders.get(virtual_address) else {
+            continue;
+        };
+        let Ok(decoded) = decoder.disasm_count(&bytes[offset..range.end], virtual_address, 1)
+        else {
+            continue;
+        };
+        let Some(instruction) = decoded.iter().next() else {
+            continue;
+        };
+        let next = offset + instruction.len();
+        let flow = Flow::of(decoder, instruction);
+        if let Some(target) = flow.target.and_then(to_file_offset) {
+            worklist.push((target, true));
+        }
+        if follow && next < range.end {
+            if flow.falls_through {
+                worklist.push((next, true));
+            } else if has_delay_slot {
+                worklist.push((next, false));
+            }
+        }
+        let instruction =
+            Instruction::new(instruction, symbols).with_reference(flow.memory_reference);
+        instructions.insert(offset, (instruction, flow));
+    }
+    instructions
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::files::filesystem::FileSystem;
+
+    use super::*;
+
+    #[test]
+    fn test_recursive_descent() {
+        // jmp +2; .byte 0xff, 0xff; nop; call 0x0; ret; .byte 0xcc
+        let bytes = vec![
+            0xEB, 0x02, 0xFF, 0xFF, 0x90, 0xE8, 0xF6, 0xFF, 0xFF, 0xFF, 0xC3, 0xCC,
+        ];
+        let ranges = [CodeRange {
+            start: 0,
+            end: bytes.len(),
+            virtual_address: 0,
+        }];
+        let instructions =
+            recursive_descent(&bytes, &Header::None, X86Syntax::Intel, None, &ranges);
+        let mut offsets = instructions.keys().copied().collect::<Vec<_>>();
+        offsets.sort();
+        assert_eq!(offsets, vec![0, 4, 5, 10]);
+        assert_eq!(instructions[&5].0.mnemonic(), "call");
+        assert_eq!(instructions[&5].1.target, Some(0));
+    }
+
+    #[test]
+    fn test_delay_slot() {
+        let path = "test/elf_sparc.bin";
+        let bytes = std::fs::read(path).unwrap();
+        let header = Header::parse_header(&bytes, path, &FileSystem::new_local(".").unwrap());
+        assert!(header.has_delay_slot());
+        let text = header.get_text_section().unwrap();
+        let start = text.file_offset as usize;
+        let ranges = [CodeRange {
+            start,
+            end: start + text.size as usize,
+            virtual_address: text.virtual_address,
+        }];
+        let instructions = recursive_descent(&bytes, &header, X86Syntax::Intel, None, &ranges);
+        // retl; nop, the nop in the delay slot is executed before returning
+        assert_eq!(instructions[&start].0.mnemonic(), "retl");
+        assert_eq!(instructions[&(start + 4)].0.mnemonic(), "nop");
+    }
+}
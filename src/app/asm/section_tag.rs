@@ -4,4 +4,6 @@ pub struct SectionTag {
     pub file_address: u64,
     pub virtual_address: u64,
     pub size: usize,
+    /// The attributes of the section, `None` if the tag is not the start of a section.
+    pub attributes: Option<String>,
 }
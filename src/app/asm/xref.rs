@@ -0,0 +1,261 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use object::Endianness;
+
+use crate::headers::{section::SectionKind, Header};
+
+use super::flow::Flow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XrefKind {
+    Call,
+    Jump,
+    /// A RIP/PC-relative memory operand.
+    Read,
+    /// An absolute address stored in a data section.
+    Pointer,
+}
+
+impl Display for XrefKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XrefKind::Call => write!(f, "call"),
+            XrefKind::Jump => write!(f, "jump"),
+            XrefKind::Read => write!(f, "read"),
+            XrefKind::Pointer => write!(f, "pointer"),
+        }
+    }
+}
+
+/// A reference from the instruction or the pointer at `file_address` to another address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Xref {
+    pub file_address: u64,
+    pub virtual_address: u64,
+    pub kind: XrefKind,
+}
+
+/// The references to every address, built while disassembling.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XrefIndex {
+    references: HashMap<u64, Vec<Xref>>,
+    /// The addresses referenced from every file address, to remove the references of an edited range.
+    sources: BTreeMap<u64, Vec<u64>>,
+}
+
+impl XrefIndex {
+    pub fn add(&mut self, to: u64, xref: Xref) {
+        self.references.entry(to).or_default().push(xref);
+        self.sources.entry(xref.file_address).or_default().push(to);
+    }
+
+    /// Adds the jumps, calls and memory operands of an instruction.
+    pub fn add_flow(&mut self, file_address: u64, virtual_address: u64, flow: &Flow) {
+        if let Some(target) = flow.target {
+            let kind = if flow.is_call {
+                XrefKind::Call
+            } else {
+                XrefKind::Jump
+            };
+            self.add(
+                target,
+                Xref {
+                    file_address,
+                    virtual_address,
+                    kind,
+                },
+            );
+        }
+        if let Some(memory_reference) = flow.memory_reference {
+            self.add(
+                memory_reference,
+                Xref {
+                    file_address,
+                    virtual_address,
+                    kind: XrefKind::Read,
+                },
+            );
+        }
+    }
+
+    /// Returns the references to `to` sorted by file address.
+    pub fn get(&self, to: u64) -> Vec<Xref> {
+        let mut xrefs = self.references.get(&to).cloned().unwrap_or_default();
+        xrefs.sort_by_key(|xref| xref.file_address);
+        xrefs
+    }
+
+    /// Returns the addresses referenced at least once with the given kind.
+    pub fn targets(&self, kind: XrefKind) -> impl Iterator<Item = u64> + '_ {
+        self.references
+            .iter()
+            .filter(move |(_, xrefs)| xrefs.iter().any(|xref| xref.kind == kind))
+            .map(|(to, _)| *to)
+    }
+
+    pub fn count(&self, to: u64) -> usize {
+        self.references.get(&to).map_or(0, |xrefs| xrefs.len())
+    }
+
+    /// Removes the references that start between the file addresses `start` and `end`
+    /// and whose kind satisfies `filter`.
+    pub fn remove_from(&mut self, start: usize, end: usize, filter: impl Fn(XrefKind) -> bool) {
+        let sources = self
+            .sources
+            .range(start as u64..end as u64)
+            .map(|(source, _)| *source)
+            .collect::<Vec<_>>();
+        for source in sources {
+            let Some(mut targets) = self.sources.remove(&source) else {
+                continue;
+            };
+            targets.sort_unstable();
+            targets.dedup();
+            let mut remaining = Vec::new();
+            for target in targets {
+                let Some(xrefs) = self.references.get_mut(&target) else {
+                    continue;
+                };
+                xrefs.retain(|xref| !(xref.file_address == source && filter(xref.kind)));
+                remaining.extend(
+                    xrefs
+                        .iter()
+                        .filter(|xref| xref.file_address == source)
+                        .map(|_| target),
+                );
+                if xrefs.is_empty() {
+                    self.references.remove(&target);
+                }
+            }
+            if !remaining.is_empty() {
+                self.sources.insert(source, remaining);
+            }
+        }
+    }
+
+    /// Looks for aligned pointers to mapped addresses in the data sections,
+    /// only the pointers that start between `start` and `end` are added.
+    pub fn add_pointers(&mut self, bytes: &[u8], header: &Header, start: usize, end: usize) {
+        let pointer_size = header.bitness() as usize / 8;
+        let segments = header.get_segments();
+        let is_mapped = |address: u64| {
+            segments.iter().any(|segment| {
+                segment.virtual_address <= address
+                    && address < segment.virtual_address + segment.virtual_size
+            }) || header.virtual_to_physical_address(address).is_some()
+        };
+        for section in header.get_sections() {
+            // writable sections of other kinds contain pointers too, e.g. `.init_array` or `.got`
+            let has_data = match section.kind {
+                SectionKind::Data => true,
+                SectionKind::Other => section.permissions.write,
+                _ => false,
+            };
+            if !has_data || !section.permissions.read || section.permissions.execute {
+                continue;
+            }
+            let section_start = section.file_offset as usize;
+            let section_end = (section_start + section.size as usize).min(bytes.len());
+            let misalignment = (section.virtual_address as usize) % pointer_size;
+            let mut offset = section_start + (pointer_size - misalignment) % pointer_size;
+            if offset < start {
+                offset += (start - offset).div_ceil(pointer_size) * pointer_size;
+            }
+            while offset < end && offset + pointer_size <= section_end {
+                let value = &bytes[offset..offset + pointer_size];
+                let value = match (pointer_size, header.endianness()) {
+                    (8, Endianness::Little) => u64::from_le_bytes(value.try_into().unwrap()),
+                    (8, Endianness::Big) => u64::from_be_bytes(value.try_into().unwrap()),
+                    (4, Endianness::Little) => u32::from_le_bytes(value.try_into().unwrap()) as u64,
+                    (4, Endianness::Big) => u32::from_be_bytes(value.try_into().unwrap()) as u64,
+                    _ => break,
+                };
+                if value != 0 && is_mapped(value) {
+                    self.add(
+                        value,
+                        Xref {
+                            file_address: offset as u64,
+                            virtual_address: section.virtual_address
+                                + (offset - section_start) as u64,
+                            kind: XrefKind::Pointer,
+                        },
+                    );
+                }
+                offset += pointer_size;
+            }
+        }
+    }
+
+    /// Updates the pointers after `len` bytes were changed at `offset`.
+    pub fn update_pointers(&mut self, bytes: &[u8], header: &Header, offset: usize, len: usize) {
+        let start = offset.saturating_sub(header.bitness() as usize / 8 - 1);
+        let end = offset + len.max(1);
+        self.remove_from(start, end, |kind| kind == XrefKind::Pointer);
+        self.add_pointers(bytes, header, start, end);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::files::filesystem::FileSystem;
+
+    use super::*;
+
+    #[test]
+    fn test_pointers() {
+        let mut data = std::fs::read("test/elf.bin").unwrap();
+        let header = Header::parse_header(&data, "./elf.bin", &FileSystem::new_local(".").unwrap());
+        let mut xrefs = XrefIndex::default();
+        xrefs.add_pointers(&data, &header, 0, data.len());
+        // .init_array contains a pointer to a function
+        let init_array = header
+            .get_sections()
+            .into_iter()
+            .find(|section| section.name == ".init_array")
+            .unwrap();
+        let offset = init_array.file_offset as usize;
+        let target = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        assert!(xrefs
+            .get(target)
+            .iter()
+            .any(|xref| xref.file_address == offset as u64 && xref.kind == XrefKind::Pointer));
+
+        data[offset..offset + 8].fill(0);
+        xrefs.update_pointers(&data, &header, offset + 2, 1);
+        assert!(!xrefs
+            .get(target)
+            .iter()
+            .any(|xref| xref.file_address == offset as u64));
+    }
+
+    #[test]
+    fn test_remove_from() {
+        let xref = |file_address, kind| Xref {
+            file_address,
+            virtual_address: file_address + 0x1000,
+            kind,
+        };
+        let mut xrefs = XrefIndex::default();
+        xrefs.add(0x2000, xref(0x10, XrefKind::Call));
+        xrefs.add(0x2000, xref(0x10, XrefKind::Read));
+        xrefs.add(0x2000, xref(0x20, XrefKind::Jump));
+        xrefs.add(0x3000, xref(0x18, XrefKind::Pointer));
+
+        xrefs.remove_from(0x10, 0x20, |kind| kind == XrefKind::Read);
+        assert_eq!(
+            xrefs.get(0x2000),
+            vec![xref(0x10, XrefKind::Call), xref(0x20, XrefKind::Jump)]
+        );
+        assert_eq!(xrefs.count(0x3000), 1);
+
+        xrefs.remove_from(0x10, 0x20, |_| true);
+        assert_eq!(xrefs.get(0x2000), vec![xref(0x20, XrefKind::Jump)]);
+        assert_eq!(xrefs.count(0x3000), 0);
+
+        xrefs.remove_from(0, 0x100, |_| true);
+        assert_eq!(xrefs, XrefIndex::default());
+    }
+}
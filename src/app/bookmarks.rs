@@ -0,0 +1,270 @@
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
+
+use crate::fuzzer::fuzzy_search_in_place;
+
+use super::{asm::assembly_line::AssemblyLine, popup::popup_state::PopupState, App};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub file_address: u64,
+}
+
+impl AsRef<str> for Bookmark {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Named positions in the file, sorted by file address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Sets the bookmark `name` at `file_address`, a bookmark with the same name is moved.
+    pub fn set(&mut self, name: &str, file_address: u64) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid bookmark name: \"{}\"", name));
+        }
+        self.remove(name);
+        let index = self
+            .bookmarks
+            .partition_point(|bookmark| bookmark.file_address <= file_address);
+        self.bookmarks.insert(
+            index,
+            Bookmark {
+                name: name.to_string(),
+                file_address,
+            },
+        );
+        Ok(())
+    }
+
+    /// Removes the bookmark `name`, returns the removed bookmark if it existed.
+    pub fn remove(&mut self, name: &str) -> Option<Bookmark> {
+        let index = self
+            .bookmarks
+            .iter()
+            .position(|bookmark| bookmark.name == name)?;
+        Some(self.bookmarks.remove(index))
+    }
+
+    /// Removes every bookmark at `file_address`, returns how many were removed.
+    pub fn remove_at(&mut self, file_address: u64) -> usize {
+        let count = self.bookmarks.len();
+        self.bookmarks
+            .retain(|bookmark| bookmark.file_address != file_address);
+        count - self.bookmarks.len()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|bookmark| bookmark.name == name)
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// Returns the bookmarks between `start` included and `end` excluded.
+    pub fn between(&self, start: u64, end: u64) -> &[Bookmark] {
+        let first = self
+            .bookmarks
+            .partition_point(|bookmark| bookmark.file_address < start);
+        let last = self
+            .bookmarks
+            .partition_point(|bookmark| bookmark.file_address < end);
+        &self.bookmarks[first..last.max(first)]
+    }
+
+    /// The smallest number that is not the name of a bookmark, used as the default name.
+    pub fn next_number(&self) -> usize {
+        (1..)
+            .find(|number| self.get(&number.to_string()).is_none())
+            .expect("There is always a free number")
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.bookmarks.clear();
+    }
+}
+
+impl App {
+    pub(in crate::app) fn request_popup_set_bookmark(&mut self) {
+        let file_address = self.get_cursor_position().global_byte_index as u64;
+        let name = self
+            .bookmarks
+            .between(file_address, file_address + 1)
+            .first()
+            .map(|bookmark| bookmark.name.clone())
+            .unwrap_or_else(|| self.bookmarks.next_number().to_string());
+        self.popup = Some(PopupState::SetBookmark {
+            address: file_address,
+            cursor: name.chars().count(),
+            name,
+        });
+    }
+
+    pub(in crate::app) fn find_bookmarks(&self, filter: &str) -> Vec<Bookmark> {
+        let mut bookmarks = self.bookmarks.bookmarks().to_vec();
+        if !filter.is_empty() {
+            fuzzy_search_in_place(filter, &mut bookmarks);
+        }
+        bookmarks
+    }
+
+    pub(in crate::app) fn request_popup_bookmarks(&mut self) {
+        if self.bookmarks.is_empty() {
+            self.log(super::log::NotificationLevel::Warning, "No bookmarks set.");
+        } else {
+            self.popup = Some(PopupState::Bookmarks {
+                filter: String::new(),
+                cursor: 0,
+                results: self.find_bookmarks(""),
+                scroll: 0,
+            });
+        }
+    }
+
+    /// Sets the bookmark `name` at `file_address`, an empty name removes the bookmarks at `file_address`.
+    pub(in crate::app) fn set_bookmark(&mut self, name: &str, file_address: u64) {
+        if name.trim().is_empty() {
+            if self.bookmarks.remove_at(file_address) == 0 {
+                self.log(
+                    super::log::NotificationLevel::Warning,
+                    "No bookmarks at the cursor.",
+                );
+            } else {
+                self.project_dirty = true;
+            }
+        } else if let Err(e) = self.bookmarks.set(name, file_address) {
+            self.log(super::log::NotificationLevel::Error, &e);
+        } else {
+            self.project_dirty = true;
+        }
+    }
+
+    /// The section, the virtual address and a preview of the instruction or of the bytes of a bookmark.
+    pub(in crate::app) fn bookmark_to_line(
+        &self,
+        bookmark: &Bookmark,
+        selected: bool,
+        name_width: usize,
+    ) -> Line<'static> {
+        let address = bookmark.file_address;
+        let virtual_address = self
+            .header
+            .physical_to_virtual_address(address)
+            .unwrap_or(address);
+        let section = self
+            .header
+            .get_sections()
+            .into_iter()
+            .find(|section| {
+                section.file_offset <= address && address < section.file_offset + section.size
+            })
+            .map(|section| section.name)
+            .unwrap_or_default();
+        let preview = match self
+            .assembly_offsets
+            .get(address as usize)
+            .and_then(|line| self.assembly_instructions.get(*line))
+        {
+            Some(AssemblyLine::Instruction(instruction))
+                if instruction.file_address == address && !instruction.instruction.is_data() =>
+            {
+                instruction.instruction.to_string()
+            }
+            _ => self
+                .data
+                .bytes()
+                .iter()
+                .skip(address as usize)
+                .take(8)
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        let (name_style, info_style) = if selected {
+            (
+                self.settings.color.command_selected,
+                self.settings.color.command_selected,
+            )
+        } else {
+            (
+                self.settings.color.command_name,
+                self.settings.color.command_description,
+            )
+        };
+        Line::from(vec![
+            Span::styled(format!("{:<name_width$} ", bookmark.name), name_style),
+            Span::styled(
+                format!("{:08X} ", address),
+                self.settings.color.assembly_address,
+            ),
+            Span::styled(
+                format!("@{:<12X} ", virtual_address),
+                self.settings.color.assembly_virtual_address,
+            ),
+            Span::styled(format!("{:<10} ", section), info_style),
+            Span::styled(preview, info_style),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bookmarks() {
+        let mut bookmarks = Bookmarks::default();
+        assert_eq!(bookmarks.next_number(), 1);
+        bookmarks.set("1", 0x20).unwrap();
+        bookmarks.set("a", 0x10).unwrap();
+        bookmarks.set("b", 0x30).unwrap();
+        assert!(bookmarks.set("two words", 0x30).is_err());
+        assert_eq!(bookmarks.next_number(), 2);
+        let names = |bookmarks: &Bookmarks| {
+            bookmarks
+                .bookmarks()
+                .iter()
+                .map(|bookmark| bookmark.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&bookmarks), vec!["a", "1", "b"]);
+
+        // setting a bookmark with the same name moves it
+        bookmarks.set("a", 0x40).unwrap();
+        assert_eq!(names(&bookmarks), vec!["1", "b", "a"]);
+        assert_eq!(bookmarks.between(0x20, 0x40).len(), 2);
+        assert_eq!(bookmarks.remove_at(0x30), 1);
+        assert_eq!(bookmarks.remove("a").unwrap().file_address, 0x40);
+        assert_eq!(names(&bookmarks), vec!["1"]);
+    }
+
+    #[test]
+    fn test_bookmark_popup() {
+        let mut app = App::mockup(vec![0x90; 0x40]);
+        app.set_bookmark("start", 0x8);
+        app.set_bookmark("", 0x10);
+        assert_eq!(app.bookmarks.len(), 1);
+        let line = app
+            .bookmark_to_line(&app.bookmarks.bookmarks()[0], false, 8)
+            .to_string();
+        assert!(line.starts_with("start    00000008 @8"));
+        assert!(line.contains("nop"));
+        assert_eq!(app.find_bookmarks("st").len(), 1);
+    }
+}
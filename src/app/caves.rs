@@ -0,0 +1,472 @@
+use object::Architecture;
+use ratatui::text::{Line, Span};
+
+use crate::{get_app_context, headers::section::SectionKind};
+
+use super::{
+    asm::assembly_line::AssemblyLine, log::NotificationLevel, popup::popup_state::PopupState, App,
+};
+
+/// The bytes that fill the gaps between functions: zeros, `int3` and `nop` on x86.
+const PADDING_BYTES: [u8; 3] = [0x00, 0xCC, 0x90];
+
+/// A run of padding bytes in an executable section, where new code can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeCave {
+    pub file_address: u64,
+    pub virtual_address: u64,
+    pub size: u64,
+    pub byte: u8,
+}
+
+/// The code that a detour writes in a code cave, and the jump to it that replaces
+/// the instructions at `site`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detour {
+    pub site: u64,
+    pub cave_bytes: Vec<u8>,
+    pub site_bytes: Vec<u8>,
+}
+
+impl App {
+    /// The executable ranges of the file as (file offset, virtual address, size),
+    /// the whole file if the header has neither sections nor segments.
+    fn executable_ranges(&self) -> Vec<(u64, u64, u64)> {
+        let len = self.data.len() as u64;
+        let sections = self.header.get_sections_or_segments();
+        if sections.is_empty() {
+            let virtual_address = self.header.physical_to_virtual_address(0).unwrap_or(0);
+            return vec![(0, virtual_address, len)];
+        }
+        sections
+            .into_iter()
+            .filter(|section| {
+                (section.permissions.execute || matches!(section.kind, SectionKind::Code))
+                    && !matches!(section.kind, SectionKind::Bss)
+            })
+            .map(|section| {
+                let size = section.size.min(len.saturating_sub(section.file_offset));
+                (section.file_offset, section.virtual_address, size)
+            })
+            .collect()
+    }
+
+    /// The first byte of a run of padding that starts at `start` and that is neither
+    /// part of an instruction decoded before the run nor misaligned.
+    fn cave_start(&self, start: u64, alignment: u64) -> u64 {
+        let start = match self.assembly_offsets.get(start as usize) {
+            Some(&index) => match &self.assembly_instructions[index] {
+                AssemblyLine::Instruction(instruction)
+                    if !instruction.instruction.is_data() && instruction.file_address < start =>
+                {
+                    instruction.file_address + instruction.instruction.len() as u64
+                }
+                _ => start,
+            },
+            None => start,
+        };
+        start.next_multiple_of(alignment)
+    }
+
+    /// Finds the runs of padding bytes of at least `min_cave_size` bytes in the executable sections.
+    pub(in crate::app) fn find_code_caves(&self) -> Vec<CodeCave> {
+        let alignment = match self.header.architecture() {
+            Architecture::Unknown
+            | Architecture::I386
+            | Architecture::X86_64
+            | Architecture::X86_64_X32 => 1,
+            _ => 4,
+        };
+        let min_size = self.settings.app.min_cave_size.max(1) as u64;
+        let bytes = self.data.bytes();
+        let mut caves = Vec::new();
+        for (file_offset, virtual_address, size) in self.executable_ranges() {
+            let end = file_offset + size;
+            let mut start = file_offset;
+            while start < end {
+                let byte = bytes[start as usize];
+                let run_end = (start..end)
+                    .find(|i| bytes[*i as usize] != byte)
+                    .unwrap_or(end);
+                if PADDING_BYTES.contains(&byte) {
+                    let cave_start = self.cave_start(start, alignment).min(run_end);
+                    if run_end - cave_start >= min_size {
+                        caves.push(CodeCave {
+                            file_address: cave_start,
+                            virtual_address: virtual_address + cave_start - file_offset,
+                            size: run_end - cave_start,
+                            byte,
+                        });
+                    }
+                }
+                start = run_end;
+            }
+        }
+        caves
+    }
+
+    /// Lists the code caves, if `detour` is set the selected one receives a detour
+    /// from the current instruction.
+    pub(in crate::app) fn request_popup_code_caves(&mut self, detour: bool) {
+        if detour && !self.is_at_instruction() {
+            self.log(
+                NotificationLevel::Warning,
+                "The detour must start at an instruction.",
+            );
+            return;
+        }
+        let results = self.find_code_caves();
+        if results.is_empty() {
+            self.log(
+                NotificationLevel::Warning,
+                &format!(
+                    "No code caves of at least {} bytes.",
+                    self.settings.app.min_cave_size
+                ),
+            );
+        } else {
+            self.popup = Some(PopupState::CodeCaves {
+                results,
+                scroll: 0,
+                detour,
+            });
+        }
+    }
+
+    /// Jumps to `cave`, or asks for the code of the detour through it.
+    pub(in crate::app) fn open_code_cave(&mut self, cave: &CodeCave, detour: bool) {
+        if detour {
+            self.popup = Some(PopupState::Detour {
+                cave: *cave,
+                assembly: String::new(),
+                preview: self.assemble_detour(cave, ""),
+                cursor: 0,
+            });
+        } else {
+            self.jump_to(cave.file_address as usize, false);
+            self.popup = None;
+        }
+    }
+
+    pub(in crate::app) fn code_cave_to_line(
+        &self,
+        cave: &CodeCave,
+        selected: bool,
+    ) -> Line<'static> {
+        let (address_style, info_style) = if selected {
+            (
+                self.settings.color.command_selected,
+                self.settings.color.command_selected,
+            )
+        } else {
+            (
+                self.settings.color.assembly_virtual_address,
+                self.settings.color.command_description,
+            )
+        };
+        Line::from(vec![
+            Span::styled(format!("{:16X} ", cave.virtual_address), address_style),
+            Span::styled(
+                format!(
+                    "{} bytes of {:02X} at file offset {:X}",
+                    cave.size, cave.byte, cave.file_address
+                ),
+                info_style,
+            ),
+        ])
+    }
+
+    /// The bytes written in the cave and at the current instruction, or why the detour can't be made.
+    pub(in crate::app) fn detour_preview_lines(
+        &self,
+        cave: &CodeCave,
+        preview: &Result<Detour, String>,
+    ) -> Vec<Line<'static>> {
+        let color = &self.settings.color;
+        match preview {
+            Ok(detour) => {
+                let bytes_to_spans = |bytes: &[u8]| {
+                    bytes
+                        .iter()
+                        .map(|byte| {
+                            Span::styled(
+                                format!("{:02X} ", byte),
+                                Self::get_style_for_byte(color, *byte),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let mut cave_line = vec![Span::styled(" Cave ", color.command_description)];
+                cave_line.extend(bytes_to_spans(&detour.cave_bytes));
+                cave_line.push(Span::styled(
+                    format!("({}/{} bytes)", detour.cave_bytes.len(), cave.size),
+                    color.command_description,
+                ));
+                let mut site_line = vec![Span::styled(" Site ", color.command_description)];
+                site_line.extend(bytes_to_spans(&detour.site_bytes));
+                vec![Line::from(cave_line), Line::from(site_line)]
+            }
+            Err(e) => vec![
+                Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled(e.clone(), color.log_error),
+                ]),
+                Line::raw(""),
+            ],
+        }
+    }
+
+    fn is_at_instruction(&self) -> bool {
+        matches!(
+            self.get_current_instruction(),
+            Some(AssemblyLine::Instruction(instruction)) if !instruction.instruction.is_data()
+        )
+    }
+
+    /// An unconditional jump to `address`, the delay slots of MIPS and SPARC are filled with a NOP.
+    fn jump_assembly(&self, address: u64) -> String {
+        match self.header.architecture() {
+            Architecture::Arm
+            | Architecture::Aarch64
+            | Architecture::Aarch64_Ilp32
+            | Architecture::PowerPc
+            | Architecture::PowerPc64 => format!("b {:#x}", address),
+            Architecture::Mips | Architecture::Mips64 | Architecture::Mips64_N32 => {
+                format!("j {:#x}\nnop", address)
+            }
+            Architecture::Riscv32 | Architecture::Riscv64 => format!("j {:#x}", address),
+            Architecture::Sparc | Architecture::Sparc32Plus | Architecture::Sparc64 => {
+                format!("ba {:#x}\nnop", address)
+            }
+            _ => format!("jmp {:#x}", address),
+        }
+    }
+
+    /// Builds a detour from the current instruction through `cave`: the code in `assembly`,
+    /// the instructions displaced by the jump to the cave and a jump back after them.
+    pub(in crate::app) fn assemble_detour(
+        &self,
+        cave: &CodeCave,
+        assembly: &str,
+    ) -> Result<Detour, String> {
+        let Some(AssemblyLine::Instruction(site)) = self.get_current_instruction() else {
+            return Err("The detour must start at an instruction.".to_string());
+        };
+        let site_file_address = site.file_address;
+        let site_virtual_address = site.instruction.ip();
+        let jump_to_cave = self.bytes_from_assembly(
+            &self.jump_assembly(cave.virtual_address),
+            site_virtual_address,
+        )?;
+
+        let mut displaced = Vec::new();
+        let mut displaced_len = 0;
+        let mut index = self.assembly_offsets[site_file_address as usize];
+        while displaced_len < jump_to_cave.len() as u64 {
+            match self.assembly_instructions.get(index) {
+                Some(AssemblyLine::Instruction(instruction))
+                    if !instruction.instruction.is_data()
+                        && instruction.file_address == site_file_address + displaced_len =>
+                {
+                    let is_pc_relative = instruction
+                        .instruction
+                        .operands()
+                        .split(|c: char| !c.is_ascii_alphanumeric())
+                        .any(|word| matches!(word, "rip" | "eip" | "pc"));
+                    if is_pc_relative {
+                        return Err(format!(
+                            "`{}` is relative to the program counter and can't be moved.",
+                            instruction.instruction
+                        ));
+                    }
+                    // a jump to a displaced instruction would land in the middle of the jump to the cave
+                    if displaced_len > 0 && self.xrefs.count(instruction.instruction.ip()) > 0 {
+                        return Err(format!(
+                            "`{}` at {:#X} is referenced by other code and can't be moved.",
+                            instruction.instruction,
+                            instruction.instruction.ip()
+                        ));
+                    }
+                    displaced.push(instruction.instruction.to_string());
+                    displaced_len += instruction.instruction.len() as u64;
+                }
+                _ => {
+                    return Err(format!(
+                        "The jump to the code cave needs {} bytes of instructions at the cursor.",
+                        jump_to_cave.len()
+                    ))
+                }
+            }
+            index += 1;
+        }
+        if site_file_address < cave.file_address + cave.size
+            && cave.file_address < site_file_address + displaced_len
+        {
+            return Err("The detour can't start in its own code cave.".to_string());
+        }
+
+        let mut cave_bytes = if assembly.trim().is_empty() {
+            Vec::new()
+        } else {
+            self.bytes_from_assembly(assembly, cave.virtual_address)?
+        };
+        for instruction in displaced {
+            let virtual_address = cave.virtual_address + cave_bytes.len() as u64;
+            cave_bytes.extend(self.bytes_from_assembly(&instruction, virtual_address)?);
+        }
+        let virtual_address = cave.virtual_address + cave_bytes.len() as u64;
+        cave_bytes.extend(self.bytes_from_assembly(
+            &self.jump_assembly(site_virtual_address + displaced_len),
+            virtual_address,
+        )?);
+        if cave_bytes.len() as u64 > cave.size {
+            return Err(format!(
+                "The detour needs {} bytes, the code cave has {}.",
+                cave_bytes.len(),
+                cave.size
+            ));
+        }
+
+        let mut site_bytes = jump_to_cave;
+        let nop = self.bytes_from_assembly("nop", site_virtual_address)?;
+        while !nop.is_empty() && (site_bytes.len() + nop.len()) as u64 <= displaced_len {
+            site_bytes.extend(&nop);
+        }
+        Ok(Detour {
+            site: site_file_address,
+            cave_bytes,
+            site_bytes,
+        })
+    }
+
+    /// Writes the detour through `cave` and the jump to it, they are undone as one change.
+    pub(in crate::app) fn detour(&mut self, cave: &CodeCave, assembly: &str) {
+        let detour = match self.assemble_detour(cave, assembly) {
+            Ok(detour) => detour,
+            Err(e) => {
+                self.log(NotificationLevel::Error, &e);
+                return;
+            }
+        };
+        let relocations = self.relocations_overwritten(detour.site, detour.site_bytes.len() as u64);
+        if !relocations.is_empty() {
+            self.log(
+                NotificationLevel::Warning,
+                &format!(
+                    "The patch overwrites bytes rewritten by the loader: {}.",
+                    relocations.join(", ")
+                ),
+            );
+        }
+        let cave_len = detour.cave_bytes.len();
+        self.data.start_group();
+        for (offset, mut bytes) in [
+            (cave.file_address as usize, detour.cave_bytes),
+            (detour.site as usize, detour.site_bytes),
+        ] {
+            let mut app_context = get_app_context!(self);
+            app_context.offset = offset;
+            self.plugin_manager.on_edit(&mut bytes, &mut app_context);
+            self.data.push_change(offset, bytes);
+        }
+        self.data.end_group();
+        self.disassemble();
+        self.log(
+            NotificationLevel::Info,
+            &format!(
+                "Detour through the code cave at {:#X}, {} of {} bytes used.",
+                cave.virtual_address, cave_len, cave.size
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn detour_mockup() -> App {
+        let mut data = vec![
+            0x55, // push rbp
+            0x48, 0x89, 0xE5, // mov rbp, rsp
+            0x31, 0xC0, // xor eax, eax
+            0xC3, // ret
+        ];
+        data.extend([0xCC; 0x20]);
+        data.extend([0x90; 4]);
+        App::mockup(data)
+    }
+
+    #[test]
+    fn test_find_code_caves() {
+        let mut app = detour_mockup();
+        let caves = app.find_code_caves();
+        assert_eq!(
+            caves,
+            vec![CodeCave {
+                file_address: 7,
+                virtual_address: 7,
+                size: 0x20,
+                byte: 0xCC
+            }]
+        );
+        assert!(app
+            .code_cave_to_line(&caves[0], false)
+            .to_string()
+            .contains("32 bytes of CC"));
+        app.settings.app.min_cave_size = 4;
+        assert_eq!(app.find_code_caves().len(), 2);
+        app.settings.app.min_cave_size = 0x40;
+        app.request_popup_code_caves(false);
+        assert!(app.popup.is_none());
+    }
+
+    #[test]
+    fn test_detour() {
+        let mut app = detour_mockup();
+        let original = app.data.bytes().to_vec();
+        let cave = app.find_code_caves()[0];
+        app.request_popup_code_caves(true);
+        app.open_code_cave(&cave, true);
+        assert!(matches!(app.popup, Some(PopupState::Detour { .. })));
+
+        app.detour(&cave, "inc eax");
+        // the short jump to the cave displaces `push rbp` and `mov rbp, rsp`
+        assert_eq!(
+            &app.data.bytes()[..6],
+            &[0xEB, 0x05, 0x90, 0x90, 0x31, 0xC0]
+        );
+        assert_eq!(
+            &app.data.bytes()[7..15],
+            &[0xFF, 0xC0, 0x55, 0x48, 0x89, 0xE5, 0xEB, 0xF5]
+        );
+        assert_eq!(app.data.bytes()[15], 0xCC);
+
+        app.undo();
+        assert_eq!(app.data.bytes(), original.as_slice());
+        app.redo();
+        assert_eq!(app.data.bytes()[0], 0xEB);
+        assert_eq!(app.data.bytes()[7], 0xFF);
+
+        let too_small = CodeCave { size: 4, ..cave };
+        app.undo();
+        assert!(app.assemble_detour(&too_small, "inc eax").is_err());
+    }
+
+    #[test]
+    fn test_detour_jump_target() {
+        let mut data = vec![
+            0x55, // push rbp
+            0x48, 0x89, 0xE5, // loop: mov rbp, rsp
+            0xFF, 0xC0, // inc eax
+            0xEB, 0xF9, // jmp loop
+            0xC3, // ret
+        ];
+        data.extend([0xCC; 0x20]);
+        let app = App::mockup(data);
+        let cave = app.find_code_caves()[0];
+        // the head of the loop is displaced by the jump to the cave
+        let error = app.assemble_detour(&cave, "inc eax").unwrap_err();
+        assert!(error.contains("at 0x1 is referenced"), "{}", error);
+    }
+}
@@ -35,6 +35,53 @@ impl CommandInfo {
             CommandInfo::new("view", "Switch between text and assembly."),
             CommandInfo::new("undo", "Undo the last change."),
             CommandInfo::new("redo", "Redo the last change."),
+            CommandInfo::new("template", "Apply a structure template at the cursor."),
+            CommandInfo::new("tview", "Show the fields of the applied template."),
+            CommandInfo::new("tclear", "Remove the applied template."),
+            CommandInfo::new("header", "Show and edit the fields of the file header."),
+            CommandInfo::new(
+                "reparse",
+                "Parse the header and disassemble the file again.",
+            ),
+            CommandInfo::new(
+                "sweep",
+                "Switch between linear sweep and recursive descent disassembly.",
+            ),
+            CommandInfo::new(
+                "syntax",
+                "Switch between the Intel, AT&T and MASM syntax of x86 assembly.",
+            ),
+            CommandInfo::new(
+                "nops",
+                "Switch the padding of the shorter patches with NOPs.",
+            ),
+            CommandInfo::new("follow", "Jump to the target of the current instruction."),
+            CommandInfo::new("back", "Go back to the position before the last jump."),
+            CommandInfo::new("forward", "Go forward to the next position."),
+            CommandInfo::new("label", "Label the current instruction."),
+            CommandInfo::new("comment", "Comment the current instruction."),
+            CommandInfo::new("bookmark", "Set or remove a bookmark at the cursor."),
+            CommandInfo::new("bookmarks", "Show the list of the bookmarks."),
+            CommandInfo::new(
+                "arch",
+                "Set the architecture, mode, endianness and base address of a file without a header.",
+            ),
+            CommandInfo::new("functions", "Show the list of the functions."),
+            CommandInfo::new("xrefs", "Show the references to the current instruction."),
+            CommandInfo::new("mmap", "Show the memory map of the file."),
+            CommandInfo::new("imports", "Show the imports and exports of the file."),
+            CommandInfo::new("caves", "Show the code caves of the executable sections."),
+            CommandInfo::new(
+                "detour",
+                "Insert a detour through a code cave at the current instruction.",
+            ),
+            CommandInfo::new(
+                "detail",
+                "Show the registers, groups and operands of the current instruction.",
+            ),
+            CommandInfo::new("code", "Mark bytes from the cursor as code."),
+            CommandInfo::new("data", "Mark bytes from the cursor as data."),
+            CommandInfo::new("unmark", "Remove the code and data marks at the cursor."),
         ]
     }
 
@@ -2,13 +2,17 @@ use std::error::Error;
 
 use crate::{
     app::{
+        asm::code_region::RangeKind,
+        history::change::Change,
         info_mode::InfoMode,
         log::NotificationLevel,
         popup::{
             binary_choice::BinaryChoice, popup_state::PopupState, simple_choice::SimpleChoice,
         },
+        settings::app_settings::X86Syntax,
         App,
     },
+    asm::assembler::read_only_reason,
     fuzzer::fuzzy_search_in_place,
     get_app_context,
 };
@@ -42,9 +46,7 @@ impl App {
                 self.quit(Some(true))?;
             }
             "save" => {
-                if self.data.dirty() {
-                    self.save_file()?;
-                }
+                self.save()?;
             }
             "saveas" => {
                 self.request_popup_save_as();
@@ -85,6 +87,114 @@ impl App {
             "redo" => {
                 self.redo();
             }
+            "template" => {
+                self.request_popup_select_template();
+            }
+            "tview" => {
+                self.request_popup_template();
+            }
+            "tclear" => {
+                self.clear_template();
+            }
+            "code" => {
+                self.request_popup_mark_range(RangeKind::Code);
+            }
+            "data" => {
+                self.request_popup_mark_range(RangeKind::Data);
+            }
+            "unmark" => {
+                self.unmark_range(self.get_cursor_position().global_byte_index);
+            }
+            "reparse" => {
+                self.reparse_header();
+                self.reapply_template();
+                self.log(NotificationLevel::Info, "Header parsed again.");
+            }
+            "nops" => {
+                self.settings.app.fill_with_nops = !self.settings.app.fill_with_nops;
+                let message = if self.settings.app.fill_with_nops {
+                    "Patches are padded with NOPs to the next instruction."
+                } else {
+                    "Patches are not padded."
+                };
+                self.log(NotificationLevel::Info, message);
+            }
+            "sweep" => {
+                self.settings.app.linear_sweep = !self.settings.app.linear_sweep;
+                self.disassemble();
+                let mode = if self.settings.app.linear_sweep {
+                    "linear sweep"
+                } else {
+                    "recursive descent"
+                };
+                self.log(
+                    NotificationLevel::Info,
+                    &format!("Disassembling with {}.", mode),
+                );
+            }
+            "syntax" => {
+                self.settings.app.x86_syntax = self.settings.app.x86_syntax.next();
+                self.disassemble();
+                let syntax = match self.settings.app.x86_syntax {
+                    X86Syntax::Intel => "Intel",
+                    X86Syntax::Att => "AT&T",
+                    X86Syntax::Masm => "MASM",
+                };
+                self.log(
+                    NotificationLevel::Info,
+                    &format!("Disassembling x86 with the {} syntax.", syntax),
+                );
+            }
+            "follow" => {
+                self.follow_instruction();
+            }
+            "back" => {
+                self.navigate_back();
+            }
+            "forward" => {
+                self.navigate_forward();
+            }
+            "label" => {
+                self.request_popup_label();
+            }
+            "comment" => {
+                self.request_popup_comment();
+            }
+            "bookmark" => {
+                self.request_popup_set_bookmark();
+            }
+            "bookmarks" => {
+                self.request_popup_bookmarks();
+            }
+            "arch" => {
+                self.request_popup_target();
+            }
+            "functions" => {
+                self.request_popup_functions();
+            }
+            "xrefs" => {
+                self.request_popup_xrefs();
+            }
+            "mmap" => {
+                self.request_popup_memory_map();
+            }
+            "imports" => {
+                self.request_popup_imports();
+            }
+            "caves" => {
+                self.request_popup_code_caves(false);
+            }
+            "detour" => {
+                self.request_popup_code_caves(true);
+            }
+            "detail" => {
+                self.request_popup_instruction_detail();
+            }
+            "header" => {
+                if self.apply_header_template() {
+                    self.request_popup_template();
+                }
+            }
             any_other_command => {
                 let mut app_context = get_app_context!(self);
                 self.plugin_manager
@@ -98,9 +208,7 @@ impl App {
         match save {
             Some(true) => {
                 self.log(NotificationLevel::Debug, "Saving and quitting...");
-                if self.data.dirty() {
-                    self.save_file()?;
-                }
+                self.save()?;
                 self.needs_to_exit = true;
             }
             Some(false) => {
@@ -109,7 +217,7 @@ impl App {
             }
             None => {
                 self.log(NotificationLevel::Debug, "Quitting...");
-                if self.data.dirty() {
+                if self.is_dirty() {
                     self.log(NotificationLevel::Warning, "You have unsaved changes.")
                 } else {
                     self.needs_to_exit = true;
@@ -119,8 +227,13 @@ impl App {
         Ok(())
     }
 
+    /// Whether the bytes or the project have unsaved changes.
+    pub(in crate::app) fn is_dirty(&self) -> bool {
+        self.data.dirty() || self.project_dirty
+    }
+
     pub(in crate::app) fn request_quit(&mut self) {
-        if self.data.dirty() {
+        if self.is_dirty() {
             self.popup = Some(PopupState::QuitDirtySave(SimpleChoice::Cancel));
         } else {
             self.needs_to_exit = true;
@@ -128,13 +241,13 @@ impl App {
     }
 
     pub(in crate::app) fn request_save(&mut self) {
-        if self.data.dirty() {
+        if self.is_dirty() {
             self.popup = Some(PopupState::Save(BinaryChoice::No));
         }
     }
 
     pub(in crate::app) fn request_save_and_quit(&mut self) {
-        if self.data.dirty() {
+        if self.is_dirty() {
             self.popup = Some(PopupState::SaveAndQuit(BinaryChoice::No));
         } else {
             self.needs_to_exit = true;
@@ -162,6 +275,17 @@ impl App {
         self.popup = Some(PopupState::Help(0));
     }
 
+    pub(in crate::app) fn request_popup_memory_map(&mut self) {
+        if self.header.get_segments().is_empty() {
+            self.log(
+                NotificationLevel::Warning,
+                "The memory map of this file is not available.",
+            );
+        } else {
+            self.popup = Some(PopupState::MemoryMap(0));
+        }
+    }
+
     pub(in crate::app) fn request_popup_log(&mut self) {
         self.logger.reset_notification_level();
         self.popup = Some(PopupState::Log(0));
@@ -202,7 +326,7 @@ impl App {
     pub(in crate::app) fn request_popup_patch(&mut self) {
         self.popup = Some(PopupState::Patch {
             assembly: String::new(),
-            preview: Ok(Vec::new()),
+            preview: read_only_reason(&self.header).map_or(Ok(Vec::new()), Err),
             cursor: 0,
         });
     }
@@ -214,41 +338,69 @@ impl App {
         });
     }
 
+    pub(in crate::app) fn request_popup_mark_range(&mut self, kind: RangeKind) {
+        self.popup = Some(PopupState::MarkRange {
+            kind,
+            size: String::new(),
+            cursor: 0,
+        });
+    }
+
     pub(in crate::app) fn request_view_change(&mut self) {
         match self.info_mode {
             InfoMode::Text => {
                 self.info_mode = InfoMode::Assembly;
             }
             InfoMode::Assembly => {
+                self.info_mode = InfoMode::Graph;
+            }
+            InfoMode::Graph => {
                 self.info_mode = InfoMode::Text;
             }
         }
     }
 
     pub(in crate::app) fn undo(&mut self) {
-        if let Some(change) = self.data.undo().cloned() {
-            let instruction_offset = self.get_instruction_at(change.offset()).file_address();
-            let instruction_offset = change
-                .offset()
-                .checked_sub(instruction_offset as usize)
-                .unwrap();
-            self.edit_assembly(change.offset() + instruction_offset);
-        } else {
+        let changes = self.data.undo();
+        if changes.is_empty() {
             self.log(NotificationLevel::Warning, "Nothing to undo.")
+        } else {
+            self.update_after_changes(&changes);
         }
     }
 
     pub(in crate::app) fn redo(&mut self) {
-        if let Some(change) = self.data.redo().cloned() {
+        let changes = self.data.redo();
+        if changes.is_empty() {
+            self.log(NotificationLevel::Warning, "Nothing to redo.")
+        } else {
+            self.update_after_changes(&changes);
+        }
+    }
+
+    /// Updates the disassembly after an undo or a redo, a group of changes
+    /// is disassembled again as a whole.
+    fn update_after_changes(&mut self, changes: &[Change]) {
+        if let [change] = changes {
             let instruction_offset = self.get_instruction_at(change.offset()).file_address();
             let instruction_offset = change
                 .offset()
                 .checked_sub(instruction_offset as usize)
                 .unwrap();
-            self.edit_assembly(change.offset() + instruction_offset);
+            self.update_after_edit(
+                change.offset(),
+                change.len(),
+                change.offset() + instruction_offset,
+            );
+        } else if changes
+            .iter()
+            .any(|change| self.is_header_range(change.offset(), change.len()))
+        {
+            self.reparse_header();
         } else {
-            self.log(NotificationLevel::Warning, "Nothing to redo.")
+            self.disassemble();
         }
+        self.reapply_template();
     }
 }
 
@@ -284,4 +436,54 @@ mod test {
             panic!("Expected an instruction.")
         }
     }
+
+    #[test]
+    fn test_reparse_after_header_edit() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let mut app = App::mockup(data);
+        let entry_point = app.header.entry_point();
+        app.jump_to(0x18, false);
+        let cursor = app.cursor;
+        let scroll = app.scroll;
+
+        app.edit_data('F').unwrap();
+        assert_eq!(app.header.entry_point(), entry_point | 0xF0);
+        assert_eq!(app.cursor.1, cursor.1);
+        assert_eq!(app.scroll, scroll);
+
+        app.undo();
+        assert_eq!(app.header.entry_point(), entry_point);
+        app.redo();
+        assert_eq!(app.header.entry_point(), entry_point | 0xF0);
+    }
+
+    #[test]
+    fn test_x86_syntax() {
+        // mov eax, 0x10; mov eax, 0x10
+        let mut app = App::mockup(vec![0xB8, 0x10, 0, 0, 0, 0xB8, 0x10, 0, 0, 0]);
+        let text = |app: &App, offset: usize| match &app.assembly_instructions
+            [app.assembly_offsets[offset]]
+        {
+            AssemblyLine::Instruction(tag) => tag.instruction.to_string().trim().to_string(),
+            AssemblyLine::SectionTag(_) => String::new(),
+        };
+        assert_eq!(text(&app, 0), "mov eax, 0x10");
+
+        // the patches are typed in the syntax that is shown
+        app.run_command("syntax", 0).unwrap();
+        assert_eq!(app.settings.app.x86_syntax, X86Syntax::Att);
+        assert_eq!(text(&app, 0), "movl $0x10, %eax");
+        app.patch("movl $0x20, %eax");
+        assert_eq!(app.data.bytes()[0..5], [0xB8, 0x20, 0, 0, 0]);
+
+        app.run_command("syntax", 0).unwrap();
+        assert_eq!(text(&app, 5), "mov eax, 10h");
+        app.jump_to(5, false);
+        app.patch("mov eax, 30h");
+        assert_eq!(app.data.bytes()[5..10], [0xB8, 0x30, 0, 0, 0]);
+
+        app.run_command("syntax", 0).unwrap();
+        assert_eq!(app.settings.app.x86_syntax, X86Syntax::Intel);
+        assert_eq!(text(&app, 5), "mov eax, 0x30");
+    }
 }
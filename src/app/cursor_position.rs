@@ -1,3 +1,5 @@
+use crate::headers::Header;
+
 use super::{data::Data, log::NotificationLevel, App};
 
 pub struct CursorPosition {
@@ -133,11 +135,12 @@ impl App {
         scroll: usize,
     ) {
         if symbol.is_empty() {
-            if let Some(symbols) = self.header.get_symbols() {
+            if let Some(symbols) = self.annotations.symbols(&self.header) {
                 if let Some(symbol) = symbols.iter().nth(scroll) {
                     let (address, name) = symbol;
                     let log_message = format!("Jumping to symbol {} at {:#X}", name, address);
-                    self.jump_to(*address as usize, true);
+                    // without a header the labels are at their file address
+                    self.jump_to(*address as usize, !matches!(self.header, Header::None));
                     self.log(NotificationLevel::Debug, &log_message);
                 } else {
                     unreachable!("The scroll should not be greater than the number of symbols")
@@ -161,7 +164,7 @@ impl App {
                 NotificationLevel::Debug,
                 &format!("Jumping to symbol {} at {:#X}", name, address),
             );
-            self.jump_to(*address as usize, true);
+            self.jump_to(*address as usize, !matches!(self.header, Header::None));
         } else {
             unreachable!("The scroll should not be greater than the number of symbols");
         }
@@ -194,6 +197,12 @@ impl App {
                     &format!("Invalid virtual address: {}", symbol),
                 );
             }
+        } else if let Some(address) = self.annotations.label_address(symbol) {
+            self.log(
+                NotificationLevel::Debug,
+                &format!("Jumping to label {} at {:#X}", symbol, address),
+            );
+            self.jump_to(address as usize, false);
         } else if let Some(address) = self.header.symbol_to_address(symbol) {
             self.log(
                 NotificationLevel::Debug,
@@ -267,6 +276,7 @@ impl App {
         }
     }
 
+    /// Moves the cursor to `address` and records the current position in the navigation history.
     pub(super) fn jump_to(&mut self, mut address: usize, is_virtual: bool) {
         if is_virtual {
             if let Some(physical_address) = self.header.virtual_to_physical_address(address as u64)
@@ -280,6 +290,15 @@ impl App {
                 return;
             }
         }
+        let current_address = self.get_cursor_position().global_byte_index;
+        if current_address != address {
+            self.navigation.push(current_address);
+        }
+        self.move_cursor_to(address);
+    }
+
+    /// Moves the cursor to the file `address` without recording it in the navigation history.
+    pub(super) fn move_cursor_to(&mut self, address: usize) {
         Self::jump_to_no_self(
             address,
             &self.data,
@@ -382,7 +401,25 @@ impl App {
         }
 
         let target_address = self.assembly_instructions[next_instruction_index].file_address();
-        self.jump_to(target_address as usize, false);
+        self.move_cursor_to(target_address as usize);
+    }
+
+    pub(super) fn navigate_back(&mut self) {
+        let current_address = self.get_cursor_position().global_byte_index;
+        if let Some(address) = self.navigation.back(current_address) {
+            self.move_cursor_to(address);
+        } else {
+            self.log(NotificationLevel::Warning, "Nothing to go back to.");
+        }
+    }
+
+    pub(super) fn navigate_forward(&mut self) {
+        let current_address = self.get_cursor_position().global_byte_index;
+        if let Some(address) = self.navigation.forward(current_address) {
+            self.move_cursor_to(address);
+        } else {
+            self.log(NotificationLevel::Warning, "Nothing to go forward to.");
+        }
     }
 }
 
@@ -5,6 +5,8 @@ pub struct Data {
     bytes: Vec<u8>,
     history: History,
     dirty: bool,
+    /// Set between `start_group` and `end_group`, true once the group has a change.
+    group: Option<bool>,
 }
 
 impl Data {
@@ -13,6 +15,7 @@ impl Data {
             bytes,
             history: History::with_limit(history_limit),
             dirty: false,
+            group: None,
         }
     }
 
@@ -60,22 +63,39 @@ impl Data {
         if old == new.as_slice() {
             return 0;
         }
-        self.history.push(Change::new(offset, old, &new));
+        let change = Change::new(offset, old, &new);
+        self.history.push(if self.group == Some(true) {
+            change.grouped()
+        } else {
+            change
+        });
+        if self.group.is_some() {
+            self.group = Some(true);
+        }
         self.bytes[offset..offset + new.len()].copy_from_slice(&new);
         self.dirty = true;
         new.len()
     }
 
-    /// Undo the last change.
-    /// Returns the change that was undone, if any.
-    pub fn undo(&mut self) -> Option<&Change> {
-        self.history.undo(&mut self.bytes)
+    /// The changes pushed until `end_group` are undone and redone as one.
+    pub fn start_group(&mut self) {
+        self.group = Some(false);
+    }
+
+    pub fn end_group(&mut self) {
+        self.group = None;
+    }
+
+    /// Undo the last change, or the last group of changes.
+    /// Returns the changes that were undone, the last one first.
+    pub fn undo(&mut self) -> Vec<Change> {
+        self.history.undo_group(&mut self.bytes)
     }
 
-    /// Redo the last change.
-    /// Returns the change that was redone, if any.
-    pub fn redo(&mut self) -> Option<&Change> {
-        self.history.redo(&mut self.bytes)
+    /// Redo the next change, or the next group of changes.
+    /// Returns the changes that were redone, in order.
+    pub fn redo(&mut self) -> Vec<Change> {
+        self.history.redo_group(&mut self.bytes)
     }
 
     pub fn clear_history(&mut self) {
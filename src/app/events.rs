@@ -4,6 +4,7 @@ use ratatui::{backend::Backend, Terminal};
 use crate::get_app_context;
 
 use super::{
+    log::NotificationLevel,
     plugins::ui_location::point::Point,
     popup::{binary_choice::BinaryChoice, popup_state::PopupState, simple_choice::SimpleChoice},
     settings::key_settings::KeySettings,
@@ -33,6 +34,9 @@ impl App {
                         super::info_mode::InfoMode::Assembly => {
                             self.move_cursor_to_near_instruction(1);
                         }
+                        super::info_mode::InfoMode::Graph => {
+                            self.move_cursor_to_near_block(1);
+                        }
                     }
                 } else if event == self.settings.key.previous {
                     match self.info_mode {
@@ -42,6 +46,9 @@ impl App {
                         super::info_mode::InfoMode::Assembly => {
                             self.move_cursor_to_near_instruction(-1);
                         }
+                        super::info_mode::InfoMode::Graph => {
+                            self.move_cursor_to_near_block(-1);
+                        }
                     }
                 } else if event == self.settings.key.page_up {
                     self.move_cursor_page_up();
@@ -83,6 +90,22 @@ impl App {
                     self.undo();
                 } else if event == self.settings.key.redo {
                     self.redo();
+                } else if event == self.settings.key.xrefs {
+                    self.request_popup_xrefs();
+                } else if event == self.settings.key.follow {
+                    self.follow_instruction();
+                } else if event == self.settings.key.back {
+                    self.navigate_back();
+                } else if event == self.settings.key.forward {
+                    self.navigate_forward();
+                } else if event == self.settings.key.label {
+                    self.request_popup_label();
+                } else if event == self.settings.key.comment {
+                    self.request_popup_comment();
+                } else if event == self.settings.key.set_bookmark {
+                    self.request_popup_set_bookmark();
+                } else if event == self.settings.key.bookmarks {
+                    self.request_popup_bookmarks();
                 } else if let KeyCode::Char(c) = event.code {
                     match c {
                         '0'..='9' | 'A'..='F' | 'a'..='f' => {
@@ -300,11 +323,27 @@ impl App {
                     true,
                     &self.settings.key,
                 )?;
-                if let Some(current_instruction) = self.get_current_instruction() {
-                    *preview =
-                        self.bytes_from_assembly(assembly, current_instruction.virtual_address());
+                if self.get_current_instruction().is_some() {
+                    *preview = self.assemble_patch(assembly);
                 }
             }
+            Some(PopupState::Detour {
+                cave,
+                assembly,
+                preview,
+                cursor,
+            }) => {
+                Self::handle_string_edit(
+                    assembly,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    true,
+                    &self.settings.key,
+                )?;
+                *preview = self.assemble_detour(cave, assembly);
+            }
             Some(PopupState::JumpToAddress {
                 location: address,
                 cursor,
@@ -319,6 +358,63 @@ impl App {
                     &self.settings.key,
                 )?;
             }
+            Some(PopupState::MarkRange { size, cursor, .. }) => {
+                Self::handle_string_edit(
+                    size,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+            }
+            Some(PopupState::Label { label, cursor, .. }) => {
+                Self::handle_string_edit(
+                    label,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+            }
+            Some(PopupState::Comment {
+                comment, cursor, ..
+            }) => {
+                Self::handle_string_edit(
+                    comment,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+            }
+            Some(PopupState::Target { target, cursor }) => {
+                Self::handle_string_edit(
+                    target,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+            }
+            Some(PopupState::SetBookmark { name, cursor, .. }) => {
+                Self::handle_string_edit(
+                    name,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+            }
             Some(PopupState::SaveAs { path, cursor }) => {
                 Self::handle_string_edit(
                     path,
@@ -330,6 +426,102 @@ impl App {
                     &self.settings.key,
                 )?;
             }
+            Some(PopupState::Functions {
+                filter,
+                cursor,
+                results,
+                scroll: _scroll,
+            }) => {
+                let old_filter = filter.clone();
+                Self::handle_string_edit(
+                    filter,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+                if old_filter != *filter {
+                    *results = self.find_functions(filter);
+                }
+            }
+            Some(PopupState::Bookmarks {
+                filter,
+                cursor,
+                results,
+                scroll: _scroll,
+            }) => {
+                let old_filter = filter.clone();
+                Self::handle_string_edit(
+                    filter,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+                if old_filter != *filter {
+                    *results = self.find_bookmarks(filter);
+                }
+            }
+            Some(PopupState::Imports {
+                filter,
+                cursor,
+                results,
+                scroll: _scroll,
+            }) => {
+                let old_filter = filter.clone();
+                Self::handle_string_edit(
+                    filter,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+                if old_filter != *filter {
+                    *results = self.find_linked_symbols(filter);
+                }
+            }
+            Some(PopupState::SelectTemplate {
+                filter,
+                cursor,
+                results,
+                scroll: _scroll,
+            }) => {
+                let old_filter = filter.clone();
+                Self::handle_string_edit(
+                    filter,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+                if old_filter != *filter || results.is_empty() {
+                    *results = self.find_templates(filter);
+                }
+            }
+            Some(PopupState::Template {
+                scroll: _scroll,
+                editing: true,
+                value,
+                cursor,
+            }) => {
+                Self::handle_string_edit(
+                    value,
+                    cursor,
+                    &event,
+                    None,
+                    None,
+                    false,
+                    &self.settings.key,
+                )?;
+            }
             _ => {}
         }
 
@@ -343,6 +535,13 @@ impl App {
                         Some(PopupState::QuitDirtySave(choice)) => {
                             *choice = choice.next();
                         }
+                        Some(PopupState::Template {
+                            scroll,
+                            editing: false,
+                            ..
+                        }) => {
+                            self.set_template_row_expanded(*scroll, true);
+                        }
                         _ => {}
                     }
                 } else if event == self.settings.key.left {
@@ -353,6 +552,13 @@ impl App {
                         Some(PopupState::QuitDirtySave(choice)) => {
                             *choice = choice.previous();
                         }
+                        Some(PopupState::Template {
+                            scroll,
+                            editing: false,
+                            ..
+                        }) => {
+                            self.set_template_row_expanded(*scroll, false);
+                        }
                         _ => {}
                     }
                 } else if event == self.settings.key.confirm {
@@ -418,6 +624,10 @@ impl App {
                             self.patch(assembly);
                             popup = None;
                         }
+                        Some(PopupState::Detour { cave, assembly, .. }) => {
+                            self.detour(cave, assembly);
+                            popup = None;
+                        }
                         Some(PopupState::JumpToAddress {
                             location,
                             cursor: _cursor,
@@ -425,19 +635,42 @@ impl App {
                             self.jump_to_symbol(location);
                             popup = None;
                         }
+                        Some(PopupState::MarkRange { kind, size, .. }) => {
+                            let start = self.get_cursor_position().global_byte_index;
+                            self.mark_range(start, size, *kind);
+                            popup = None;
+                        }
+                        Some(PopupState::Label { address, label, .. }) => {
+                            self.set_label(*address, label);
+                            popup = None;
+                        }
+                        Some(PopupState::Comment {
+                            address, comment, ..
+                        }) => {
+                            self.set_comment(*address, comment);
+                            popup = None;
+                        }
+                        Some(PopupState::SetBookmark { address, name, .. }) => {
+                            self.set_bookmark(name, *address);
+                            popup = None;
+                        }
+                        Some(PopupState::Target { target, .. }) => {
+                            self.set_target(target);
+                            popup = None;
+                        }
                         Some(PopupState::SaveAs { path, cursor: _ }) => {
                             self.save_file_as(path)?;
                             popup = None;
                         }
                         Some(PopupState::Save(choice)) => {
                             if *choice == BinaryChoice::Yes {
-                                self.save_file()?;
+                                self.save()?;
                             }
                             popup = None;
                         }
                         Some(PopupState::SaveAndQuit(choice)) => {
                             if *choice == BinaryChoice::Yes {
-                                self.save_file()?;
+                                self.save()?;
                                 self.needs_to_exit = true;
                             }
                             popup = None;
@@ -445,7 +678,7 @@ impl App {
                         Some(PopupState::QuitDirtySave(choice)) => {
                             match choice {
                                 SimpleChoice::Yes => {
-                                    self.save_file()?;
+                                    self.save()?;
                                     self.needs_to_exit = true;
                                 }
                                 SimpleChoice::No => {
@@ -458,6 +691,101 @@ impl App {
                         Some(PopupState::Help(_)) => {
                             popup = None;
                         }
+                        Some(PopupState::MemoryMap(scroll)) => {
+                            if let Some(segment) = self.header.get_segments().get(*scroll) {
+                                self.jump_to(segment.file_offset as usize, false);
+                            }
+                            popup = None;
+                        }
+                        Some(PopupState::InstructionDetail { .. }) => {
+                            popup = None;
+                        }
+                        Some(PopupState::Xrefs { address, scroll }) => {
+                            if let Some(xref) = self.xrefs.get(*address).get(*scroll) {
+                                self.jump_to(xref.file_address as usize, false);
+                            }
+                            popup = None;
+                        }
+                        Some(PopupState::Functions {
+                            results, scroll, ..
+                        }) => {
+                            if let Some(function) = results.get(*scroll) {
+                                self.jump_to(function.file_address as usize, false);
+                            }
+                            popup = None;
+                        }
+                        Some(PopupState::Bookmarks {
+                            results, scroll, ..
+                        }) => {
+                            if let Some(bookmark) = results.get(*scroll) {
+                                self.jump_to(bookmark.file_address as usize, false);
+                            }
+                            popup = None;
+                        }
+                        Some(PopupState::Imports {
+                            results, scroll, ..
+                        }) => {
+                            let symbol = results.get(*scroll).cloned();
+                            popup = None;
+                            if let Some(symbol) = symbol {
+                                self.open_linked_symbol(&symbol);
+                                popup.clone_from(&self.popup);
+                            }
+                        }
+                        Some(PopupState::CodeCaves {
+                            results,
+                            scroll,
+                            detour,
+                        }) => {
+                            let cave = results.get(*scroll).copied();
+                            let detour = *detour;
+                            popup = None;
+                            if let Some(cave) = cave {
+                                self.open_code_cave(&cave, detour);
+                                popup.clone_from(&self.popup);
+                            }
+                        }
+                        Some(PopupState::SelectTemplate {
+                            filter: _,
+                            cursor: _,
+                            results,
+                            scroll,
+                        }) => {
+                            let name = results.get(*scroll).cloned();
+                            popup = None;
+                            if let Some(name) = name {
+                                if self.apply_template(&name) {
+                                    self.request_popup_template();
+                                    popup.clone_from(&self.popup);
+                                }
+                            }
+                        }
+                        Some(PopupState::Template {
+                            scroll,
+                            editing,
+                            value,
+                            cursor,
+                        }) => {
+                            if *editing {
+                                match self.edit_template_field(*scroll, value) {
+                                    Ok(()) => *editing = false,
+                                    Err(e) => self.log(NotificationLevel::Error, &e.to_string()),
+                                }
+                                *scroll =
+                                    (*scroll).min(self.get_template_row_count().saturating_sub(1));
+                                if self.template_manager.applied.is_none() {
+                                    popup = None;
+                                }
+                            } else if !self.toggle_template_row(*scroll) {
+                                if let Some(current_value) =
+                                    self.get_template_row_editable_value(*scroll)
+                                {
+                                    *cursor = current_value.len();
+                                    *value = current_value;
+                                    *editing = true;
+                                }
+                            }
+                        }
                         Some(PopupState::Custom {
                             plugin_index: _,
                             callback: _,
@@ -490,7 +818,7 @@ impl App {
                             scroll,
                         }) => {
                             if symbols.is_empty() {
-                                if let Some(symbols) = self.header.get_symbols() {
+                                if let Some(symbols) = self.annotations.symbols(&self.header) {
                                     Self::handle_popup_scroll(scroll, symbols.len(), None, 1);
                                 } else {
                                     *scroll = 0;
@@ -515,6 +843,66 @@ impl App {
                                 1,
                             );
                         }
+                        Some(PopupState::MemoryMap(scroll)) => {
+                            Self::handle_popup_scroll(
+                                scroll,
+                                self.header.get_segments().len(),
+                                None,
+                                1,
+                            );
+                        }
+                        Some(PopupState::InstructionDetail { detail, scroll }) => {
+                            Self::handle_popup_scroll(
+                                scroll,
+                                detail.to_lines(&self.settings.color).len(),
+                                Some(self.get_scrollable_popup_line_count()),
+                                1,
+                            );
+                        }
+                        Some(PopupState::Xrefs { address, scroll }) => {
+                            Self::handle_popup_scroll(scroll, self.xrefs.count(*address), None, 1);
+                        }
+                        Some(PopupState::Functions {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, 1);
+                        }
+                        Some(PopupState::Bookmarks {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, 1);
+                        }
+                        Some(PopupState::Imports {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, 1);
+                        }
+                        Some(PopupState::CodeCaves {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, 1);
+                        }
+                        Some(PopupState::SelectTemplate {
+                            filter: _,
+                            cursor: _,
+                            results,
+                            scroll,
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, 1);
+                        }
+                        Some(PopupState::Template {
+                            scroll,
+                            editing: false,
+                            ..
+                        }) => {
+                            Self::handle_popup_scroll(
+                                scroll,
+                                self.get_template_row_count(),
+                                None,
+                                1,
+                            );
+                            self.jump_to_template_row(*scroll);
+                        }
                         _ => {}
                     }
                 } else if event == self.settings.key.up {
@@ -560,10 +948,76 @@ impl App {
                                 -1,
                             );
                         }
+                        Some(PopupState::MemoryMap(scroll)) => {
+                            Self::handle_popup_scroll(
+                                scroll,
+                                self.header.get_segments().len(),
+                                None,
+                                -1,
+                            );
+                        }
+                        Some(PopupState::InstructionDetail { detail, scroll }) => {
+                            Self::handle_popup_scroll(
+                                scroll,
+                                detail.to_lines(&self.settings.color).len(),
+                                Some(self.get_scrollable_popup_line_count()),
+                                -1,
+                            );
+                        }
+                        Some(PopupState::Xrefs { address, scroll }) => {
+                            Self::handle_popup_scroll(scroll, self.xrefs.count(*address), None, -1);
+                        }
+                        Some(PopupState::Functions {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, -1);
+                        }
+                        Some(PopupState::Bookmarks {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, -1);
+                        }
+                        Some(PopupState::Imports {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, -1);
+                        }
+                        Some(PopupState::CodeCaves {
+                            results, scroll, ..
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, -1);
+                        }
+                        Some(PopupState::SelectTemplate {
+                            filter: _,
+                            cursor: _,
+                            results,
+                            scroll,
+                        }) => {
+                            Self::handle_popup_scroll(scroll, results.len(), None, -1);
+                        }
+                        Some(PopupState::Template {
+                            scroll,
+                            editing: false,
+                            ..
+                        }) => {
+                            Self::handle_popup_scroll(
+                                scroll,
+                                self.get_template_row_count(),
+                                None,
+                                -1,
+                            );
+                            self.jump_to_template_row(*scroll);
+                        }
                         _ => {}
                     }
                 } else if event == self.settings.key.close_popup {
-                    if self.filesystem.is_file(self.filesystem.pwd())
+                    if let Some(PopupState::Template {
+                        editing: editing @ true,
+                        ..
+                    }) = &mut popup
+                    {
+                        *editing = false;
+                    } else if self.filesystem.is_file(self.filesystem.pwd())
                     // if no file is open, close the program instead of the popup
                     {
                         popup = None;
@@ -600,7 +1054,16 @@ impl App {
                                 symbols: _,
                                 cursor: _,
                                 scroll,
-                            }) => {
+                            })
+                            | Some(PopupState::SelectTemplate {
+                                filter: _,
+                                cursor: _,
+                                results: _,
+                                scroll,
+                            })
+                            | Some(PopupState::Functions { scroll, .. })
+                            | Some(PopupState::Bookmarks { scroll, .. })
+                            | Some(PopupState::Imports { scroll, .. }) => {
                                 *scroll = 0;
                             }
                             _ => {}
@@ -124,6 +124,16 @@ impl App {
                     &format!("File type: {:?}", header.file_type()),
                 ),
                 // TODO: maybe add info for a more detailed log
+                Header::CustomHeader(header)
+                    if self
+                        .target
+                        .is_some_and(|target| target.to_header(self.data.len()) == *header) =>
+                {
+                    self.log(
+                        NotificationLevel::Info,
+                        &format!("File type: Raw ({})", self.target.unwrap()),
+                    )
+                }
                 Header::CustomHeader(_) => self.log(NotificationLevel::Info, "File type: Custom"),
                 Header::None => unreachable!(),
             }
@@ -169,6 +179,14 @@ impl App {
         self.info_mode = InfoMode::Text;
         self.scroll = 0;
         self.cursor = (0, 0);
+        self.template_manager.applied = None;
+        self.marked_ranges.clear();
+        self.annotations.clear();
+        self.bookmarks.clear();
+        // the target of the previous project must not be applied to this file
+        self.target = self.cli_target;
+        self.project_dirty = false;
+        self.navigation.clear();
 
         self.screen_size = Self::get_size(terminal)?;
         self.block_size = 8;
@@ -188,6 +206,8 @@ impl App {
         Self::print_loading_status(&self.settings.color, "Decoding binary data...", terminal)?;
 
         self.header = self.parse_header();
+        self.header_ranges = self.header.get_structural_ranges(self.data.bytes());
+        let project = self.load_project();
 
         Self::print_loading_status(
             &self.settings.color,
@@ -195,8 +215,10 @@ impl App {
             terminal,
         )?;
 
-        (self.assembly_offsets, self.assembly_instructions) =
-            Self::sections_from_bytes(self.data.bytes(), &self.header);
+        self.disassemble();
+        if let Some(project) = project {
+            self.restore_project_view(&project);
+        }
 
         Self::print_loading_status(&self.settings.color, "Opening ui...", terminal)?;
         self.log_header_info();
@@ -217,12 +239,38 @@ impl App {
         Ok(())
     }
 
+    /// Saves the file and the project if the bytes changed,
+    /// only the project if just the annotations changed.
+    pub(in crate::app) fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.data.dirty() {
+            self.save_file()?;
+        } else if self.project_dirty {
+            match self.save_project() {
+                Ok(()) => self.log(
+                    NotificationLevel::Info,
+                    &format!("Project saved to \"{}\"", self.get_project_path()),
+                ),
+                Err(e) => self.log(
+                    NotificationLevel::Error,
+                    &format!("Could not save the project file: {}", e),
+                ),
+            }
+        }
+        Ok(())
+    }
+
     pub(in crate::app) fn save_file(&mut self) -> Result<(), Box<dyn Error>> {
         let mut app_context = get_app_context!(self);
         self.plugin_manager.on_save(&mut app_context);
         self.filesystem
             .write(self.filesystem.pwd(), self.data.bytes())?;
         self.data.reset_dirty();
+        if let Err(e) = self.save_project() {
+            self.log(
+                NotificationLevel::Error,
+                &format!("Could not save the project file: {}", e),
+            );
+        }
         match &self.filesystem {
             FileSystem::Local { path } => {
                 self.log(NotificationLevel::Info, &format!("Saved to {}", path));
@@ -2,3 +2,4 @@ mod files;
 pub mod filesystem;
 pub mod path;
 pub mod path_result;
+pub mod project;
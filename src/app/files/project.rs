@@ -0,0 +1,305 @@
+use std::{collections::BTreeMap, error::Error};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{asm::code_region::MarkedRange, bookmarks::Bookmark, log::NotificationLevel, App},
+    headers::target::Target,
+};
+
+/// The extension added to the path of a binary to find its project file.
+pub const PROJECT_EXTENSION: &str = ".hexpatch";
+
+/// A template applied when the project was saved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub name: String,
+    pub base: usize,
+}
+
+/// What was learned about a binary during a session, saved next to it in a `.hexpatch` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project {
+    /// The path of the binary when the project was saved.
+    pub path: String,
+    /// The hash of the binary when the project was saved, see [`Project::hash`].
+    pub hash: String,
+    /// The architecture chosen for a file without a header.
+    pub target: Option<Target>,
+    pub labels: BTreeMap<u64, String>,
+    pub comments: BTreeMap<u64, String>,
+    pub marked_ranges: Vec<MarkedRange>,
+    pub bookmarks: Vec<Bookmark>,
+    pub template: Option<ProjectTemplate>,
+    pub cursor: usize,
+}
+
+impl Project {
+    /// The 64-bit FNV-1a hash of `bytes` as an hex string, it is stable across versions.
+    pub fn hash(bytes: &[u8]) -> String {
+        let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// True if there is nothing worth saving.
+    pub fn is_empty(&self) -> bool {
+        self.target.is_none()
+            && self.labels.is_empty()
+            && self.comments.is_empty()
+            && self.marked_ranges.is_empty()
+            && self.bookmarks.is_empty()
+            && self.template.is_none()
+    }
+}
+
+impl App {
+    pub(in crate::app) fn get_project_path(&self) -> String {
+        format!("{}{}", self.filesystem.pwd(), PROJECT_EXTENSION)
+    }
+
+    /// Reads the project file of the open binary, if there is one.
+    /// The target, the marks and the annotations of the project are restored, they must be loaded
+    /// before the binary is disassembled. A target given on the command line is kept.
+    pub(in crate::app) fn load_project(&mut self) -> Option<Project> {
+        let path = self.get_project_path();
+        if !self.filesystem.is_file(&path) {
+            return None;
+        }
+        let project = match self
+            .filesystem
+            .read(&path)
+            .and_then(|bytes| Ok(serde_json::from_slice::<Project>(&bytes)?))
+        {
+            Ok(project) => project,
+            Err(e) => {
+                self.log(
+                    NotificationLevel::Warning,
+                    &format!("Could not load the project file \"{}\": {}", path, e),
+                );
+                return None;
+            }
+        };
+        if project.hash != Project::hash(self.data.bytes()) {
+            self.log(
+                NotificationLevel::Warning,
+                "The file has changed since the project was saved, the annotations may be wrong.",
+            );
+        }
+
+        if self.cli_target.is_none() && project.target.is_some() {
+            self.target = project.target;
+            self.header = self.parse_header();
+            self.header_ranges = self.header.get_structural_ranges(self.data.bytes());
+        }
+        self.marked_ranges = project.marked_ranges.clone();
+        for (address, label) in project.labels.iter() {
+            if let Err(e) = self.annotations.set_label(*address, label) {
+                self.log(NotificationLevel::Warning, &e);
+            }
+        }
+        for (address, comment) in project.comments.iter() {
+            self.annotations.set_comment(*address, comment);
+        }
+        self.annotations.update_symbols(&self.header);
+        for bookmark in project.bookmarks.iter() {
+            if let Err(e) = self.bookmarks.set(&bookmark.name, bookmark.file_address) {
+                self.log(NotificationLevel::Warning, &e);
+            }
+        }
+        self.log(
+            NotificationLevel::Info,
+            &format!("Project loaded from \"{}\"", path),
+        );
+        Some(project)
+    }
+
+    /// Restores the template and the cursor of `project` once the binary is disassembled.
+    pub(in crate::app) fn restore_project_view(&mut self, project: &Project) {
+        if let Some(template) = &project.template {
+            let is_header_template = self
+                .header
+                .get_header_template()
+                .is_some_and(|header_template| header_template.name == template.name);
+            if is_header_template && template.base == 0 {
+                self.apply_header_template();
+            } else {
+                self.apply_template_at(&template.name, template.base);
+            }
+        }
+        self.move_cursor_to(project.cursor.min(self.data.len().saturating_sub(1)));
+    }
+
+    /// Collects the annotations of the open binary.
+    pub(in crate::app) fn get_project(&self) -> Project {
+        Project {
+            path: self.filesystem.pwd().to_string(),
+            hash: Project::hash(self.data.bytes()),
+            target: self
+                .target
+                .filter(|target| Some(*target) != self.cli_target),
+            labels: self
+                .annotations
+                .labels()
+                .map(|(address, label)| (address, label.to_string()))
+                .collect(),
+            comments: self
+                .annotations
+                .comments()
+                .map(|(address, comment)| (address, comment.to_string()))
+                .collect(),
+            marked_ranges: self.marked_ranges.clone(),
+            bookmarks: self.bookmarks.bookmarks().to_vec(),
+            template: self
+                .template_manager
+                .applied
+                .as_ref()
+                .map(|applied| ProjectTemplate {
+                    name: applied.template.name.clone(),
+                    base: applied.base,
+                }),
+            cursor: self.get_cursor_position().global_byte_index,
+        }
+    }
+
+    /// Writes the project file next to the binary. Nothing is written if there is nothing to save
+    /// and there is no project file yet.
+    pub(in crate::app) fn save_project(&mut self) -> Result<(), Box<dyn Error>> {
+        let project = self.get_project();
+        let path = self.get_project_path();
+        if project.is_empty() && !self.filesystem.is_file(&path) {
+            self.project_dirty = false;
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&project)?;
+        self.filesystem.write(&path, json.as_bytes())?;
+        self.project_dirty = false;
+        self.log(
+            NotificationLevel::Debug,
+            &format!("Project saved to \"{}\"", path),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use crate::app::asm::code_region::RangeKind;
+
+    use super::*;
+
+    #[test]
+    fn test_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("binary");
+        std::fs::write(&path, [0x90; 0x40]).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+
+        let mut app = App::default();
+        app.open_file(&path, &mut terminal).unwrap();
+        app.save_file().unwrap();
+        // nothing to save
+        assert!(!std::path::Path::new(&app.get_project_path()).exists());
+
+        app.set_label(0x10, "start");
+        app.set_comment(0x12, "a comment");
+        app.mark_range(0x20, "0x10", RangeKind::Data);
+        app.set_bookmark("loop", 0x18);
+        app.set_target("x86 0x400000");
+        app.move_cursor_to(0x30);
+        app.save_file().unwrap();
+
+        let mut app = App::default();
+        app.open_file(&path, &mut terminal).unwrap();
+        assert_eq!(app.annotations.label(0x10), Some("start"));
+        assert_eq!(app.annotations.comment(0x12), Some("a comment"));
+        assert_eq!(app.marked_ranges.len(), 1);
+        assert_eq!(app.bookmarks.get("loop").unwrap().file_address, 0x18);
+        assert_eq!(app.target.unwrap().base, 0x400000);
+        assert_eq!(app.header.physical_to_virtual_address(0x10), Some(0x400010));
+        assert_eq!(app.get_cursor_position().global_byte_index, 0x30);
+        assert_eq!(
+            app.annotations
+                .symbols(&app.header)
+                .and_then(|symbols| symbols.get(&0x400010)),
+            Some(&"start".to_string())
+        );
+        assert_eq!(app.get_project().hash, Project::hash(&[0x90; 0x40]));
+    }
+
+    #[test]
+    fn test_save_only_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("binary");
+        std::fs::write(&path, [0x90; 0x40]).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+
+        let mut app = App::default();
+        app.open_file(&path, &mut terminal).unwrap();
+        assert!(!app.is_dirty());
+        app.set_label(0x10, "start");
+        assert!(app.is_dirty());
+        assert!(!app.data.dirty());
+        // quitting without saving asks first
+        app.quit(None).unwrap();
+        assert!(!app.needs_to_exit);
+
+        let save = app
+            .find_commands("save")
+            .iter()
+            .position(|command| command.command == "save")
+            .unwrap();
+        app.run_command("save", save).unwrap();
+        assert!(!app.is_dirty());
+        assert!(std::path::Path::new(&app.get_project_path()).exists());
+        assert_eq!(std::fs::read(&path).unwrap(), vec![0x90; 0x40]);
+
+        let mut app = App::default();
+        app.open_file(&path, &mut terminal).unwrap();
+        assert_eq!(app.annotations.label(0x10), Some("start"));
+        assert!(!app.is_dirty());
+    }
+
+    #[test]
+    fn test_project_target_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+        let mut paths = Vec::new();
+        for (name, target) in [("a", "x86 0x400000"), ("b", "arm 0x8000")] {
+            let path = dir.path().join(name).to_string_lossy().to_string();
+            std::fs::write(&path, [0x90; 0x40]).unwrap();
+            let mut app = App::default();
+            app.open_file(&path, &mut terminal).unwrap();
+            app.set_target(target);
+            app.save().unwrap();
+            paths.push(path);
+        }
+        let raw = dir.path().join("c").to_string_lossy().to_string();
+        std::fs::write(&raw, [0x90; 0x40]).unwrap();
+
+        let mut app = App::default();
+        app.open_file(&paths[0], &mut terminal).unwrap();
+        assert_eq!(app.target.unwrap().base, 0x400000);
+        app.open_file(&paths[1], &mut terminal).unwrap();
+        assert_eq!(app.target.unwrap().base, 0x8000);
+        assert_eq!(app.header.architecture(), object::Architecture::Arm);
+        assert_eq!(app.get_project().target.unwrap().base, 0x8000);
+        app.open_file(&raw, &mut terminal).unwrap();
+        assert!(app.target.is_none());
+        assert_eq!(app.header, crate::headers::Header::None);
+
+        // the target of the command line is used by every file and it is not saved in the projects
+        app.cli_target = Some("mips 0x1000".parse().unwrap());
+        app.open_file(&paths[0], &mut terminal).unwrap();
+        assert_eq!(app.target.unwrap().base, 0x1000);
+        app.open_file(&raw, &mut terminal).unwrap();
+        assert_eq!(app.target.unwrap().base, 0x1000);
+        assert!(app.get_project().target.is_none());
+    }
+}
@@ -18,4 +18,5 @@ pub struct FrameInfo {
 pub enum InfoViewFrameInfo {
     TextView,
     AssemblyView { scroll: usize },
+    GraphView { scroll: usize },
 }
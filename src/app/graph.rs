@@ -0,0 +1,319 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use super::{
+    asm::{
+        assembly_line::AssemblyLine,
+        control_flow::{ControlFlowGraph, EdgeKind},
+    },
+    App,
+};
+
+/// The cell of an edge that is drawn on the left of the blocks.
+#[derive(Debug, Clone, Copy)]
+struct GutterCell {
+    character: char,
+    style: Style,
+}
+
+impl Default for GutterCell {
+    fn default() -> Self {
+        Self {
+            character: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+impl App {
+    /// Returns the control flow graph of the function at the cursor, if any.
+    pub(in crate::app) fn get_current_control_flow_graph(&self) -> Option<ControlFlowGraph> {
+        let cursor = self.get_cursor_position().global_byte_index as u64;
+        let function = self.functions.containing(cursor)?;
+        Some(ControlFlowGraph::build(
+            function,
+            &self.assembly_instructions,
+            &self.assembly_offsets,
+            self.data.bytes(),
+            &self.header,
+        ))
+    }
+
+    fn get_edge_style(&self, kind: EdgeKind) -> Style {
+        match kind {
+            EdgeKind::Unconditional => self.settings.color.graph_edge,
+            EdgeKind::True => self.settings.color.graph_true_edge,
+            EdgeKind::False => self.settings.color.graph_false_edge,
+        }
+    }
+
+    /// Draws the basic blocks of the function at the cursor as boxes sorted by address.
+    /// The edges to the next block are drawn below the block, the other edges on the left.
+    /// Returns the lines of the view and the assembly line shown in each of them.
+    pub(in crate::app) fn get_graph_view(
+        &self,
+        width: usize,
+    ) -> (Vec<Line<'static>>, Vec<Option<usize>>) {
+        let cursor = self.get_cursor_position().global_byte_index as u64;
+        let (Some(function), Some(graph)) = (
+            self.functions.containing(cursor),
+            self.get_current_control_flow_graph(),
+        ) else {
+            return (
+                vec![Line::styled(
+                    "No function at the cursor.",
+                    self.settings.color.menu_text,
+                )],
+                vec![None],
+            );
+        };
+
+        // the rows of the top and bottom border of every block
+        let mut borders = Vec::with_capacity(graph.blocks.len());
+        let mut row_count = 1;
+        for (i, block) in graph.blocks.iter().enumerate() {
+            let top = row_count;
+            row_count += block.lines.len() + 1;
+            borders.push((top, row_count));
+            row_count += if i + 1 < graph.blocks.len() { 2 } else { 1 };
+        }
+
+        // every edge that doesn't go to the next block gets a lane that no other edge
+        // uses between the same rows, the shortest edges get the lanes closest to the blocks
+        let borders = &borders;
+        let mut edges = graph
+            .blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(from, block)| {
+                block
+                    .edges
+                    .iter()
+                    .filter(move |edge| edge.to != from + 1)
+                    .map(move |edge| (borders[from].1, borders[edge.to].0, edge.kind))
+            })
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|(source, target, _)| source.abs_diff(*target));
+        let mut lanes: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut edge_lanes = Vec::with_capacity(edges.len());
+        for (source, target, _) in edges.iter() {
+            let span = (*source.min(target), *source.max(target));
+            let lane = lanes
+                .iter()
+                .position(|used| used.iter().all(|(lo, hi)| span.1 < *lo || *hi < span.0))
+                .unwrap_or_else(|| {
+                    lanes.push(Vec::new());
+                    lanes.len() - 1
+                });
+            lanes[lane].push(span);
+            edge_lanes.push(lane);
+        }
+        let gutter_width = if lanes.is_empty() {
+            0
+        } else {
+            lanes.len() * 2 + 1
+        };
+        let mut gutter = vec![vec![GutterCell::default(); gutter_width]; row_count];
+        let mut set = |row: usize, x: usize, character: char, style: Style| {
+            let cell = &mut gutter[row][x];
+            cell.character = match (cell.character, character) {
+                ('│', '─') | ('─', '│') | ('┼', _) => '┼',
+                _ => character,
+            };
+            cell.style = style;
+        };
+        for ((source, target, kind), lane) in edges.iter().zip(edge_lanes) {
+            let style = self.get_edge_style(*kind);
+            let x = gutter_width - 2 - lane * 2;
+            let (lo, hi) = (*source.min(target), *source.max(target));
+            for row in lo + 1..hi {
+                set(row, x, '│', style);
+            }
+            set(lo, x, '┌', style);
+            set(hi, x, '└', style);
+            for column in x + 1..gutter_width {
+                set(*source, column, '─', style);
+                set(*target, column, '─', style);
+            }
+            set(*target, gutter_width - 1, '►', style);
+        }
+
+        let texts = graph
+            .blocks
+            .iter()
+            .flat_map(|block| block.lines.clone())
+            .map(|index| match &self.assembly_instructions[index] {
+                AssemblyLine::Instruction(instruction) => (
+                    format!("{:X}", instruction.instruction.ip()),
+                    instruction.instruction.to_string(),
+                ),
+                AssemblyLine::SectionTag(_) => (String::new(), String::new()),
+            })
+            .collect::<Vec<_>>();
+        let inner_width = texts
+            .iter()
+            .map(|(address, text)| address.len() + 1 + text.chars().count())
+            .max()
+            .unwrap_or_default()
+            .min(width.saturating_sub(gutter_width + 4))
+            .max(8);
+
+        let mut lines = vec![Line::from(vec![
+            Span::styled(function.name.clone(), self.settings.color.assembly_symbol),
+            Span::styled(
+                format!(" @{:X}", function.virtual_address),
+                self.settings.color.assembly_virtual_address,
+            ),
+        ])];
+        let mut rows = vec![None];
+        let mut texts = texts.into_iter();
+        for (i, block) in graph.blocks.iter().enumerate() {
+            let border_style = if block.contains(cursor) {
+                self.settings.color.graph_selected_block
+            } else {
+                self.settings.color.graph_block
+            };
+            let horizontal = "─".repeat(inner_width + 2);
+            lines.push(Line::from(vec![Span::styled(
+                format!("┌{}┐", horizontal),
+                border_style,
+            )]));
+            rows.push(None);
+            for index in block.lines.clone() {
+                let (address, text) = texts.next().unwrap_or_default();
+                let line = &self.assembly_instructions[index];
+                let selected = line.file_address() <= cursor
+                    && cursor < line.file_address() + line.len() as u64;
+                let text_width = inner_width.saturating_sub(address.len() + 1);
+                let text = if text.chars().count() > text_width {
+                    let mut text = text
+                        .chars()
+                        .take(text_width.saturating_sub(1))
+                        .collect::<String>();
+                    text.push('…');
+                    text
+                } else {
+                    text
+                };
+                let (address_style, text_style) = if selected {
+                    (
+                        self.settings.color.assembly_selected,
+                        self.settings.color.assembly_selected,
+                    )
+                } else {
+                    (
+                        self.settings.color.assembly_address,
+                        self.settings.color.assembly_default,
+                    )
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("│ ", border_style),
+                    Span::styled(address, address_style),
+                    Span::raw(" "),
+                    Span::styled(format!("{:<text_width$}", text), text_style),
+                    Span::styled(" │", border_style),
+                ]));
+                rows.push(Some(index));
+            }
+            lines.push(Line::from(vec![Span::styled(
+                format!("└{}┘", horizontal),
+                border_style,
+            )]));
+            rows.push(None);
+            if i + 1 < graph.blocks.len() {
+                match block.edges.iter().find(|edge| edge.to == i + 1) {
+                    Some(edge) => lines.push(Line::from(vec![Span::styled(
+                        "  ▼",
+                        self.get_edge_style(edge.kind),
+                    )])),
+                    None => lines.push(Line::raw("")),
+                }
+                rows.push(None);
+            }
+        }
+
+        for (line, cells) in lines.iter_mut().zip(gutter) {
+            let mut spans = cells
+                .into_iter()
+                .map(|cell| Span::styled(cell.character.to_string(), cell.style))
+                .collect::<Vec<_>>();
+            spans.append(&mut line.spans);
+            line.spans = spans;
+        }
+        (lines, rows)
+    }
+
+    /// Returns the first row of the graph view that is shown, the instruction at the cursor
+    /// is kept at the center of the view.
+    pub(in crate::app) fn get_graph_view_scroll(
+        &self,
+        rows: &[Option<usize>],
+        visible_rows: usize,
+    ) -> usize {
+        let cursor = self.get_cursor_position().global_byte_index;
+        let current_line = self.assembly_offsets.get(cursor).copied();
+        let current_row = rows
+            .iter()
+            .position(|row| row.is_some() && *row == current_line)
+            .unwrap_or_default();
+        current_row
+            .saturating_sub(visible_rows / 2)
+            .min(rows.len().saturating_sub(visible_rows))
+    }
+
+    /// Moves the cursor to the start of a block of the function at the cursor,
+    /// `block_count` blocks after the current one.
+    pub(in crate::app) fn move_cursor_to_near_block(&mut self, block_count: isize) {
+        let cursor = self.get_cursor_position().global_byte_index as u64;
+        let Some(graph) = self.get_current_control_flow_graph() else {
+            self.move_cursor_to_near_instruction(block_count);
+            return;
+        };
+        let Some(current) = graph.block_containing(cursor) else {
+            self.move_cursor_to_near_instruction(block_count);
+            return;
+        };
+        let next = (current as isize + block_count).clamp(0, graph.blocks.len() as isize - 1);
+        self.move_cursor_to(graph.blocks[next as usize].file_address as usize);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::info_mode::InfoMode;
+
+    use super::*;
+
+    #[test]
+    fn test_graph_view() {
+        // call f; ret
+        // f: test eax, eax; je 0xB; inc eax; dec eax; jmp 0x6
+        let mut app = App::mockup(vec![
+            0xE8, 0x01, 0x00, 0x00, 0x00, 0xC3, 0x85, 0xC0, 0x74, 0x02, 0xFF, 0xC0, 0xFF, 0xC8,
+            0xEB, 0xF6,
+        ]);
+        app.info_mode = InfoMode::Graph;
+        app.jump_to(6, false);
+        let (lines, rows) = app.get_graph_view(80);
+        let text = lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        // title, 3 blocks and 2 connectors
+        assert_eq!(lines.len(), 1 + 4 + 1 + 3 + 1 + 4);
+        assert_eq!(rows[2], Some(app.assembly_offsets[6]));
+        assert!(text[1].trim_start().starts_with('┌'));
+        // the taken branch of je goes to the third block, jmp goes back to the first
+        assert!(text.iter().any(|line| line.contains('►')));
+        assert!(text[5].contains('▼'));
+
+        app.move_cursor_to_near_block(1);
+        assert_eq!(app.get_cursor_position().global_byte_index, 0xA);
+        app.move_cursor_to_near_block(1);
+        assert_eq!(app.get_cursor_position().global_byte_index, 0xC);
+        app.move_cursor_to_near_block(-2);
+        assert_eq!(app.get_cursor_position().global_byte_index, 6);
+    }
+}
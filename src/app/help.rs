@@ -180,6 +180,38 @@ impl App {
                 &Self::key_event_to_string(key_settings.redo),
                 "Redo last change",
             ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.xrefs),
+                "Show references to the current instruction",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.follow),
+                "Follow branch or memory operand",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.back),
+                "Go back to the previous position",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.forward),
+                "Go forward to the next position",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.label),
+                "Label the current instruction",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.comment),
+                "Comment the current instruction",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.set_bookmark),
+                "Set or remove a bookmark",
+            ),
+            HelpLine::new(
+                &Self::key_event_to_string(key_settings.bookmarks),
+                "Show the bookmarks",
+            ),
             HelpLine::new(&Self::key_event_to_string(key_settings.help), "Help"),
         ]
     }
@@ -5,8 +5,8 @@ use ratatui::text::{Line, Span, Text};
 use crate::get_app_context;
 
 use super::{
-    asm::assembly_line::AssemblyLine, info_mode::InfoMode, settings::color_settings::ColorSettings,
-    App,
+    asm::assembly_line::AssemblyLine, info_mode::InfoMode, popup::popup_state::PopupState,
+    settings::color_settings::ColorSettings, App,
 };
 
 pub(super) struct InstructionInfo {
@@ -15,7 +15,14 @@ pub(super) struct InstructionInfo {
     pub is_section: bool,
 }
 
+pub(super) struct TemplateFieldInfo {
+    pub offset: isize,
+    pub length: usize,
+    pub alternate: bool,
+}
+
 impl App {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn bytes_to_styled_hex(
         color_settings: &ColorSettings,
         bytes: &[u8],
@@ -24,6 +31,10 @@ impl App {
         selected_byte_index: usize,
         high_byte: bool,
         instruction_info: Option<InstructionInfo>,
+        template_fields: &[TemplateFieldInfo],
+        comments: &[isize],
+        bookmarks: &[isize],
+        relocations: &[isize],
     ) -> Text<'static> {
         let mut ret = Text::default();
         ret.lines
@@ -42,6 +53,20 @@ impl App {
                 Self::get_style_for_byte(color_settings, *b),
             );
 
+            if let Some(field) = template_fields.iter().rev().find(|field| {
+                byte_index >= field.offset && byte_index < field.offset + field.length as isize
+            }) {
+                let used_style = if field.alternate {
+                    color_settings.hex_template_field_alternate
+                } else {
+                    color_settings.hex_template_field
+                };
+                if byte_index != field.offset + field.length as isize - 1 {
+                    space_style = used_style;
+                }
+                style = used_style;
+            }
+
             if let Some(instruction_info) = &instruction_info {
                 let used_style = if instruction_info.is_section {
                     color_settings.hex_current_section
@@ -60,6 +85,16 @@ impl App {
                 }
             }
 
+            if relocations.contains(&byte_index) {
+                style = style.patch(color_settings.hex_relocation);
+            }
+            if comments.contains(&byte_index) {
+                style = style.patch(color_settings.hex_comment);
+            }
+            if bookmarks.contains(&byte_index) {
+                style = style.patch(color_settings.hex_bookmark);
+            }
+
             let span = Span::styled(
                 hex_high,
                 if byte_index == selected_byte_index as isize && high_byte {
@@ -118,7 +153,7 @@ impl App {
         let old_cursor = self.get_cursor_position();
         self.blocks_per_row = blocks_per_row;
 
-        self.jump_to(old_cursor.global_byte_index, false);
+        self.move_cursor_to(old_cursor.global_byte_index);
     }
 
     pub(super) fn calc_blocks_per_row(block_size: usize, width: u16) -> usize {
@@ -160,11 +195,12 @@ impl App {
             self.plugin_manager
                 .on_edit(&mut new_bytes, &mut app_context);
 
+            let len = new_bytes.len();
             let modified_bytes = self
                 .data
                 .push_change(cursor_position.global_byte_index, new_bytes);
 
-            self.edit_assembly(modified_bytes);
+            self.update_after_edit(cursor_position.global_byte_index, len, modified_bytes);
         }
         Ok(())
     }
@@ -181,7 +217,14 @@ impl App {
             .saturating_sub(start_byte);
         let high_byte = self.get_cursor_position().high_byte;
         let instruction_info = {
-            if self.info_mode == InfoMode::Assembly {
+            if let Some(PopupState::Template { scroll, .. }) = &self.popup {
+                self.get_template_row(*scroll)
+                    .map(|(offset, length, _)| InstructionInfo {
+                        offset: offset as isize - start_byte as isize,
+                        length,
+                        is_section: false,
+                    })
+            } else if matches!(self.info_mode, InfoMode::Assembly | InfoMode::Graph) {
                 let current_instruction = self.get_current_instruction();
                 if let Some(assembly_line) = current_instruction {
                     let offset = assembly_line.file_address() as isize - start_byte as isize;
@@ -207,6 +250,26 @@ impl App {
             selected_byte_index,
             high_byte,
             instruction_info,
+            &self.get_template_fields_info(start_byte, end_byte),
+            &self
+                .annotations
+                .comments_in(start_byte as u64, end_byte as u64)
+                .map(|address| address as isize - start_byte as isize)
+                .collect::<Vec<_>>(),
+            &self
+                .bookmarks
+                .between(start_byte as u64, end_byte as u64)
+                .iter()
+                .map(|bookmark| bookmark.file_address as isize - start_byte as isize)
+                .collect::<Vec<_>>(),
+            &self
+                .header
+                .relocations_in(start_byte as u64, (end_byte - start_byte) as u64)
+                .flat_map(|relocation| {
+                    relocation.file_address..relocation.file_address + relocation.size
+                })
+                .map(|address| address as isize - start_byte as isize)
+                .collect::<Vec<_>>(),
         )
     }
 }
@@ -3,6 +3,8 @@ pub struct Change {
     offset: usize,
     old: Vec<u8>,
     new: Vec<u8>,
+    /// The change is undone and redone together with the one before it.
+    grouped: bool,
 }
 
 impl Change {
@@ -14,9 +16,20 @@ impl Change {
             offset,
             old: old.to_vec(),
             new: new.to_vec(),
+            grouped: false,
         }
     }
 
+    /// Joins the change to the one before it in the history.
+    pub fn grouped(mut self) -> Self {
+        self.grouped = true;
+        self
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.grouped
+    }
+
     pub fn offset(&self) -> usize {
         self.offset
     }
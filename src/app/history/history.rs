@@ -54,6 +54,37 @@ impl History {
         }
     }
 
+    /// Undo the last change and the changes grouped with it.
+    /// Returns the changes that were undone, the last one first.
+    pub fn undo_group(&mut self, data: &mut Vec<u8>) -> Vec<Change> {
+        let mut changes = Vec::new();
+        while let Some(change) = self.undo(data) {
+            let grouped = change.is_grouped();
+            changes.push(change.clone());
+            if !grouped {
+                break;
+            }
+        }
+        changes
+    }
+
+    /// Redo the next change and the changes grouped with it.
+    /// Returns the changes that were redone, in order.
+    pub fn redo_group(&mut self, data: &mut Vec<u8>) -> Vec<Change> {
+        let mut changes = Vec::new();
+        while let Some(change) = self.redo(data) {
+            changes.push(change.clone());
+            if !self
+                .changes
+                .get(self.current)
+                .is_some_and(|next| next.is_grouped())
+            {
+                break;
+            }
+        }
+        changes
+    }
+
     pub fn change_limit(&mut self, limit: usize) {
         self.limit = limit;
         if self.changes.len() > limit && limit > 0 {
@@ -113,4 +144,30 @@ mod test {
         assert_eq!(history.current, 1);
         assert_eq!(history.changes[0], Change::new(0, &[2], &[3]));
     }
+
+    #[test]
+    fn test_history_group() {
+        let mut data = vec![0, 0, 0];
+        let mut history = History::with_limit(0);
+        history.push(Change::new(0, &[0], &[1]));
+        history.push(Change::new(1, &[0], &[2]));
+        history.push(Change::new(2, &[0], &[3]).grouped());
+        data.copy_from_slice(&[1, 2, 3]);
+
+        let undone = history.undo_group(&mut data);
+        assert_eq!(undone.len(), 2);
+        assert_eq!(undone[0].offset(), 2);
+        assert_eq!(data, vec![1, 0, 0]);
+        assert_eq!(history.undo_group(&mut data).len(), 1);
+        assert_eq!(data, vec![0, 0, 0]);
+        assert!(history.undo_group(&mut data).is_empty());
+
+        assert_eq!(history.redo_group(&mut data).len(), 1);
+        assert_eq!(data, vec![1, 0, 0]);
+        let redone = history.redo_group(&mut data);
+        assert_eq!(redone.len(), 2);
+        assert_eq!(redone[0].offset(), 1);
+        assert_eq!(data, vec![1, 2, 3]);
+        assert!(history.redo_group(&mut data).is_empty());
+    }
 }
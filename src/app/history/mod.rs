@@ -2,3 +2,4 @@ pub mod history;
 pub use history::History;
 
 pub mod change;
+pub mod navigation;
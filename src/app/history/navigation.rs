@@ -0,0 +1,64 @@
+/// The maximum number of positions that can be gone back to.
+const NAVIGATION_LIMIT: usize = 256;
+
+/// The positions of the cursor before every jump, used to go back and forward like a browser.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Navigation {
+    back: Vec<usize>,
+    forward: Vec<usize>,
+}
+
+impl Navigation {
+    /// Records a jump from `from`, this clears the positions that can be gone forward to.
+    pub fn push(&mut self, from: usize) {
+        self.forward.clear();
+        if self.back.last() != Some(&from) {
+            if self.back.len() >= NAVIGATION_LIMIT {
+                self.back.remove(0);
+            }
+            self.back.push(from);
+        }
+    }
+
+    /// Returns the position before the last jump, `current` can then be gone forward to.
+    pub fn back(&mut self, current: usize) -> Option<usize> {
+        let position = self.back.pop()?;
+        self.forward.push(current);
+        Some(position)
+    }
+
+    /// Returns the position the last [`Navigation::back`] came from.
+    pub fn forward(&mut self, current: usize) -> Option<usize> {
+        let position = self.forward.pop()?;
+        self.back.push(current);
+        Some(position)
+    }
+
+    pub fn clear(&mut self) {
+        self.back.clear();
+        self.forward.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_navigation() {
+        let mut navigation = Navigation::default();
+        assert_eq!(navigation.back(0), None);
+        navigation.push(0);
+        navigation.push(10);
+        assert_eq!(navigation.back(20), Some(10));
+        assert_eq!(navigation.back(10), Some(0));
+        assert_eq!(navigation.back(0), None);
+        assert_eq!(navigation.forward(0), Some(10));
+        assert_eq!(navigation.forward(10), Some(20));
+        assert_eq!(navigation.forward(20), None);
+
+        navigation.back(20);
+        navigation.push(10);
+        assert_eq!(navigation.forward(30), None);
+    }
+}
@@ -0,0 +1,198 @@
+use ratatui::text::{Line, Span};
+
+use crate::{
+    fuzzer::fuzzy_search_in_place,
+    headers::imports::{Export, Import},
+};
+
+use super::{log::NotificationLevel, popup::popup_state::PopupState, App};
+
+/// An entry of the imports popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkedSymbol {
+    Import(Import),
+    Export(Export),
+}
+
+impl AsRef<str> for LinkedSymbol {
+    fn as_ref(&self) -> &str {
+        match self {
+            LinkedSymbol::Import(import) => &import.name,
+            LinkedSymbol::Export(export) => &export.name,
+        }
+    }
+}
+
+impl App {
+    pub(in crate::app) fn find_linked_symbols(&self, filter: &str) -> Vec<LinkedSymbol> {
+        let mut symbols = self
+            .header
+            .get_imports()
+            .iter()
+            .cloned()
+            .map(LinkedSymbol::Import)
+            .chain(
+                self.header
+                    .get_exports()
+                    .iter()
+                    .cloned()
+                    .map(LinkedSymbol::Export),
+            )
+            .collect::<Vec<_>>();
+        if !filter.is_empty() {
+            fuzzy_search_in_place(filter, &mut symbols);
+        }
+        symbols
+    }
+
+    pub(in crate::app) fn request_popup_imports(&mut self) {
+        if self.header.get_imports().is_empty() && self.header.get_exports().is_empty() {
+            self.log(
+                NotificationLevel::Warning,
+                "The file has no imports or exports.",
+            );
+        } else {
+            self.popup = Some(PopupState::Imports {
+                filter: String::new(),
+                cursor: 0,
+                results: self.find_linked_symbols(""),
+                scroll: 0,
+            });
+        }
+    }
+
+    /// The virtual address whose callers are listed for `symbol`: the stub of an import
+    /// if it is called, otherwise its slot, and the address of an export.
+    fn linked_symbol_address(&self, symbol: &LinkedSymbol) -> Option<u64> {
+        match symbol {
+            LinkedSymbol::Import(import) => match (import.stub, import.slot) {
+                (Some(stub), _) if self.xrefs.count(stub) > 0 => Some(stub),
+                (_, Some(slot)) => Some(slot),
+                (stub, None) => stub,
+            },
+            LinkedSymbol::Export(export) => Some(export.address),
+        }
+    }
+
+    /// Opens the callers of `symbol`, an export without callers is jumped to.
+    pub(in crate::app) fn open_linked_symbol(&mut self, symbol: &LinkedSymbol) {
+        let Some(address) = self.linked_symbol_address(symbol) else {
+            self.log(
+                NotificationLevel::Warning,
+                &format!("{} has no address.", symbol.as_ref()),
+            );
+            return;
+        };
+        if self.xrefs.count(address) > 0 {
+            self.popup = Some(PopupState::Xrefs { address, scroll: 0 });
+        } else if let LinkedSymbol::Export(_) = symbol {
+            self.jump_to(address as usize, true);
+            self.popup = None;
+        } else {
+            self.log(
+                NotificationLevel::Info,
+                &format!("No references to {}.", symbol.as_ref()),
+            );
+            self.popup = None;
+        }
+    }
+
+    /// The kind, the name, the addresses and the number of callers of an import or an export.
+    pub(in crate::app) fn linked_symbol_to_line(
+        &self,
+        symbol: &LinkedSymbol,
+        selected: bool,
+    ) -> Line<'static> {
+        let (name_style, info_style) = if selected {
+            (
+                self.settings.color.command_selected,
+                self.settings.color.command_selected,
+            )
+        } else {
+            (
+                self.settings.color.command_name,
+                self.settings.color.command_description,
+            )
+        };
+        let (kind, name, addresses) = match symbol {
+            LinkedSymbol::Import(import) => {
+                let addresses = [("slot", import.slot), ("stub", import.stub)]
+                    .into_iter()
+                    .filter_map(|(label, address)| {
+                        address.map(|address| format!("{} {:X}", label, address))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                ("import", import.to_string(), addresses)
+            }
+            LinkedSymbol::Export(export) => (
+                "export",
+                export.name.clone(),
+                format!("@{:X}", export.address),
+            ),
+        };
+        let xref_count = self
+            .linked_symbol_address(symbol)
+            .map(|address| self.xrefs.count(address))
+            .unwrap_or_default();
+        let mut spans = vec![
+            Span::styled(format!("{} ", kind), info_style),
+            Span::styled(format!("{:<32} ", name), name_style),
+            Span::styled(addresses, self.settings.color.assembly_virtual_address),
+        ];
+        if xref_count > 0 {
+            spans.push(Span::styled(
+                format!(
+                    " ({} xref{})",
+                    xref_count,
+                    if xref_count > 1 { "s" } else { "" }
+                ),
+                info_style,
+            ));
+        }
+        Line::from(spans)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_imports_popup() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let mut app = App::mockup(data);
+        let symbols = app.find_linked_symbols("");
+        assert_eq!(
+            symbols.len(),
+            app.header.get_imports().len() + app.header.get_exports().len()
+        );
+        let symbol = app
+            .find_linked_symbols("__cxa_finalize")
+            .into_iter()
+            .next()
+            .unwrap();
+        let line = app.linked_symbol_to_line(&symbol, false).to_string();
+        assert!(line.starts_with("import libc!__cxa_finalize"));
+        assert!(line.contains("slot 23A2F0 stub 18050 (1 xref)"));
+
+        app.request_popup_imports();
+        assert!(matches!(app.popup, Some(PopupState::Imports { .. })));
+        app.open_linked_symbol(&symbol);
+        // the callers of the PLT entry are listed
+        assert!(matches!(
+            app.popup,
+            Some(PopupState::Xrefs {
+                address: 0x18050,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_no_imports() {
+        let mut app = App::mockup(vec![0x90; 0x10]);
+        app.request_popup_imports();
+        assert!(app.popup.is_none());
+    }
+}
@@ -2,4 +2,5 @@
 pub enum InfoMode {
     Text,
     Assembly,
+    Graph,
 }
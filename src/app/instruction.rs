@@ -2,12 +2,17 @@ use std::{collections::HashMap, fmt::Display};
 
 use capstone::Insn;
 
+/// The mnemonic used by capstone for the bytes it can't decode.
+pub const DATA_DIRECTIVE: &str = ".byte";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Instruction {
     pub(super) mnemonic: String,
     pub(super) operands: String,
     pub(super) virtual_address: u64,
     pub(super) bytes: Vec<u8>,
+    /// The address of a RIP/PC-relative memory operand.
+    pub(super) reference: Option<u64>,
 }
 
 impl Instruction {
@@ -35,6 +40,18 @@ impl Instruction {
                         }
                     }
                 }
+                // MASM numbers end with `h` and start with a digit, unlike the registers
+                if let Some(operand) = operand
+                    .strip_suffix('h')
+                    .filter(|operand| operand.starts_with(|c: char| c.is_ascii_digit()))
+                {
+                    if let Ok(operand_address) = u64::from_str_radix(operand, 16) {
+                        if let Some(symbol) = symbols.get(&operand_address) {
+                            found_symbol = true;
+                            operands_string.push_str(symbol);
+                        }
+                    }
+                }
             }
             if !found_symbol {
                 operands_string.push_str(operand);
@@ -50,9 +67,35 @@ impl Instruction {
             operands: operands_string,
             virtual_address,
             bytes,
+            reference: None,
         }
     }
 
+    pub fn with_reference(mut self, reference: Option<u64>) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    /// A `.byte` directive that shows bytes that are not code.
+    pub fn data(virtual_address: u64, bytes: &[u8]) -> Self {
+        let operands = bytes
+            .iter()
+            .map(|byte| format!("{:#04x}", byte))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Instruction {
+            mnemonic: DATA_DIRECTIVE.to_string(),
+            operands,
+            virtual_address,
+            bytes: bytes.to_vec(),
+            reference: None,
+        }
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.mnemonic == DATA_DIRECTIVE
+    }
+
     pub fn mnemonic(&self) -> &str {
         &self.mnemonic
     }
@@ -61,6 +104,10 @@ impl Instruction {
         &self.operands
     }
 
+    pub fn reference(&self) -> Option<u64> {
+        self.reference
+    }
+
     pub fn ip(&self) -> u64 {
         self.virtual_address
     }
@@ -3,15 +3,19 @@ pub use app::*;
 
 pub mod address;
 pub mod asm;
+pub mod bookmarks;
+pub mod caves;
 pub mod commands;
 pub mod cursor_position;
 pub mod data;
 pub mod events;
 pub mod files;
 pub mod frame_info;
+pub mod graph;
 pub mod help;
 pub mod hex;
 pub mod history;
+pub mod imports;
 pub mod info_mode;
 pub mod instruction;
 pub mod log;
@@ -21,5 +25,6 @@ pub mod popup;
 pub mod settings;
 pub mod ssh;
 pub mod status_bar;
+pub mod templates;
 pub mod text;
 pub mod widgets;
@@ -7,6 +7,7 @@ use mlua::{Function, Lua, Scope, Table};
 
 use crate::{
     app::{
+        bookmarks::Bookmarks,
         data::Data,
         log::{logger::Logger, NotificationLevel},
         popup::popup_state::PopupState,
@@ -26,7 +27,7 @@ macro_rules! get_app_context {
     ($app:ident) => {
         $crate::app::plugins::app_context::AppContext::new(
             $app.get_cursor_position().global_byte_index,
-            $app.get_current_instruction().map(|i| i.into()),
+            $app.get_current_instruction_info(),
             $app.screen_size.1,
             $app.screen_size.0,
             $app.blocks_per_row,
@@ -39,6 +40,7 @@ macro_rules! get_app_context {
             &mut $app.settings,
             &mut $app.logger,
             &mut $app.popup,
+            &mut $app.bookmarks,
         )
     };
 }
@@ -62,6 +64,7 @@ pub struct AppContext<'app> {
     pub settings: &'app mut Settings,
     pub logger: &'app mut Logger,
     pub popup: Arc<Mutex<&'app mut Option<PopupState>>>,
+    pub bookmarks: Arc<Mutex<&'app mut Bookmarks>>,
 }
 
 impl<'app> AppContext<'app> {
@@ -81,6 +84,7 @@ impl<'app> AppContext<'app> {
         settings: &'app mut Settings,
         logger: &'app mut Logger,
         popup: &'app mut Option<PopupState>,
+        bookmarks: &'app mut Bookmarks,
     ) -> Self {
         Self {
             exported_commands: Arc::new(Mutex::new(ExportedCommands::default())),
@@ -100,6 +104,7 @@ impl<'app> AppContext<'app> {
             settings,
             logger,
             popup: Arc::new(Mutex::new(popup)),
+            bookmarks: Arc::new(Mutex::new(bookmarks)),
         }
     }
 
@@ -389,5 +394,57 @@ impl<'app> AppContext<'app> {
             )
             .unwrap();
         context
+            .set(
+                "add_bookmark",
+                scope
+                    .create_function_mut(|_, (name, file_address): (String, usize)| {
+                        if file_address >= self.data.lock().unwrap().len() {
+                            return Err(mlua::Error::external("Address out of bounds"));
+                        }
+                        self.bookmarks
+                            .lock()
+                            .unwrap()
+                            .set(&name, file_address as u64)
+                            .map_err(mlua::Error::external)
+                    })
+                    .unwrap(),
+            )
+            .unwrap();
+        context
+            .set(
+                "remove_bookmark",
+                scope
+                    .create_function_mut(|_, name: String| {
+                        if self.bookmarks.lock().unwrap().remove(&name).is_some() {
+                            Ok(())
+                        } else {
+                            Err(mlua::Error::external(format!(
+                                "Bookmark '{}' not found",
+                                name
+                            )))
+                        }
+                    })
+                    .unwrap(),
+            )
+            .unwrap();
+        context
+            .set(
+                "get_bookmarks",
+                scope
+                    .create_function(|lua, ()| {
+                        let bookmarks = self.bookmarks.lock().unwrap();
+                        let table = lua.create_table()?;
+                        for bookmark in bookmarks.bookmarks() {
+                            let entry = lua.create_table()?;
+                            entry.set("name", bookmark.name.as_str())?;
+                            entry.set("file_address", bookmark.file_address)?;
+                            table.push(entry)?;
+                        }
+                        Ok(table)
+                    })
+                    .unwrap(),
+            )
+            .unwrap();
+        context
     }
 }
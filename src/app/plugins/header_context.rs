@@ -3,7 +3,24 @@ use std::collections::HashMap;
 use mlua::UserData;
 use object::{Architecture, Endianness};
 
-use crate::headers::{bitness::Bitness, custom_header::CustomHeader, section::Section};
+use crate::headers::{
+    bitness::Bitness,
+    custom_header::CustomHeader,
+    section::{Section, SectionKind, SectionPermissions},
+    target::CpuMode,
+};
+
+/// name, virtual_address, file_offset, size, permissions, kind, alignment, segment
+type SectionArguments = (
+    String,
+    u64,
+    u64,
+    u64,
+    Option<String>,
+    Option<String>,
+    Option<u64>,
+    Option<String>,
+);
 
 #[derive(Debug, Clone, Default)]
 pub struct HeaderContext {
@@ -26,6 +43,7 @@ impl HeaderContext {
                 entry,
                 endianness,
                 architecture,
+                mode: CpuMode::Default,
                 sections: self.sections,
                 symbols: self.symbols,
                 symbols_by_name,
@@ -91,6 +109,7 @@ impl UserData for HeaderContext {
                     "Unknown" => Architecture::Unknown,
                     "Aarch64" => Architecture::Aarch64,
                     "Aarch64_Ilp32" => Architecture::Aarch64_Ilp32,
+                    "Arm64E" | "Arm64EC" => Architecture::Aarch64,
                     "Arm" => Architecture::Arm,
                     "Avr" => Architecture::Avr,
                     "Bpf" => Architecture::Bpf,
@@ -100,6 +119,7 @@ impl UserData for HeaderContext {
                     "X86_64_X32" => Architecture::X86_64_X32,
                     "Hexagon" => Architecture::Hexagon,
                     "LoongArch64" => Architecture::LoongArch64,
+                    "M68k" => Architecture::M68k,
                     "Mips" => Architecture::Mips,
                     "Mips64" => Architecture::Mips64,
                     "Msp430" => Architecture::Msp430,
@@ -123,18 +143,30 @@ impl UserData for HeaderContext {
             }
         });
 
-        methods.add_method_mut(
-            "add_section",
-            |_, this, (name, virtual_address, file_offset, size): (String, u64, u64, u64)| {
-                this.sections.push(Section {
-                    name,
-                    virtual_address,
-                    file_offset,
-                    size,
-                });
-                Ok(())
-            },
-        );
+        methods.add_method_mut("add_section", |_, this, arguments: SectionArguments| {
+            let (name, virtual_address, file_offset, size, permissions, kind, alignment, segment) =
+                arguments;
+            let mut section = Section::new(name, virtual_address, file_offset, size);
+            if let Some(permissions) = permissions {
+                section.permissions = SectionPermissions::parse(&permissions)
+                    .ok_or(mlua::Error::external("invalid permissions"))?;
+                section.kind = if section.permissions.execute {
+                    SectionKind::Code
+                } else {
+                    SectionKind::Data
+                };
+            }
+            if let Some(kind) = kind {
+                section.kind = SectionKind::parse(&kind)
+                    .ok_or(mlua::Error::external("invalid section kind"))?;
+            }
+            if let Some(alignment) = alignment {
+                section.alignment = alignment;
+            }
+            section.segment = segment;
+            this.sections.push(section);
+            Ok(())
+        });
 
         methods.add_method_mut("add_symbol", |_, this, (address, name): (u64, String)| {
             this.symbols.insert(address, name);
@@ -1,6 +1,9 @@
 use mlua::UserData;
 
-use crate::app::asm::assembly_line::AssemblyLine;
+use crate::app::asm::{
+    assembly_line::AssemblyLine,
+    instruction_detail::{InstructionDetail, OperandDetail},
+};
 
 #[derive(Debug, Clone)]
 pub struct InstructionInfo {
@@ -8,6 +11,8 @@ pub struct InstructionInfo {
     pub physical_address: u64,
     pub virtual_address: u64,
     pub size: usize,
+    /// `None` for the section tags and the data directives.
+    pub detail: Option<InstructionDetail>,
 }
 
 impl InstructionInfo {
@@ -22,8 +27,14 @@ impl InstructionInfo {
             physical_address,
             virtual_address,
             size,
+            detail: None,
         }
     }
+
+    pub fn with_detail(mut self, detail: Option<InstructionDetail>) -> Self {
+        self.detail = detail;
+        self
+    }
 }
 
 impl From<&AssemblyLine> for InstructionInfo {
@@ -51,5 +62,50 @@ impl UserData for InstructionInfo {
         fields.add_field_method_get("physical_address", |_lua, this| Ok(this.physical_address));
         fields.add_field_method_get("virtual_address", |_lua, this| Ok(this.virtual_address));
         fields.add_field_method_get("size", |_lua, this| Ok(this.size));
+        fields.add_field_method_get("registers_read", |_lua, this| {
+            Ok(this
+                .detail
+                .as_ref()
+                .map(|detail| detail.registers_read.clone())
+                .unwrap_or_default())
+        });
+        fields.add_field_method_get("registers_written", |_lua, this| {
+            Ok(this
+                .detail
+                .as_ref()
+                .map(|detail| detail.registers_written.clone())
+                .unwrap_or_default())
+        });
+        fields.add_field_method_get("groups", |_lua, this| {
+            Ok(this
+                .detail
+                .as_ref()
+                .map(|detail| detail.groups.clone())
+                .unwrap_or_default())
+        });
+        fields.add_field_method_get("operands", |lua, this| {
+            let operands = lua.create_table()?;
+            let Some(detail) = &this.detail else {
+                return Ok(operands);
+            };
+            for operand in &detail.operands {
+                let table = lua.create_table()?;
+                table.set("type", operand.kind())?;
+                match operand {
+                    OperandDetail::Register(register) => table.set("register", register.clone())?,
+                    OperandDetail::Immediate(value) => table.set("value", *value)?,
+                    OperandDetail::Memory(memory) => {
+                        table.set("segment", memory.segment.clone())?;
+                        table.set("base", memory.base.clone())?;
+                        table.set("index", memory.index.clone())?;
+                        table.set("scale", memory.scale)?;
+                        table.set("displacement", memory.displacement)?;
+                    }
+                    OperandDetail::Other(other) => table.set("value", other.clone())?,
+                }
+                operands.push(table)?;
+            }
+            Ok(operands)
+        });
     }
 }
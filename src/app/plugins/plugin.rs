@@ -266,7 +266,10 @@ mod test {
     use crate::{
         app::{log::NotificationLevel, settings::settings_value::SettingsValue, App},
         get_app_context,
-        headers::{bitness::Bitness, section::Section},
+        headers::{
+            bitness::Bitness,
+            section::{Section, SectionKind, SectionPermissions},
+        },
     };
 
     use super::*;
@@ -630,11 +633,16 @@ mod test {
         assert_eq!(header.entry, 0x40);
         assert_eq!(
             header.sections[0],
+            Section::new(".text".to_string(), 0x40, 0x40, 0x100 - 0x40)
+        );
+        assert_eq!(
+            header.sections[1],
             Section {
-                name: ".text".to_string(),
-                virtual_address: 0x40,
-                file_offset: 0x40,
-                size: 0x100 - 0x40
+                permissions: SectionPermissions::parse("r--").unwrap(),
+                kind: SectionKind::Data,
+                alignment: 8,
+                segment: Some("HEADER".to_string()),
+                ..Section::new(".rodata".to_string(), 0x20, 0x20, 0x20)
             }
         );
         assert_eq!(header.symbols[&0x40], "_start");
@@ -658,12 +666,7 @@ mod test {
         assert_eq!(header.entry, 0x50);
         assert_eq!(
             header.sections[0],
-            Section {
-                name: ".text".to_string(),
-                virtual_address: 0x40,
-                file_offset: 0x40,
-                size: 0x100 - 0x40
-            }
+            Section::new(".text".to_string(), 0x40, 0x40, 0x100 - 0x40)
         );
 
         plugin.try_parse_header(&mut app_context).unwrap();
@@ -672,16 +675,44 @@ mod test {
         assert_eq!(header.entry, 0x50);
         assert_eq!(
             header.sections[0],
-            Section {
-                name: ".text".to_string(),
-                virtual_address: 0x40,
-                file_offset: 0x40,
-                size: 0x100 - 0x40
-            }
+            Section::new(".text".to_string(), 0x40, 0x40, 0x100 - 0x40)
         );
         assert_eq!(header.symbols[&0x50], "_start");
     }
 
+    #[test]
+    fn test_parse_custom_invalid_section() {
+        for arguments in ["\"rwz\"", "\"r-x\", \"text\""] {
+            let source = format!(
+                "
+                function init(context)
+                    context.add_header_parser(\"test\")
+                end
+
+                function test(header_context, context)
+                    header_context:set_architecture(\"X86_64\")
+                    header_context:set_bitness(64)
+                    header_context:set_entry(0)
+                    header_context:add_section(\".text\", 0, 0, 0x10, {})
+                end
+                ",
+                arguments
+            );
+            let mut app = App::mockup(vec![0; 0x100]);
+            app.logger.clear();
+            let mut app_context = get_app_context!(app);
+            let mut plugin = Plugin::new_from_source(&source, &mut app_context).unwrap();
+            assert!(plugin.try_parse_header(&mut app_context).is_none());
+            let log = app_context.logger.iter().collect::<Vec<_>>();
+            assert!(
+                log.iter()
+                    .any(|message| message.message.contains("invalid")),
+                "{:?}",
+                log
+            );
+        }
+    }
+
     #[test]
     fn test_custom_popup() {
         let source = "
@@ -767,4 +798,57 @@ mod test {
         plugin.handle_with_error(event, &mut app_context).unwrap();
         assert_eq!(app.get_cursor_position().global_byte_index, 0x42);
     }
+
+    #[test]
+    fn test_bookmarks() {
+        let source = "
+            function init(context)
+                context.add_bookmark(\"start\", 0x10)
+                context.add_bookmark(\"end\", 0xF0)
+                context.add_bookmark(\"temp\", 0x20)
+                context.remove_bookmark(\"temp\")
+                local bookmarks = context.get_bookmarks()
+                if #bookmarks ~= 2 or bookmarks[1].name ~= \"start\" or bookmarks[2].file_address ~= 0xF0 then
+                    error(\"Wrong bookmarks\")
+                end
+                if pcall(context.add_bookmark, \"out\", 0x100) then
+                    error(\"Bookmark out of bounds\")
+                end
+            end
+        ";
+
+        let mut app = App::mockup(vec![0; 0x100]);
+        let mut app_context = get_app_context!(app);
+        Plugin::new_from_source(source, &mut app_context).unwrap();
+        assert_eq!(app.bookmarks.len(), 2);
+        assert_eq!(app.bookmarks.get("start").unwrap().file_address, 0x10);
+    }
+
+    #[test]
+    fn test_instruction_detail() {
+        let source = "
+            function init(context)
+                local instruction = context.current_instruction
+                if instruction.registers_read[1] ~= \"rbx\" or instruction.registers_written[1] ~= \"rax\" then
+                    error(\"Wrong registers\")
+                end
+                local operands = instruction.operands
+                if #operands ~= 2 or operands[1].type ~= \"register\" or operands[1].register ~= \"rax\" then
+                    error(\"Wrong register operand\")
+                end
+                local memory = operands[2]
+                if memory.type ~= \"memory\" or memory.base ~= \"rbx\" or memory.index ~= \"rcx\"
+                    or memory.scale ~= 8 or memory.displacement ~= 0x10 or memory.segment ~= nil then
+                    error(\"Wrong memory operand\")
+                end
+            end
+        ";
+
+        // mov rax, qword ptr [rbx + rcx*8 + 0x10]
+        let mut data = vec![0x48, 0x8B, 0x44, 0xCB, 0x10];
+        data.resize(0x100, 0x90);
+        let mut app = App::mockup(data);
+        let mut app_context = get_app_context!(app);
+        Plugin::new_from_source(source, &mut app_context).unwrap();
+    }
 }
@@ -80,11 +80,26 @@ impl App {
                     PopupState::InsertText { .. } => "InsertText",
                     PopupState::Patch { .. } => "Patch",
                     PopupState::JumpToAddress { .. } => "JumpToAddress",
+                    PopupState::MarkRange { .. } => "MarkRange",
+                    PopupState::Label { .. } => "Label",
+                    PopupState::Comment { .. } => "Comment",
+                    PopupState::SetBookmark { .. } => "SetBookmark",
+                    PopupState::Target { .. } => "Target",
                     PopupState::QuitDirtySave(_) => "QuitDirtySave",
                     PopupState::SaveAndQuit(_) => "SaveAndQuit",
                     PopupState::SaveAs { .. } => "SaveAs",
                     PopupState::Save(_) => "Save",
                     PopupState::Help(_) => "Help",
+                    PopupState::MemoryMap(_) => "MemoryMap",
+                    PopupState::InstructionDetail { .. } => "InstructionDetail",
+                    PopupState::Xrefs { .. } => "Xrefs",
+                    PopupState::Functions { .. } => "Functions",
+                    PopupState::Bookmarks { .. } => "Bookmarks",
+                    PopupState::Imports { .. } => "Imports",
+                    PopupState::CodeCaves { .. } => "CodeCaves",
+                    PopupState::Detour { .. } => "Detour",
+                    PopupState::SelectTemplate { .. } => "SelectTemplate",
+                    PopupState::Template { .. } => "Template",
                     PopupState::Custom { .. } => "Custom",
                 }
                 .into();
@@ -161,38 +176,54 @@ impl App {
                         relative_location,
                     })
                 }
-                InfoViewFrameInfo::AssemblyView { scroll } => {
-                    let (section, file_address, virtual_address, instruction) =
-                        if borders.top || borders.right {
-                            (None, None, None, None)
-                        } else {
-                            let instruction_offset = scroll + relative_location.y as usize - 1;
-                            if let Some(assembly_line) =
-                                self.assembly_instructions.get(instruction_offset)
-                            {
-                                match assembly_line {
-                                    AssemblyLine::Instruction(instruction_tag) => (
-                                        Some(
-                                            self.header
-                                                .get_text_section()
-                                                .map(|s| s.name.clone())
-                                                .unwrap_or(".text".into()),
-                                        ),
-                                        Some(instruction_tag.file_address),
-                                        Some(instruction_tag.instruction.ip()),
-                                        Some(instruction_tag.instruction.to_string()),
-                                    ),
-                                    AssemblyLine::SectionTag(section_tag) => (
-                                        Some(section_tag.name.clone()),
-                                        Some(section_tag.file_address),
-                                        Some(section_tag.virtual_address),
-                                        None,
+                InfoViewFrameInfo::AssemblyView { .. } | InfoViewFrameInfo::GraphView { .. } => {
+                    let (section, file_address, virtual_address, instruction) = if borders.top
+                        || borders.right
+                    {
+                        (None, None, None, None)
+                    } else {
+                        let row = relative_location.y as usize - 1;
+                        let line_index = match &self.last_frame_info.info_view_frame_info {
+                            InfoViewFrameInfo::AssemblyView { scroll } => self
+                                .get_assembly_view_rows(*scroll, row + 1)
+                                .get(row)
+                                .map(|row| row.line_index()),
+                            InfoViewFrameInfo::GraphView { scroll } => self
+                                .get_graph_view(
+                                    self.last_frame_info.info_view.width.saturating_sub(1) as usize,
+                                )
+                                .1
+                                .get(scroll + row)
+                                .copied()
+                                .flatten(),
+                            InfoViewFrameInfo::TextView => None,
+                        };
+                        if let Some(assembly_line) =
+                            line_index.and_then(|index| self.assembly_instructions.get(index))
+                        {
+                            match assembly_line {
+                                AssemblyLine::Instruction(instruction_tag) => (
+                                    Some(
+                                        self.header
+                                            .get_text_section()
+                                            .map(|s| s.name.clone())
+                                            .unwrap_or(".text".into()),
                                     ),
-                                }
-                            } else {
-                                (None, None, None, None)
+                                    Some(instruction_tag.file_address),
+                                    Some(instruction_tag.instruction.ip()),
+                                    Some(instruction_tag.instruction.to_string()),
+                                ),
+                                AssemblyLine::SectionTag(section_tag) => (
+                                    Some(section_tag.name.clone()),
+                                    Some(section_tag.file_address),
+                                    Some(section_tag.virtual_address),
+                                    None,
+                                ),
                             }
-                        };
+                        } else {
+                            (None, None, None, None)
+                        }
+                    };
                     Some(UiLocation {
                         info: UiLocationInfo::AssemblyView {
                             section,
@@ -5,11 +5,18 @@ use ratatui::text::{Line, Span, Text};
 use crate::get_app_context;
 
 use crate::app::{
-    asm::assembly_line::AssemblyLine,
+    asm::{
+        assembly_line::AssemblyLine, code_region::RangeKind, function::Function,
+        instruction_detail::InstructionDetail, xref::XrefKind,
+    },
+    bookmarks::Bookmark,
+    caves::{CodeCave, Detour},
     commands::command_info::CommandInfo,
     files::{path, path_result::PathResult},
+    imports::LinkedSymbol,
     plugins::popup_context::PopupContext,
     settings::color_settings::ColorSettings,
+    templates::template_node::TemplateNodeContent,
     App,
 };
 
@@ -55,6 +62,30 @@ pub enum PopupState {
         location: String,
         cursor: usize,
     },
+    MarkRange {
+        kind: RangeKind,
+        size: String,
+        cursor: usize,
+    },
+    Label {
+        address: u64,
+        label: String,
+        cursor: usize,
+    },
+    Comment {
+        address: u64,
+        comment: String,
+        cursor: usize,
+    },
+    SetBookmark {
+        address: u64,
+        name: String,
+        cursor: usize,
+    },
+    Target {
+        target: String,
+        cursor: usize,
+    },
     QuitDirtySave(SimpleChoice),
     SaveAndQuit(BinaryChoice),
     SaveAs {
@@ -63,6 +94,56 @@ pub enum PopupState {
     },
     Save(BinaryChoice),
     Help(usize),
+    MemoryMap(usize),
+    InstructionDetail {
+        detail: InstructionDetail,
+        scroll: usize,
+    },
+    Xrefs {
+        address: u64,
+        scroll: usize,
+    },
+    Functions {
+        filter: String,
+        cursor: usize,
+        results: Vec<Function>,
+        scroll: usize,
+    },
+    Bookmarks {
+        filter: String,
+        cursor: usize,
+        results: Vec<Bookmark>,
+        scroll: usize,
+    },
+    Imports {
+        filter: String,
+        cursor: usize,
+        results: Vec<LinkedSymbol>,
+        scroll: usize,
+    },
+    CodeCaves {
+        results: Vec<CodeCave>,
+        scroll: usize,
+        detour: bool,
+    },
+    Detour {
+        cave: CodeCave,
+        assembly: String,
+        preview: Result<Detour, String>,
+        cursor: usize,
+    },
+    SelectTemplate {
+        filter: String,
+        cursor: usize,
+        results: Vec<String>,
+        scroll: usize,
+    },
+    Template {
+        scroll: usize,
+        editing: bool,
+        value: String,
+        cursor: usize,
+    },
     Custom {
         plugin_index: usize,
         callback: String,
@@ -78,8 +159,18 @@ impl App {
             Some(PopupState::FindSymbol { .. }) => screen_height - 6 - 2,
             Some(PopupState::Log(_)) => screen_height - 4 - 2,
             Some(PopupState::Help(_)) => screen_height - 4 - 2,
+            Some(PopupState::MemoryMap(_)) => screen_height - 5 - 2,
+            Some(PopupState::InstructionDetail { .. }) => screen_height - 4 - 2,
+            Some(PopupState::Xrefs { .. }) => screen_height - 4 - 2,
+            Some(PopupState::Functions { .. }) => screen_height - 6 - 2,
+            Some(PopupState::Bookmarks { .. }) => screen_height - 6 - 2,
+            Some(PopupState::Imports { .. }) => screen_height - 6 - 2,
+            Some(PopupState::CodeCaves { .. }) => screen_height - 4 - 2,
             Some(PopupState::Patch { .. }) => screen_height - 6 - 2,
+            Some(PopupState::Detour { .. }) => screen_height - 7 - 2,
             Some(PopupState::InsertText { .. }) => screen_height - 5 - 2,
+            Some(PopupState::SelectTemplate { .. }) => screen_height - 6 - 2,
+            Some(PopupState::Template { .. }) => screen_height - 5 - 2,
             _ => unimplemented!("Popup is not supposed to have scrollable lines"),
         };
 
@@ -90,7 +181,7 @@ impl App {
         }
     }
 
-    pub(super) fn get_patch_preview(
+    pub(in crate::app) fn get_patch_preview(
         &self,
         color_settings: &ColorSettings,
         preview: &Result<Vec<u8>, String>,
@@ -99,6 +190,12 @@ impl App {
         match preview {
             Ok(preview) => {
                 let old_instruction = self.get_current_instruction();
+                let patch_offset = match old_instruction {
+                    Some(AssemblyLine::Instruction(instruction)) => instruction.file_address,
+                    _ => self.get_cursor_position().global_byte_index as u64,
+                };
+                let overwritten = self.instructions_overwritten(patch_offset, preview.len() as u64);
+                let relocations = self.relocations_overwritten(patch_offset, preview.len() as u64);
                 if let Some(old_instruction) = old_instruction {
                     if let AssemblyLine::Instruction(instruction) = old_instruction {
                         let old_bytes_offset = instruction.file_address as usize;
@@ -143,6 +240,22 @@ impl App {
                         }
                     }
                 }
+                if overwritten > 0 {
+                    preview_string.spans.push(Span::styled(
+                        format!(
+                            "Overwrites {} more instruction{} ",
+                            overwritten,
+                            if overwritten > 1 { "s" } else { "" }
+                        ),
+                        color_settings.log_warning,
+                    ));
+                }
+                if !relocations.is_empty() {
+                    preview_string.spans.push(Span::styled(
+                        format!("Relocated: {}", relocations.join(", ")),
+                        color_settings.log_warning,
+                    ));
+                }
             }
             Err(e) => {
                 preview_string
@@ -157,7 +270,14 @@ impl App {
         match popup {
             Some(PopupState::FindSymbol { scroll, .. })
             | Some(PopupState::Log(scroll))
-            | Some(PopupState::Help(scroll)) => {
+            | Some(PopupState::Help(scroll))
+            | Some(PopupState::MemoryMap(scroll))
+            | Some(PopupState::InstructionDetail { scroll, .. })
+            | Some(PopupState::Xrefs { scroll, .. })
+            | Some(PopupState::Functions { scroll, .. })
+            | Some(PopupState::Bookmarks { scroll, .. })
+            | Some(PopupState::Imports { scroll, .. })
+            | Some(PopupState::CodeCaves { scroll, .. }) => {
                 *scroll = 0;
             }
             _ => {}
@@ -476,7 +596,7 @@ impl App {
                 let mut selection = *scroll;
                 let symbols_len = if !symbols.is_empty() {
                     symbols.len()
-                } else if let Some(symbol_table) = self.header.get_symbols() {
+                } else if let Some(symbol_table) = self.annotations.symbols(&self.header) {
                     symbol_table.len()
                 } else {
                     0
@@ -499,10 +619,10 @@ impl App {
                     available_width,
                     true,
                 );
-                if self.header.get_symbols().is_some() {
+                if self.annotations.symbols(&self.header).is_some() {
                     let symbols_as_lines = if !symbols.is_empty() || filter.is_empty() {
                         let additional_vector = if filter.is_empty() {
-                            if let Some(symbol_table) = self.header.get_symbols() {
+                            if let Some(symbol_table) = self.annotations.symbols(&self.header) {
                                 symbol_table
                                     .iter()
                                     .skip(scroll)
@@ -691,7 +811,11 @@ impl App {
                 preview,
                 cursor,
             }) => {
-                *popup_title = "Patch".into();
+                *popup_title = if self.settings.app.fill_with_nops {
+                    "Patch (fill with NOPs)".into()
+                } else {
+                    "Patch".into()
+                };
                 let available_editable_text_lines = self.get_scrollable_popup_line_count();
                 *height = 6 + available_editable_text_lines;
                 let available_width = width.saturating_sub(2);
@@ -741,6 +865,63 @@ impl App {
                     popup_text.lines.push(Line::raw(""));
                 }
             }
+            Some(PopupState::Detour {
+                cave,
+                assembly,
+                preview,
+                cursor,
+            }) => {
+                *popup_title = format!("Detour through {:X}", cave.virtual_address);
+                let available_editable_text_lines = self.get_scrollable_popup_line_count();
+                *height = 7 + available_editable_text_lines;
+                let available_width = width.saturating_sub(2);
+                let (editable_lines, selected_line) = Self::get_multiline_from_string_and_cursor(
+                    &self.settings.color,
+                    assembly,
+                    *cursor,
+                    "Assembly",
+                    available_width,
+                );
+                popup_text.lines.extend(
+                    self.detour_preview_lines(cave, preview)
+                        .into_iter()
+                        .map(|line| line.left_aligned()),
+                );
+                popup_text.lines.push(Line::raw("─".repeat(*width)));
+                let skip_lines = 0
+                    .max(selected_line as isize - (available_editable_text_lines as isize - 1) / 2)
+                    as usize;
+                let skip_lines = skip_lines.min(
+                    editable_lines
+                        .len()
+                        .saturating_sub(available_editable_text_lines),
+                );
+                if skip_lines == 0 {
+                    popup_text.lines.push(Line::raw(""));
+                } else {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▲",
+                        self.settings.color.menu_text,
+                    )]));
+                }
+                let editable_lines_count = editable_lines.len();
+                popup_text.lines.extend(
+                    editable_lines
+                        .into_iter()
+                        .skip(skip_lines)
+                        .take(available_editable_text_lines),
+                );
+                if editable_lines_count as isize - skip_lines as isize
+                    > available_editable_text_lines as isize
+                {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▼",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+            }
             Some(PopupState::JumpToAddress {
                 location: address,
                 cursor,
@@ -760,6 +941,102 @@ impl App {
                     .lines
                     .extend(vec![editable_string.left_aligned()]);
             }
+            Some(PopupState::MarkRange { kind, size, cursor }) => {
+                *popup_title = format!(
+                    "Mark as {} from {:#X}",
+                    kind,
+                    self.get_cursor_position().global_byte_index
+                );
+                let available_width = width.saturating_sub(2);
+                *height = 3;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    size,
+                    *cursor,
+                    "Size",
+                    available_width,
+                    true,
+                );
+                popup_text
+                    .lines
+                    .extend(vec![editable_string.left_aligned()]);
+            }
+            Some(PopupState::Label {
+                address,
+                label,
+                cursor,
+            }) => {
+                *popup_title = format!("Label at {:#X}", address);
+                let available_width = width.saturating_sub(2);
+                *height = 3;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    label,
+                    *cursor,
+                    "Label",
+                    available_width,
+                    true,
+                );
+                popup_text
+                    .lines
+                    .extend(vec![editable_string.left_aligned()]);
+            }
+            Some(PopupState::Comment {
+                address,
+                comment,
+                cursor,
+            }) => {
+                *popup_title = format!("Comment at {:#X}", address);
+                let available_width = width.saturating_sub(2);
+                *height = 3;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    comment,
+                    *cursor,
+                    "Comment",
+                    available_width,
+                    true,
+                );
+                popup_text
+                    .lines
+                    .extend(vec![editable_string.left_aligned()]);
+            }
+            Some(PopupState::SetBookmark {
+                address,
+                name,
+                cursor,
+            }) => {
+                *popup_title = format!("Bookmark at {:#X}", address);
+                let available_width = width.saturating_sub(2);
+                *height = 3;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    name,
+                    *cursor,
+                    "Name",
+                    available_width,
+                    true,
+                );
+                popup_text
+                    .lines
+                    .extend(vec![editable_string.left_aligned()]);
+            }
+            Some(PopupState::Target { target, cursor }) => {
+                *popup_title = "Architecture".into();
+                let available_width = width.saturating_sub(2);
+                *height = 3;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    target,
+                    *cursor,
+                    "arch [mode] [endian] [base]",
+                    available_width,
+                    true,
+                );
+                popup_text
+                    .lines
+                    .extend(vec![editable_string.left_aligned()]);
+            }
             Some(PopupState::SaveAndQuit(choice)) => {
                 *popup_title = "Save and Quit".into();
                 popup_text.lines.extend(vec![
@@ -828,6 +1105,613 @@ impl App {
                     popup_text.lines.push(Line::raw(""));
                 }
             }
+            Some(PopupState::MemoryMap(scroll)) => {
+                let max_lines = self.get_scrollable_popup_line_count();
+                *height = max_lines + 5;
+                *width = 80;
+                *popup_title = "Memory Map".into();
+                let segments = self.header.get_segments();
+                popup_text.lines.push(
+                    Line::styled(
+                        format!(
+                            "{:<16} {:<35} {:<19} {}",
+                            "Name", "Virtual range", "File range", "Perm"
+                        ),
+                        self.settings.color.command_description,
+                    )
+                    .left_aligned(),
+                );
+                let skip = 0.max(*scroll as isize - max_lines as isize / 2) as usize;
+                let skip = skip.min(segments.len().saturating_sub(max_lines));
+                if skip > 0 {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▲",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                for (i, segment) in segments.iter().enumerate().skip(skip).take(max_lines) {
+                    let (name_style, range_style) = if i == *scroll {
+                        (
+                            self.settings.color.command_selected,
+                            self.settings.color.command_selected,
+                        )
+                    } else {
+                        (
+                            self.settings.color.command_name,
+                            self.settings.color.assembly_address,
+                        )
+                    };
+                    popup_text.lines.push(
+                        Line::from(vec![
+                            Span::styled(format!("{:<16} ", segment.name), name_style),
+                            Span::styled(
+                                format!(
+                                    "{:016X}-{:016X}  {:08X}-{:08X}  ",
+                                    segment.virtual_address,
+                                    segment.virtual_address + segment.virtual_size,
+                                    segment.file_offset,
+                                    segment.file_offset + segment.file_size
+                                ),
+                                range_style,
+                            ),
+                            Span::styled(segment.permissions.to_string(), name_style),
+                        ])
+                        .left_aligned(),
+                    );
+                }
+                for _ in 0..(max_lines as isize - segments.len() as isize) {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                if segments.len() as isize - skip as isize > max_lines as isize {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▼",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+            }
+            Some(PopupState::InstructionDetail { detail, scroll }) => {
+                let max_lines = self.get_scrollable_popup_line_count();
+                *height = max_lines + 4;
+                *width = 80;
+                *popup_title = "Instruction Detail".into();
+                let lines = detail.to_lines(&self.settings.color);
+                if *scroll > 0 {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▲",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                let len = lines.len();
+                popup_text
+                    .lines
+                    .extend(lines.into_iter().skip(*scroll).take(max_lines));
+                for _ in 0..(max_lines as isize - (len as isize - *scroll as isize)) {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                if len as isize - *scroll as isize > max_lines as isize {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▼",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+            }
+            Some(PopupState::Xrefs { address, scroll }) => {
+                let max_lines = self.get_scrollable_popup_line_count();
+                *height = max_lines + 4;
+                *width = 80;
+                *popup_title = match self
+                    .annotations
+                    .symbols(&self.header)
+                    .and_then(|symbols| symbols.get(address))
+                {
+                    Some(symbol) => format!("References to {}", symbol),
+                    None => format!("References to {:#X}", address),
+                };
+                let xrefs = self.xrefs.get(*address);
+                let skip = 0.max(*scroll as isize - max_lines as isize / 2) as usize;
+                let skip = skip.min(xrefs.len().saturating_sub(max_lines));
+                if skip > 0 {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▲",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                for (i, xref) in xrefs.iter().enumerate().skip(skip).take(max_lines) {
+                    let (kind_style, text_style) = if i == *scroll {
+                        (
+                            self.settings.color.command_selected,
+                            self.settings.color.command_selected,
+                        )
+                    } else {
+                        (
+                            self.settings.color.command_name,
+                            self.settings.color.command_description,
+                        )
+                    };
+                    let text = match self
+                        .assembly_offsets
+                        .get(xref.file_address as usize)
+                        .map(|index| &self.assembly_instructions[*index])
+                    {
+                        Some(AssemblyLine::Instruction(instruction))
+                            if xref.kind != XrefKind::Pointer =>
+                        {
+                            instruction.instruction.to_string()
+                        }
+                        _ => String::new(),
+                    };
+                    popup_text.lines.push(
+                        Line::from(vec![
+                            Span::styled(
+                                format!("{:08X} ", xref.file_address),
+                                self.settings.color.assembly_address,
+                            ),
+                            Span::styled(
+                                format!("@{:<16X} ", xref.virtual_address),
+                                self.settings.color.assembly_virtual_address,
+                            ),
+                            Span::styled(format!("{:<8}", xref.kind), kind_style),
+                            Span::styled(text, text_style),
+                        ])
+                        .left_aligned(),
+                    );
+                }
+                for _ in 0..(max_lines as isize - xrefs.len() as isize) {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                if xrefs.len() as isize - skip as isize > max_lines as isize {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▼",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+            }
+            Some(PopupState::Functions {
+                filter,
+                cursor,
+                results,
+                scroll,
+            }) => {
+                *popup_title = format!("Functions ({})", self.functions.len());
+                *width = 80;
+                let available_width = width.saturating_sub(2);
+                let max_results = self.get_scrollable_popup_line_count();
+                *height = max_results + 2 + 4;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    filter,
+                    *cursor,
+                    "Filter",
+                    available_width,
+                    true,
+                );
+                popup_text.lines.extend(vec![
+                    editable_string.left_aligned(),
+                    Line::raw("─".repeat(*width)),
+                ]);
+                if results.is_empty() {
+                    popup_text
+                        .lines
+                        .push(Line::raw("No functions found.").left_aligned());
+                } else {
+                    let skip = 0.max(*scroll as isize - max_results as isize / 2) as usize;
+                    let skip = skip.min(results.len().saturating_sub(max_results));
+                    let relative_scroll = *scroll - skip;
+                    if skip > 0 {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▲",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    let name_width = available_width.saturating_sub(40).max(8);
+                    popup_text.lines.extend(
+                        results.iter().skip(skip).take(max_results).enumerate().map(
+                            |(i, function)| {
+                                let (name_style, info_style) = if relative_scroll == i {
+                                    (
+                                        self.settings.color.command_selected,
+                                        self.settings.color.command_selected,
+                                    )
+                                } else {
+                                    (
+                                        self.settings.color.command_name,
+                                        self.settings.color.command_description,
+                                    )
+                                };
+                                let name = if function.name.chars().count() > name_width {
+                                    let mut name = function
+                                        .name
+                                        .chars()
+                                        .take(name_width - 1)
+                                        .collect::<String>();
+                                    name.push('…');
+                                    name
+                                } else {
+                                    function.name.clone()
+                                };
+                                Line::from(vec![
+                                    Span::styled(format!("{:<name_width$} ", name), name_style),
+                                    Span::styled(
+                                        format!("@{:<16X} ", function.virtual_address),
+                                        self.settings.color.assembly_virtual_address,
+                                    ),
+                                    Span::styled(
+                                        format!(
+                                            "{:>8}B {:>5} xrefs",
+                                            function.size,
+                                            self.xrefs.count(function.virtual_address)
+                                        ),
+                                        info_style,
+                                    ),
+                                ])
+                                .left_aligned()
+                            },
+                        ),
+                    );
+                    if results.len() as isize - skip as isize > max_results as isize {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▼",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                }
+            }
+            Some(PopupState::Bookmarks {
+                filter,
+                cursor,
+                results,
+                scroll,
+            }) => {
+                *popup_title = format!("Bookmarks ({})", self.bookmarks.len());
+                *width = 80;
+                let available_width = width.saturating_sub(2);
+                let max_results = self.get_scrollable_popup_line_count();
+                *height = max_results + 2 + 4;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    filter,
+                    *cursor,
+                    "Filter",
+                    available_width,
+                    true,
+                );
+                popup_text.lines.extend(vec![
+                    editable_string.left_aligned(),
+                    Line::raw("─".repeat(*width)),
+                ]);
+                if results.is_empty() {
+                    popup_text
+                        .lines
+                        .push(Line::raw("No bookmarks found.").left_aligned());
+                } else {
+                    let skip = 0.max(*scroll as isize - max_results as isize / 2) as usize;
+                    let skip = skip.min(results.len().saturating_sub(max_results));
+                    let relative_scroll = *scroll - skip;
+                    if skip > 0 {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▲",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    let name_width = results
+                        .iter()
+                        .map(|bookmark| bookmark.name.chars().count())
+                        .max()
+                        .unwrap_or_default()
+                        .clamp(4, 16);
+                    popup_text.lines.extend(
+                        results.iter().skip(skip).take(max_results).enumerate().map(
+                            |(i, bookmark)| {
+                                self.bookmark_to_line(bookmark, relative_scroll == i, name_width)
+                                    .left_aligned()
+                            },
+                        ),
+                    );
+                    if results.len() as isize - skip as isize > max_results as isize {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▼",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                }
+            }
+            Some(PopupState::Imports {
+                filter,
+                cursor,
+                results,
+                scroll,
+            }) => {
+                *popup_title = format!(
+                    "Imports ({}) and Exports ({})",
+                    self.header.get_imports().len(),
+                    self.header.get_exports().len()
+                );
+                *width = 80;
+                let available_width = width.saturating_sub(2);
+                let max_results = self.get_scrollable_popup_line_count();
+                *height = max_results + 2 + 4;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    filter,
+                    *cursor,
+                    "Filter",
+                    available_width,
+                    true,
+                );
+                popup_text.lines.extend(vec![
+                    editable_string.left_aligned(),
+                    Line::raw("─".repeat(*width)),
+                ]);
+                if results.is_empty() {
+                    popup_text
+                        .lines
+                        .push(Line::raw("No imports or exports found.").left_aligned());
+                } else {
+                    let skip = 0.max(*scroll as isize - max_results as isize / 2) as usize;
+                    let skip = skip.min(results.len().saturating_sub(max_results));
+                    let relative_scroll = *scroll - skip;
+                    if skip > 0 {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▲",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    popup_text.lines.extend(
+                        results.iter().skip(skip).take(max_results).enumerate().map(
+                            |(i, symbol)| {
+                                self.linked_symbol_to_line(symbol, relative_scroll == i)
+                                    .left_aligned()
+                            },
+                        ),
+                    );
+                    if results.len() as isize - skip as isize > max_results as isize {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▼",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                }
+            }
+            Some(PopupState::CodeCaves {
+                results,
+                scroll,
+                detour,
+            }) => {
+                *popup_title = if *detour {
+                    "Choose the code cave of the detour".into()
+                } else {
+                    format!("Code caves ({})", results.len())
+                };
+                *width = 80;
+                let max_lines = self.get_scrollable_popup_line_count();
+                *height = max_lines + 4;
+                let skip = 0.max(*scroll as isize - max_lines as isize / 2) as usize;
+                let skip = skip.min(results.len().saturating_sub(max_lines));
+                if skip > 0 {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▲",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+                popup_text.lines.extend(
+                    results
+                        .iter()
+                        .enumerate()
+                        .skip(skip)
+                        .take(max_lines)
+                        .map(|(i, cave)| self.code_cave_to_line(cave, i == *scroll).left_aligned()),
+                );
+                if results.len() as isize - skip as isize > max_lines as isize {
+                    popup_text.lines.push(Line::from(vec![Span::styled(
+                        "▼",
+                        self.settings.color.menu_text,
+                    )]));
+                } else {
+                    popup_text.lines.push(Line::raw(""));
+                }
+            }
+            Some(PopupState::SelectTemplate {
+                filter,
+                cursor,
+                results,
+                scroll,
+            }) => {
+                *popup_title = format!(
+                    "Apply Template at {:#X}",
+                    self.get_cursor_position().global_byte_index
+                );
+                let available_width = width.saturating_sub(2);
+                let max_results = self.get_scrollable_popup_line_count();
+                *height = max_results + 2 + 4;
+                let editable_string = Self::get_line_from_string_and_cursor(
+                    &self.settings.color,
+                    filter,
+                    *cursor,
+                    "Filter",
+                    available_width,
+                    true,
+                );
+                popup_text.lines.extend(vec![
+                    editable_string.left_aligned(),
+                    Line::raw("─".repeat(*width)),
+                ]);
+                if results.is_empty() {
+                    popup_text
+                        .lines
+                        .push(Line::raw("No templates found.").left_aligned());
+                } else {
+                    let skip = 0.max(*scroll as isize - max_results as isize / 2) as usize;
+                    let skip = skip.min(results.len().saturating_sub(max_results));
+                    let relative_scroll = *scroll - skip;
+                    if skip > 0 {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▲",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    popup_text.lines.extend(
+                        results
+                            .iter()
+                            .skip(skip)
+                            .take(max_results)
+                            .enumerate()
+                            .map(|(i, name)| {
+                                Line::styled(
+                                    name.clone(),
+                                    if relative_scroll == i {
+                                        self.settings.color.command_selected
+                                    } else {
+                                        self.settings.color.command_name
+                                    },
+                                )
+                                .left_aligned()
+                            }),
+                    );
+                    if results.len() as isize - skip as isize > max_results as isize {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▼",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                }
+            }
+            Some(PopupState::Template {
+                scroll,
+                editing,
+                value,
+                cursor,
+            }) => {
+                let max_lines = self.get_scrollable_popup_line_count();
+                *height = max_lines + 5;
+                *width = 80;
+                if let Some(applied) = &self.template_manager.applied {
+                    *popup_title = format!("Template: {}", applied.template.name);
+                    let rows = applied.visible_rows();
+                    let skip = 0.max(*scroll as isize - max_lines as isize / 2) as usize;
+                    let skip = skip.min(rows.len().saturating_sub(max_lines));
+                    if skip > 0 {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▲",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    for (i, row) in rows.iter().enumerate().skip(skip).take(max_lines) {
+                        let selected = i == *scroll;
+                        let (name_style, type_style, value_style) = if selected && !*editing {
+                            (
+                                self.settings.color.command_selected,
+                                self.settings.color.command_selected,
+                                self.settings.color.command_selected,
+                            )
+                        } else {
+                            (
+                                self.settings.color.command_name,
+                                self.settings.color.command_description,
+                                self.settings.color.menu_text,
+                            )
+                        };
+                        let marker = if row.node.is_leaf() {
+                            "  "
+                        } else if row.expanded {
+                            "▾ "
+                        } else {
+                            "▸ "
+                        };
+                        let mut line = Line::from(vec![
+                            Span::styled(
+                                format!("{:08X} ", row.node.offset),
+                                self.settings.color.assembly_address,
+                            ),
+                            Span::raw("  ".repeat(row.depth)),
+                            Span::styled(marker, value_style),
+                            Span::styled(row.node.name.clone(), name_style),
+                            Span::styled(format!(" {}", row.node.type_name), type_style),
+                        ]);
+                        if let TemplateNodeContent::Value { value: v, .. } = &row.node.content {
+                            line.spans.push(Span::styled(" = ", value_style));
+                            if selected && *editing {
+                                let used_width = line.width();
+                                line.spans.extend(
+                                    Self::get_line_from_string_and_cursor(
+                                        &self.settings.color,
+                                        value,
+                                        *cursor,
+                                        "Value",
+                                        width.saturating_sub(used_width + 2),
+                                        true,
+                                    )
+                                    .spans,
+                                );
+                            } else {
+                                line.spans.push(Span::styled(v.to_string(), value_style));
+                            }
+                        }
+                        popup_text.lines.push(line.left_aligned());
+                    }
+                    for _ in 0..(max_lines as isize - rows.len() as isize) {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    if rows.len() as isize - skip as isize > max_lines as isize {
+                        popup_text.lines.push(Line::from(vec![Span::styled(
+                            "▼",
+                            self.settings.color.menu_text,
+                        )]));
+                    } else {
+                        popup_text.lines.push(Line::raw(""));
+                    }
+                    let status = match rows.get(*scroll) {
+                        Some(row) => {
+                            format!("Offset: {:#X} Size: {:#X}", row.node.offset, row.node.size)
+                        }
+                        None => String::new(),
+                    };
+                    let padding = width.saturating_sub(status.len());
+                    popup_text.lines.push(
+                        Line::styled(
+                            format!("{}{}", status, " ".repeat(padding)),
+                            self.settings.color.insert_text_status,
+                        )
+                        .left_aligned(),
+                    );
+                } else {
+                    *popup_title = "Template".into();
+                    popup_text
+                        .lines
+                        .push(Line::raw("No template applied.").left_aligned());
+                }
+            }
             Some(PopupState::Custom {
                 plugin_index,
                 callback,
@@ -1,14 +1,64 @@
+use std::{fmt::Display, str::FromStr};
+
 use mlua::UserDataRegistry;
 use serde::{Deserialize, Serialize};
 
 use super::Settings;
 
+/// The syntax used to disassemble and assemble x86 code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum X86Syntax {
+    #[default]
+    Intel,
+    Att,
+    Masm,
+}
+
+impl X86Syntax {
+    /// The syntax that follows this one, used to switch between them.
+    pub fn next(self) -> Self {
+        match self {
+            X86Syntax::Intel => X86Syntax::Att,
+            X86Syntax::Att => X86Syntax::Masm,
+            X86Syntax::Masm => X86Syntax::Intel,
+        }
+    }
+}
+
+impl Display for X86Syntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            X86Syntax::Intel => write!(f, "intel"),
+            X86Syntax::Att => write!(f, "att"),
+            X86Syntax::Masm => write!(f, "masm"),
+        }
+    }
+}
+
+impl FromStr for X86Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "intel" => Ok(X86Syntax::Intel),
+            "att" => Ok(X86Syntax::Att),
+            "masm" => Ok(X86Syntax::Masm),
+            _ => Err(format!("Unknown x86 syntax \"{}\"", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppSettings {
     pub history_limit: usize,
     pub log_limit: usize,
     pub theme: Option<String>,
+    pub linear_sweep: bool,
+    pub x86_syntax: X86Syntax,
+    pub fill_with_nops: bool,
+    pub min_cave_size: usize,
 }
 
 impl AppSettings {
@@ -35,6 +85,50 @@ impl AppSettings {
                 Ok(())
             },
         );
+        mlua::UserDataFields::add_field_method_get(data, "app_linear_sweep", |_lua, settings| {
+            Ok(settings.app.linear_sweep)
+        });
+        mlua::UserDataFields::add_field_method_set(
+            data,
+            "app_linear_sweep",
+            |_lua, settings, value| {
+                settings.app.linear_sweep = value;
+                Ok(())
+            },
+        );
+        mlua::UserDataFields::add_field_method_get(data, "app_x86_syntax", |_lua, settings| {
+            Ok(settings.app.x86_syntax.to_string())
+        });
+        mlua::UserDataFields::add_field_method_set(
+            data,
+            "app_x86_syntax",
+            |_lua, settings, value: String| {
+                settings.app.x86_syntax = value.parse().map_err(mlua::Error::external)?;
+                Ok(())
+            },
+        );
+        mlua::UserDataFields::add_field_method_get(data, "app_fill_with_nops", |_lua, settings| {
+            Ok(settings.app.fill_with_nops)
+        });
+        mlua::UserDataFields::add_field_method_set(
+            data,
+            "app_fill_with_nops",
+            |_lua, settings, value| {
+                settings.app.fill_with_nops = value;
+                Ok(())
+            },
+        );
+        mlua::UserDataFields::add_field_method_get(data, "app_min_cave_size", |_lua, settings| {
+            Ok(settings.app.min_cave_size)
+        });
+        mlua::UserDataFields::add_field_method_set(
+            data,
+            "app_min_cave_size",
+            |_lua, settings, value| {
+                settings.app.min_cave_size = value;
+                Ok(())
+            },
+        );
     }
 }
 
@@ -44,6 +138,10 @@ impl Default for AppSettings {
             history_limit: 1024,
             log_limit: 1024,
             theme: None,
+            linear_sweep: false,
+            x86_syntax: X86Syntax::Intel,
+            fill_with_nops: false,
+            min_cave_size: 16,
         }
     }
 }
@@ -24,7 +24,12 @@ pub struct ColorSettings {
     pub hex_whitespace: Style,
     pub hex_current_instruction: Style,
     pub hex_current_section: Style,
+    pub hex_template_field: Style,
+    pub hex_template_field_alternate: Style,
     pub hex_default: Style,
+    pub hex_comment: Style,
+    pub hex_bookmark: Style,
+    pub hex_relocation: Style,
 
     pub text_selected: Style,
 
@@ -37,6 +42,13 @@ pub struct ColorSettings {
     pub assembly_section: Style,
     pub assembly_entry_point: Style,
     pub assembly_default: Style,
+    pub assembly_comment: Style,
+
+    pub graph_block: Style,
+    pub graph_selected_block: Style,
+    pub graph_edge: Style,
+    pub graph_true_edge: Style,
+    pub graph_false_edge: Style,
 
     pub patch_patched_less_or_equal: Style,
     pub patch_patched_greater: Style,
@@ -77,6 +89,7 @@ pub struct ColorSettings {
     pub status_error: Style,
 
     pub scrollbar: Style,
+    pub scrollbar_bookmark: Style,
     pub placeholder: Style,
 }
 
@@ -106,7 +119,12 @@ impl ColorSettings {
             hex_whitespace: Style::default().fg(desaturated_dark_brown),
             hex_current_instruction: Style::default().fg(Color::White).bg(dark_orange),
             hex_current_section: Style::default().fg(Color::White).bg(dark_orange),
+            hex_template_field: Style::default().bg(Color::Rgb(204, 224, 245)),
+            hex_template_field_alternate: Style::default().bg(Color::Rgb(226, 208, 245)),
             hex_default: Style::default(),
+            hex_comment: Style::default().add_modifier(Modifier::UNDERLINED),
+            hex_bookmark: Style::default().bg(Color::LightCyan),
+            hex_relocation: Style::default().bg(Color::Rgb(245, 214, 214)),
 
             text_selected: Style::default().fg(Color::White).bg(Color::Black),
 
@@ -119,6 +137,15 @@ impl ColorSettings {
             assembly_section: Style::default().fg(Color::Blue),
             assembly_entry_point: Style::default().fg(dark_yellow),
             assembly_default: Style::default().fg(light_brown),
+            assembly_comment: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::ITALIC),
+
+            graph_block: Style::default().fg(Color::Gray),
+            graph_selected_block: Style::default().fg(dark_orange),
+            graph_edge: Style::default().fg(Color::Blue),
+            graph_true_edge: Style::default().fg(Color::Green),
+            graph_false_edge: Style::default().fg(Color::Red),
 
             patch_patched_less_or_equal: Style::default().fg(Color::Green),
             patch_patched_greater: Style::default().fg(dark_yellow),
@@ -159,6 +186,7 @@ impl ColorSettings {
             status_error: Style::default().fg(Color::Red).bg(status_bar_bg),
 
             scrollbar: Style::default().fg(status_bar_bg).bg(Color::Gray),
+            scrollbar_bookmark: Style::default().fg(Color::Blue).bg(Color::Gray),
             placeholder: Style::default().fg(Color::Gray),
         }
     }
@@ -183,7 +211,12 @@ impl ColorSettings {
             hex_current_section: Style::default()
                 .fg(Color::Black)
                 .bg(Color::Rgb(215, 170, 92)),
+            hex_template_field: Style::default().bg(Color::Rgb(38, 58, 88)),
+            hex_template_field_alternate: Style::default().bg(Color::Rgb(64, 44, 88)),
             hex_default: Style::default(),
+            hex_comment: Style::default().add_modifier(Modifier::UNDERLINED),
+            hex_bookmark: Style::default().fg(Color::Black).bg(Color::Cyan),
+            hex_relocation: Style::default().bg(Color::Rgb(88, 38, 38)),
 
             text_selected: Style::default().fg(Color::Black).bg(Color::White),
 
@@ -198,6 +231,15 @@ impl ColorSettings {
             assembly_section: Style::default().fg(Color::LightBlue),
             assembly_entry_point: Style::default().fg(Color::Yellow),
             assembly_default: Style::default().fg(Color::Rgb(204, 152, 113)),
+            assembly_comment: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+
+            graph_block: Style::default().fg(Color::DarkGray),
+            graph_selected_block: Style::default().fg(Color::Rgb(215, 170, 92)),
+            graph_edge: Style::default().fg(Color::LightBlue),
+            graph_true_edge: Style::default().fg(Color::LightGreen),
+            graph_false_edge: Style::default().fg(Color::LightRed),
 
             patch_patched_less_or_equal: Style::default().fg(Color::Green),
             patch_patched_greater: Style::default().fg(Color::Yellow),
@@ -238,6 +280,7 @@ impl ColorSettings {
             status_error: Style::default().fg(Color::Red).bg(status_bar_bg),
 
             scrollbar: Style::default().fg(status_bar_bg).bg(Color::DarkGray),
+            scrollbar_bookmark: Style::default().fg(Color::LightCyan).bg(Color::DarkGray),
             placeholder: Style::default().fg(Color::DarkGray),
         }
     }
@@ -47,6 +47,15 @@ pub struct KeySettings {
 
     pub undo: KeyEvent,
     pub redo: KeyEvent,
+
+    pub xrefs: KeyEvent,
+    pub follow: KeyEvent,
+    pub back: KeyEvent,
+    pub forward: KeyEvent,
+    pub label: KeyEvent,
+    pub comment: KeyEvent,
+    pub set_bookmark: KeyEvent,
+    pub bookmarks: KeyEvent,
 }
 
 impl KeySettings {
@@ -241,6 +250,15 @@ impl Default for KeySettings {
 
             undo: KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL),
             redo: KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL),
+
+            xrefs: KeyEvent::new(KeyCode::Char('x'), KeyModifiers::empty()),
+            follow: KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()),
+            back: KeyEvent::new(KeyCode::Left, KeyModifiers::ALT),
+            forward: KeyEvent::new(KeyCode::Right, KeyModifiers::ALT),
+            label: KeyEvent::new(KeyCode::Char('n'), KeyModifiers::empty()),
+            comment: KeyEvent::new(KeyCode::Char(';'), KeyModifiers::empty()),
+            set_bookmark: KeyEvent::new(KeyCode::Char('m'), KeyModifiers::empty()),
+            bookmarks: KeyEvent::new(KeyCode::Char('\''), KeyModifiers::empty()),
         }
     }
 }
@@ -214,6 +214,18 @@ mod test {
         assert_eq!(settings.unwrap(), Settings::default());
     }
 
+    #[test]
+    fn test_default_app_settings() {
+        // the fixture lists every app setting with its serialized name and default value
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("test/default_settings.json").unwrap())
+                .unwrap();
+        assert_eq!(
+            fixture["app"],
+            serde_json::to_value(AppSettings::default()).unwrap()
+        );
+    }
+
     #[test]
     fn test_settings_partial_load() {
         let settings = Settings::load(
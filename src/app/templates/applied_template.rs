@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use object::Endianness;
+
+use super::{template::Template, template_node::TemplateNode};
+
+/// A row of the field tree as it is shown in the template popup.
+#[derive(Debug, Clone)]
+pub struct TemplateRow<'a> {
+    pub depth: usize,
+    pub path: String,
+    pub node: &'a TemplateNode,
+    pub expanded: bool,
+}
+
+/// A template that was applied at a given offset of the file.
+#[derive(Debug, Clone)]
+pub struct AppliedTemplate {
+    pub template: Template,
+    pub base: usize,
+    pub endianness: Endianness,
+    pub root: TemplateNode,
+    /// The paths of the expanded structs and arrays, the root is always expanded.
+    pub expanded: HashSet<String>,
+    /// The sorted ranges of every value in the tree, used to highlight the hex view.
+    pub leaf_ranges: Vec<(usize, usize)>,
+}
+
+impl AppliedTemplate {
+    pub fn new(
+        template: Template,
+        bytes: &[u8],
+        base: usize,
+        endianness: Endianness,
+    ) -> Result<Self, String> {
+        let root = template.apply(bytes, base, endianness)?;
+        let mut ret = Self {
+            template,
+            base,
+            endianness,
+            root,
+            expanded: HashSet::new(),
+            leaf_ranges: Vec::new(),
+        };
+        ret.update_leaf_ranges();
+        Ok(ret)
+    }
+
+    fn update_leaf_ranges(&mut self) {
+        self.leaf_ranges.clear();
+        self.root.collect_leaf_ranges(&mut self.leaf_ranges);
+        self.leaf_ranges.sort();
+    }
+
+    /// Applies the template again, e.g. after the bytes were modified.
+    pub fn reapply(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.root = self.template.apply(bytes, self.base, self.endianness)?;
+        self.update_leaf_ranges();
+        Ok(())
+    }
+
+    fn push_rows<'a>(
+        &self,
+        node: &'a TemplateNode,
+        depth: usize,
+        path: String,
+        rows: &mut Vec<TemplateRow<'a>>,
+    ) {
+        for child in node.children() {
+            let child_path = if path.is_empty() {
+                child.name.clone()
+            } else {
+                format!("{}.{}", path, child.name)
+            };
+            let expanded = self.expanded.contains(&child_path);
+            rows.push(TemplateRow {
+                depth,
+                path: child_path.clone(),
+                node: child,
+                expanded,
+            });
+            if expanded {
+                self.push_rows(child, depth + 1, child_path, rows);
+            }
+        }
+    }
+
+    /// The rows that are currently visible, the children of collapsed nodes are skipped.
+    pub fn visible_rows(&self) -> Vec<TemplateRow<'_>> {
+        let mut rows = Vec::new();
+        self.push_rows(&self.root, 0, String::new(), &mut rows);
+        rows
+    }
+
+    pub fn set_expanded(&mut self, path: &str, expanded: bool) {
+        if expanded {
+            self.expanded.insert(path.to_string());
+        } else {
+            self.expanded.remove(path);
+        }
+    }
+
+    /// Returns the index of the first field containing `offset`, if any.
+    pub fn leaf_range_index_at(&self, offset: usize) -> Option<usize> {
+        let index = self
+            .leaf_ranges
+            .partition_point(|(start, _)| *start <= offset);
+        self.leaf_ranges[..index]
+            .iter()
+            .rposition(|(start, size)| offset < start + size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_visible_rows() {
+        let template = Template::from_json(
+            r#"{
+                "name": "test",
+                "structs": { "pair": [
+                    { "name": "a", "type": "u8" },
+                    { "name": "b", "type": "u8" }
+                ] },
+                "fields": [
+                    { "name": "first", "type": "u16" },
+                    { "name": "pairs", "type": "pair", "count": 2 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let bytes = [0, 1, 2, 3, 4, 5];
+        let mut applied = AppliedTemplate::new(template, &bytes, 0, Endianness::Little).unwrap();
+        assert_eq!(applied.visible_rows().len(), 2);
+        applied.set_expanded("pairs", true);
+        assert_eq!(applied.visible_rows().len(), 4);
+        applied.set_expanded("pairs.pairs[1]", true);
+        let rows = applied.visible_rows();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[4].path, "pairs.pairs[1].a");
+        assert_eq!(rows[4].depth, 2);
+        assert_eq!(rows[4].node.offset, 4);
+        applied.set_expanded("pairs", false);
+        assert_eq!(applied.visible_rows().len(), 2);
+
+        assert_eq!(applied.leaf_ranges.len(), 5);
+        assert_eq!(applied.leaf_range_index_at(1), Some(0));
+        assert_eq!(applied.leaf_range_index_at(5), Some(4));
+        assert_eq!(applied.leaf_range_index_at(6), None);
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Display;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    BitOr,
+    BitAnd,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Or => 1,
+            Operator::And => 2,
+            Operator::Equal
+            | Operator::NotEqual
+            | Operator::Less
+            | Operator::LessOrEqual
+            | Operator::Greater
+            | Operator::GreaterOrEqual => 3,
+            Operator::BitOr => 4,
+            Operator::BitAnd => 5,
+            Operator::Add | Operator::Sub => 6,
+            Operator::Mul | Operator::Div => 7,
+        }
+    }
+
+    fn apply(&self, lhs: i128, rhs: i128) -> Result<i128, String> {
+        Ok(match self {
+            Operator::Or => (lhs != 0 || rhs != 0) as i128,
+            Operator::And => (lhs != 0 && rhs != 0) as i128,
+            Operator::Equal => (lhs == rhs) as i128,
+            Operator::NotEqual => (lhs != rhs) as i128,
+            Operator::Less => (lhs < rhs) as i128,
+            Operator::LessOrEqual => (lhs <= rhs) as i128,
+            Operator::Greater => (lhs > rhs) as i128,
+            Operator::GreaterOrEqual => (lhs >= rhs) as i128,
+            Operator::BitOr => lhs | rhs,
+            Operator::BitAnd => lhs & rhs,
+            Operator::Add => lhs.checked_add(rhs).ok_or("Overflow in expression")?,
+            Operator::Sub => lhs.checked_sub(rhs).ok_or("Overflow in expression")?,
+            Operator::Mul => lhs.checked_mul(rhs).ok_or("Overflow in expression")?,
+            Operator::Div => lhs
+                .checked_div(rhs)
+                .ok_or("Division by zero in expression")?,
+        })
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Operator::Or => "||",
+            Operator::And => "&&",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::BitOr => "|",
+            Operator::BitAnd => "&",
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i128),
+    Identifier(String),
+    Operator(Operator),
+    Not,
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+/// An expression used in templates to compute counts, offsets and conditions.
+/// It can reference the values of fields that were already parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "ExpressionSource")]
+pub enum Expression {
+    Number(i128),
+    Field(String),
+    Not(Box<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExpressionSource {
+    Number(u64),
+    Text(String),
+}
+
+impl TryFrom<ExpressionSource> for Expression {
+    type Error = String;
+
+    fn try_from(value: ExpressionSource) -> Result<Self, Self::Error> {
+        match value {
+            ExpressionSource::Number(n) => Ok(Expression::Number(n as i128)),
+            ExpressionSource::Text(s) => Expression::parse(&s),
+        }
+    }
+}
+
+impl Expression {
+    fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+        let chars = s.chars().collect::<Vec<_>>();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+            } else if c.is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let literal = chars[start..i]
+                    .iter()
+                    .filter(|c| **c != '_')
+                    .collect::<String>();
+                let number = if let Some(hex) = literal
+                    .strip_prefix("0x")
+                    .or_else(|| literal.strip_prefix("0X"))
+                {
+                    i128::from_str_radix(hex, 16)
+                } else {
+                    literal.parse::<i128>()
+                }
+                .map_err(|_| format!("Invalid number \"{}\"", literal))?;
+                tokens.push(Token::Number(number));
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.' | '[' | ']'))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Identifier(chars[start..i].iter().collect()));
+            } else {
+                let next = chars.get(i + 1).copied();
+                let (token, len) = match (c, next) {
+                    ('|', Some('|')) => (Token::Operator(Operator::Or), 2),
+                    ('&', Some('&')) => (Token::Operator(Operator::And), 2),
+                    ('=', Some('=')) => (Token::Operator(Operator::Equal), 2),
+                    ('!', Some('=')) => (Token::Operator(Operator::NotEqual), 2),
+                    ('<', Some('=')) => (Token::Operator(Operator::LessOrEqual), 2),
+                    ('>', Some('=')) => (Token::Operator(Operator::GreaterOrEqual), 2),
+                    ('<', _) => (Token::Operator(Operator::Less), 1),
+                    ('>', _) => (Token::Operator(Operator::Greater), 1),
+                    ('|', _) => (Token::Operator(Operator::BitOr), 1),
+                    ('&', _) => (Token::Operator(Operator::BitAnd), 1),
+                    ('+', _) => (Token::Operator(Operator::Add), 1),
+                    ('-', _) => (Token::Operator(Operator::Sub), 1),
+                    ('*', _) => (Token::Operator(Operator::Mul), 1),
+                    ('/', _) => (Token::Operator(Operator::Div), 1),
+                    ('!', _) => (Token::Not, 1),
+                    ('(', _) => (Token::OpenParenthesis, 1),
+                    (')', _) => (Token::CloseParenthesis, 1),
+                    _ => return Err(format!("Unexpected character '{}' in expression", c)),
+                };
+                tokens.push(token);
+                i += len;
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn parse_primary(tokens: &[Token], position: &mut usize) -> Result<Expression, String> {
+        let token = tokens
+            .get(*position)
+            .ok_or("Unexpected end of expression")?;
+        *position += 1;
+        match token {
+            Token::Number(n) => Ok(Expression::Number(*n)),
+            Token::Identifier(name) => Ok(Expression::Field(name.clone())),
+            Token::Not => Ok(Expression::Not(Box::new(Self::parse_primary(
+                tokens, position,
+            )?))),
+            Token::Operator(Operator::Sub) => Ok(Expression::Binary(
+                Box::new(Expression::Number(0)),
+                Operator::Sub,
+                Box::new(Self::parse_primary(tokens, position)?),
+            )),
+            Token::OpenParenthesis => {
+                let inner = Self::parse_binary(tokens, position, 0)?;
+                if tokens.get(*position) != Some(&Token::CloseParenthesis) {
+                    return Err("Missing closing parenthesis in expression".into());
+                }
+                *position += 1;
+                Ok(inner)
+            }
+            Token::Operator(op) => Err(format!("Unexpected operator \"{}\" in expression", op)),
+            Token::CloseParenthesis => Err("Unexpected closing parenthesis in expression".into()),
+        }
+    }
+
+    fn parse_binary(
+        tokens: &[Token],
+        position: &mut usize,
+        min_precedence: u8,
+    ) -> Result<Expression, String> {
+        let mut lhs = Self::parse_primary(tokens, position)?;
+        while let Some(Token::Operator(op)) = tokens.get(*position) {
+            let op = *op;
+            if op.precedence() < min_precedence {
+                break;
+            }
+            *position += 1;
+            let rhs = Self::parse_binary(tokens, position, op.precedence() + 1)?;
+            lhs = Expression::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let tokens = Self::tokenize(s)?;
+        let mut position = 0;
+        let expression = Self::parse_binary(&tokens, &mut position, 0)?;
+        if position != tokens.len() {
+            return Err(format!(
+                "Unexpected trailing tokens in expression \"{}\"",
+                s
+            ));
+        }
+        Ok(expression)
+    }
+
+    /// Evaluates the expression, `lookup` is used to get the value of the referenced fields.
+    pub fn evaluate(&self, lookup: &impl Fn(&str) -> Option<i128>) -> Result<i128, String> {
+        match self {
+            Expression::Number(n) => Ok(*n),
+            Expression::Field(name) => {
+                lookup(name).ok_or_else(|| format!("Unknown field \"{}\" in expression", name))
+            }
+            Expression::Not(inner) => Ok((inner.evaluate(lookup)? == 0) as i128),
+            Expression::Binary(lhs, op, rhs) => {
+                op.apply(lhs.evaluate(lookup)?, rhs.evaluate(lookup)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let lookup = |name: &str| match name {
+            "count" => Some(3),
+            "header.flags" => Some(0b101),
+            _ => None,
+        };
+        let cases = [
+            ("42", 42),
+            ("0x10 + 2 * 3", 22),
+            ("(0x10 + 2) * 3", 54),
+            ("count * 4 - 1", 11),
+            ("header.flags & 4 != 0", 1),
+            ("count == 3 && !(count > 5)", 1),
+            ("count < 2 || count >= 4", 0),
+            ("-count + 10", 7),
+        ];
+        for (source, expected) in cases {
+            let expression = Expression::parse(source).unwrap();
+            assert_eq!(expression.evaluate(&lookup), Ok(expected), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(Expression::parse("").is_err());
+        assert!(Expression::parse("1 +").is_err());
+        assert!(Expression::parse("(1 + 2").is_err());
+        assert!(Expression::parse("1 2").is_err());
+        assert!(Expression::parse("0xZZ").is_err());
+        assert!(Expression::parse("1 $ 2").is_err());
+        let expression = Expression::parse("missing + 1").unwrap();
+        assert!(expression.evaluate(&|_| None).is_err());
+        let expression = Expression::parse("1 / 0").unwrap();
+        assert!(expression.evaluate(&|_| None).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod applied_template;
+pub mod expression;
+pub mod template;
+pub mod template_manager;
+pub mod template_node;
+pub mod template_value;
+mod templates;
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+
+use object::Endianness;
+use serde::Deserialize;
+
+use super::{
+    expression::Expression,
+    template_node::{TemplateNode, TemplateNodeContent},
+    template_value::FieldKind,
+};
+
+const MAX_NESTING: usize = 32;
+const MAX_ARRAY_LENGTH: usize = 0x10000;
+/// The name of the value that contains the offset of the next field from the start of the struct.
+const POSITION: &str = "_position";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateEndianness {
+    Little,
+    Big,
+}
+
+impl From<TemplateEndianness> for Endianness {
+    fn from(value: TemplateEndianness) -> Self {
+        match value {
+            TemplateEndianness::Little => Endianness::Little,
+            TemplateEndianness::Big => Endianness::Big,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TemplateField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    #[serde(default)]
+    pub count: Option<Expression>,
+    #[serde(default)]
+    pub endianness: Option<TemplateEndianness>,
+    #[serde(default, rename = "if")]
+    pub condition: Option<Expression>,
+    #[serde(default)]
+    pub offset: Option<Expression>,
+}
+
+/// A structure definition that can be overlaid on the file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Template {
+    pub name: String,
+    #[serde(default)]
+    pub endianness: Option<TemplateEndianness>,
+    #[serde(default)]
+    pub structs: HashMap<String, Vec<TemplateField>>,
+    pub fields: Vec<TemplateField>,
+}
+
+impl Template {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let template: Template = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        template.validate()?;
+        Ok(template)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (struct_name, fields) in std::iter::once(("root", &self.fields))
+            .chain(self.structs.iter().map(|(k, v)| (k.as_str(), v)))
+        {
+            for field in fields {
+                if FieldKind::from_name(&field.field_type).is_none()
+                    && !self.structs.contains_key(&field.field_type)
+                {
+                    return Err(format!(
+                        "Unknown type \"{}\" for field \"{}\" in \"{}\"",
+                        field.field_type, field.name, struct_name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the template to `bytes` starting from `base`.
+    /// `default_endianness` is used if the template does not specify one.
+    pub fn apply(
+        &self,
+        bytes: &[u8],
+        base: usize,
+        default_endianness: Endianness,
+    ) -> Result<TemplateNode, String> {
+        let endianness = self
+            .endianness
+            .map(Endianness::from)
+            .unwrap_or(default_endianness);
+        let mut evaluator = Evaluator {
+            template: self,
+            bytes,
+            base,
+            scopes: vec![HashMap::new()],
+        };
+        let (children, end) = evaluator.evaluate_fields(&self.fields, base, endianness)?;
+        Ok(TemplateNode {
+            name: self.name.clone(),
+            type_name: "template".into(),
+            offset: base,
+            size: end - base,
+            content: TemplateNodeContent::Children(children),
+        })
+    }
+}
+
+struct Evaluator<'a> {
+    template: &'a Template,
+    bytes: &'a [u8],
+    base: usize,
+    scopes: Vec<HashMap<String, i128>>,
+}
+
+impl Evaluator<'_> {
+    fn evaluate_expression(&self, expression: &Expression) -> Result<i128, String> {
+        expression.evaluate(&|name| {
+            self.scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name).copied())
+        })
+    }
+
+    fn evaluate_usize(
+        &self,
+        expression: &Expression,
+        field: &TemplateField,
+    ) -> Result<usize, String> {
+        let value = self.evaluate_expression(expression)?;
+        usize::try_from(value)
+            .map_err(|_| format!("Invalid value {} in field \"{}\"", value, field.name))
+    }
+
+    fn set_value(&mut self, name: String, value: Option<i128>) {
+        if let Some(value) = value {
+            self.scopes
+                .last_mut()
+                .expect("There is always at least one scope")
+                .insert(name, value);
+        }
+    }
+
+    fn read_value(
+        &mut self,
+        name: String,
+        kind: FieldKind,
+        offset: usize,
+        size: usize,
+        endianness: Endianness,
+    ) -> Result<TemplateNode, String> {
+        let bytes = offset
+            .checked_add(size)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| {
+                format!(
+                    "Field \"{}\" at {:#X} exceeds the end of the file",
+                    name, offset
+                )
+            })?;
+        let value = kind.read(bytes, endianness);
+        self.set_value(name.clone(), value.as_integer());
+        Ok(TemplateNode {
+            name,
+            type_name: kind.to_string(),
+            offset,
+            size,
+            content: TemplateNodeContent::Value {
+                value,
+                kind,
+                endianness,
+            },
+        })
+    }
+
+    fn read_struct(
+        &mut self,
+        name: String,
+        struct_name: &str,
+        offset: usize,
+        endianness: Endianness,
+    ) -> Result<TemplateNode, String> {
+        if self.scopes.len() > MAX_NESTING {
+            return Err(format!(
+                "Template nesting is too deep while parsing \"{}\"",
+                name
+            ));
+        }
+        let fields = &self.template.structs[struct_name];
+        self.scopes.push(HashMap::new());
+        let result = self.evaluate_fields(fields, offset, endianness);
+        let scope = self.scopes.pop().expect("Scope just pushed");
+        let (children, end) = result?;
+        for (child_name, value) in scope {
+            if child_name != POSITION {
+                self.set_value(format!("{}.{}", name, child_name), Some(value));
+            }
+        }
+        Ok(TemplateNode {
+            name,
+            type_name: struct_name.to_string(),
+            offset,
+            size: end - offset,
+            content: TemplateNodeContent::Children(children),
+        })
+    }
+
+    fn read_element(
+        &mut self,
+        name: String,
+        field: &TemplateField,
+        offset: usize,
+        endianness: Endianness,
+    ) -> Result<TemplateNode, String> {
+        match FieldKind::from_name(&field.field_type) {
+            Some(kind) => {
+                let size = kind.size().expect("Strings are handled separately");
+                self.read_value(name, kind, offset, size, endianness)
+            }
+            None => self.read_struct(name, &field.field_type, offset, endianness),
+        }
+    }
+
+    /// Returns the parsed fields and the offset right after the last sequential field.
+    fn evaluate_fields(
+        &mut self,
+        fields: &[TemplateField],
+        start: usize,
+        endianness: Endianness,
+    ) -> Result<(Vec<TemplateNode>, usize), String> {
+        let mut nodes = Vec::new();
+        let mut current = start;
+        for field in fields {
+            self.set_value(POSITION.into(), Some((current - start) as i128));
+            if let Some(condition) = &field.condition {
+                if self.evaluate_expression(condition)? == 0 {
+                    continue;
+                }
+            }
+            let endianness = field.endianness.map(Endianness::from).unwrap_or(endianness);
+            let offset = match &field.offset {
+                Some(offset) => self
+                    .base
+                    .checked_add(self.evaluate_usize(offset, field)?)
+                    .ok_or_else(|| format!("Invalid offset for field \"{}\"", field.name))?,
+                None => current,
+            };
+            let count = match &field.count {
+                Some(count) => {
+                    let count = self.evaluate_usize(count, field)?;
+                    if count > MAX_ARRAY_LENGTH {
+                        return Err(format!(
+                            "Field \"{}\" has too many elements ({})",
+                            field.name, count
+                        ));
+                    }
+                    Some(count)
+                }
+                None => None,
+            };
+
+            let node = match (FieldKind::from_name(&field.field_type), count) {
+                (Some(FieldKind::String), count) => self.read_value(
+                    field.name.clone(),
+                    FieldKind::String,
+                    offset,
+                    count.unwrap_or(1),
+                    endianness,
+                )?,
+                (_, None) => self.read_element(field.name.clone(), field, offset, endianness)?,
+                (_, Some(count)) => {
+                    let mut elements = Vec::with_capacity(count);
+                    let mut element_offset = offset;
+                    for i in 0..count {
+                        let element = self.read_element(
+                            format!("{}[{}]", field.name, i),
+                            field,
+                            element_offset,
+                            endianness,
+                        )?;
+                        element_offset += element.size;
+                        elements.push(element);
+                    }
+                    TemplateNode {
+                        name: field.name.clone(),
+                        type_name: format!("{}[{}]", field.field_type, count),
+                        offset,
+                        size: element_offset - offset,
+                        content: TemplateNodeContent::Children(elements),
+                    }
+                }
+            };
+            if field.offset.is_none() {
+                current = offset + node.size;
+            }
+            nodes.push(node);
+        }
+        Ok((nodes, current))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app::templates::template_value::TemplateValue;
+
+    use super::*;
+
+    const TEMPLATE: &str = r#"{
+        "name": "test",
+        "structs": {
+            "entry": [
+                { "name": "id", "type": "u8" },
+                { "name": "value", "type": "u16", "endianness": "big" }
+            ]
+        },
+        "fields": [
+            { "name": "magic", "type": "string", "count": 4 },
+            { "name": "count", "type": "u8" },
+            { "name": "flags", "type": "u8" },
+            { "name": "entries", "type": "entry", "count": "count" },
+            { "name": "extra", "type": "u32", "if": "flags & 1" },
+            { "name": "missing", "type": "u32", "if": "flags & 2" },
+            { "name": "last_id", "type": "u8", "offset": "count * 3 + 3" },
+            { "name": "rest", "type": "u8", "count": "16 - _position" }
+        ]
+    }"#;
+
+    fn value_of(node: &TemplateNode) -> &TemplateValue {
+        match &node.content {
+            TemplateNodeContent::Value { value, .. } => value,
+            TemplateNodeContent::Children(_) => panic!("Not a value"),
+        }
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let template = Template::from_json(TEMPLATE).unwrap();
+        let bytes = [
+            0xFF, b'T', b'E', b'S', b'T', 2, 1, 7, 0x12, 0x34, 8, 0x56, 0x78, 0xEF, 0xBE, 0xAD,
+            0xDE,
+        ];
+        let root = template.apply(&bytes, 1, Endianness::Little).unwrap();
+        assert_eq!(root.offset, 1);
+        assert_eq!(root.size, 16);
+        let children = root.children();
+        assert_eq!(children.len(), 7);
+        assert_eq!(
+            value_of(&children[0]),
+            &TemplateValue::String("TEST".into())
+        );
+        assert_eq!(value_of(&children[1]), &TemplateValue::Unsigned(2));
+        let entries = &children[3];
+        assert_eq!(entries.type_name, "entry[2]");
+        assert_eq!(entries.offset, 7);
+        assert_eq!(entries.size, 6);
+        let second = &entries.children()[1];
+        assert_eq!(second.name, "entries[1]");
+        assert_eq!(value_of(&second.children()[0]), &TemplateValue::Unsigned(8));
+        assert_eq!(
+            value_of(&second.children()[1]),
+            &TemplateValue::Unsigned(0x5678)
+        );
+        assert_eq!(value_of(&children[4]), &TemplateValue::Unsigned(0xDEADBEEF));
+        assert_eq!(children[5].name, "last_id");
+        assert_eq!(children[5].offset, 10);
+        assert_eq!(value_of(&children[5]), &TemplateValue::Unsigned(8));
+        assert_eq!(children[6].offset, 17);
+        assert_eq!(children[6].size, 0);
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(
+            Template::from_json(r#"{"name": "a", "fields": [{"name": "x", "type": "nope"}]}"#)
+                .is_err()
+        );
+        assert!(Template::from_json(
+            r#"{"name": "a", "fields": [{"name": "x", "type": "u8", "count": "1 +"}]}"#
+        )
+        .is_err());
+
+        let template =
+            Template::from_json(r#"{"name": "a", "fields": [{"name": "x", "type": "u32"}]}"#)
+                .unwrap();
+        assert!(template.apply(&[0, 0], 0, Endianness::Little).is_err());
+
+        let template = Template::from_json(
+            r#"{"name": "a", "structs": {"r": [{"name": "x", "type": "r"}]}, "fields": [{"name": "x", "type": "r"}]}"#,
+        )
+        .unwrap();
+        assert!(template.apply(&[0, 0], 0, Endianness::Little).is_err());
+    }
+}
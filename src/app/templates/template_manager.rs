@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use crate::app::log::{logger::Logger, NotificationLevel};
+
+use super::{applied_template::AppliedTemplate, template::Template};
+
+#[derive(Default, Debug)]
+pub struct TemplateManager {
+    pub templates: Vec<Template>,
+    pub applied: Option<AppliedTemplate>,
+}
+
+impl TemplateManager {
+    pub fn load(path: Option<&Path>, logger: &mut Logger) -> std::io::Result<Self> {
+        Ok(Self {
+            templates: Self::load_templates(path, logger)?,
+            applied: None,
+        })
+    }
+
+    fn get_default_template_path() -> Option<PathBuf> {
+        let config = dirs::config_dir()?;
+        Some(config.join("HexPatch").join("templates"))
+    }
+
+    fn load_templates(path: Option<&Path>, logger: &mut Logger) -> std::io::Result<Vec<Template>> {
+        let mut templates = Vec::new();
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::get_default_template_path()
+                .ok_or(std::io::Error::other("Could not get default template path"))?,
+        };
+        std::fs::create_dir_all(&path)?;
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_file() && path.extension().unwrap_or_default() == "json" {
+                match std::fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| Template::from_json(&json))
+                {
+                    Ok(template) => templates.push(template),
+                    Err(e) => logger.log(
+                        NotificationLevel::Error,
+                        &format!(
+                            "Could not load template \"{}\": {}",
+                            path.to_string_lossy(),
+                            e
+                        ),
+                    ),
+                }
+            }
+        }
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load_templates() {
+        let mut logger = Logger::default();
+        let template_manager =
+            TemplateManager::load(Some(Path::new("test/templates")), &mut logger).unwrap();
+        assert!(logger.is_empty(), "{:?}", logger.iter().collect::<Vec<_>>());
+        assert!(template_manager.get("elf_identification").is_some());
+        assert!(template_manager.get("missing").is_none());
+    }
+}
@@ -0,0 +1,48 @@
+use object::Endianness;
+
+use super::template_value::{FieldKind, TemplateValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateNodeContent {
+    Value {
+        value: TemplateValue,
+        kind: FieldKind,
+        endianness: Endianness,
+    },
+    Children(Vec<TemplateNode>),
+}
+
+/// A field of a template after it has been applied to the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateNode {
+    pub name: String,
+    pub type_name: String,
+    pub offset: usize,
+    pub size: usize,
+    pub content: TemplateNodeContent,
+}
+
+impl TemplateNode {
+    pub fn children(&self) -> &[TemplateNode] {
+        match &self.content {
+            TemplateNodeContent::Children(children) => children,
+            TemplateNodeContent::Value { .. } => &[],
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        matches!(self.content, TemplateNodeContent::Value { .. })
+    }
+
+    /// Pushes the range of every value in this subtree to `ranges`.
+    pub fn collect_leaf_ranges(&self, ranges: &mut Vec<(usize, usize)>) {
+        match &self.content {
+            TemplateNodeContent::Value { .. } => ranges.push((self.offset, self.size)),
+            TemplateNodeContent::Children(children) => {
+                for child in children {
+                    child.collect_leaf_ranges(ranges);
+                }
+            }
+        }
+    }
+}
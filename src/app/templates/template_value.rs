@@ -0,0 +1,308 @@
+use std::fmt::Display;
+
+use object::Endianness;
+
+/// The type of a field that holds a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    Char,
+    /// A fixed length string, its size is the `count` of the field.
+    String,
+}
+
+impl FieldKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" => FieldKind::U8,
+            "u16" => FieldKind::U16,
+            "u32" => FieldKind::U32,
+            "u64" => FieldKind::U64,
+            "i8" => FieldKind::I8,
+            "i16" => FieldKind::I16,
+            "i32" => FieldKind::I32,
+            "i64" => FieldKind::I64,
+            "f32" => FieldKind::F32,
+            "f64" => FieldKind::F64,
+            "char" => FieldKind::Char,
+            "string" => FieldKind::String,
+            _ => return None,
+        })
+    }
+
+    /// The size of a single value of this kind, `None` for strings.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            FieldKind::U8 | FieldKind::I8 | FieldKind::Char => Some(1),
+            FieldKind::U16 | FieldKind::I16 => Some(2),
+            FieldKind::U32 | FieldKind::I32 | FieldKind::F32 => Some(4),
+            FieldKind::U64 | FieldKind::I64 | FieldKind::F64 => Some(8),
+            FieldKind::String => None,
+        }
+    }
+
+    fn to_little_endian(bytes: &[u8], endianness: Endianness) -> [u8; 8] {
+        let mut buffer = [0; 8];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        if endianness == Endianness::Big {
+            buffer[..bytes.len()].reverse();
+        }
+        buffer
+    }
+
+    fn from_little_endian(buffer: [u8; 8], size: usize, endianness: Endianness) -> Vec<u8> {
+        let mut bytes = buffer[..size].to_vec();
+        if endianness == Endianness::Big {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    /// Reads a value of this kind from `bytes`, `bytes` must be exactly as long as the value.
+    pub fn read(&self, bytes: &[u8], endianness: Endianness) -> TemplateValue {
+        if *self == FieldKind::String {
+            return TemplateValue::String(
+                String::from_utf8_lossy(bytes)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            );
+        }
+        let buffer = Self::to_little_endian(bytes, endianness);
+        let unsigned = u64::from_le_bytes(buffer);
+        match self {
+            FieldKind::U8 | FieldKind::U16 | FieldKind::U32 | FieldKind::U64 => {
+                TemplateValue::Unsigned(unsigned)
+            }
+            FieldKind::I8 => TemplateValue::Signed(unsigned as u8 as i8 as i64),
+            FieldKind::I16 => TemplateValue::Signed(unsigned as u16 as i16 as i64),
+            FieldKind::I32 => TemplateValue::Signed(unsigned as u32 as i32 as i64),
+            FieldKind::I64 => TemplateValue::Signed(unsigned as i64),
+            FieldKind::F32 => TemplateValue::Float(f32::from_bits(unsigned as u32) as f64),
+            FieldKind::F64 => TemplateValue::Float(f64::from_bits(unsigned)),
+            FieldKind::Char => TemplateValue::Char(bytes[0]),
+            FieldKind::String => unreachable!("Strings are handled separately"),
+        }
+    }
+
+    fn parse_integer(value: &str) -> Result<i128, String> {
+        let value = value.trim();
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value),
+        };
+        let parsed = if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            i128::from_str_radix(hex, 16)
+        } else {
+            digits.parse::<i128>()
+        }
+        .map_err(|_| format!("\"{}\" is not a valid integer", value))?;
+        Ok(if negative { -parsed } else { parsed })
+    }
+
+    /// Encodes the string representation of a value of this kind.
+    /// `size` is the size of the field, it is only relevant for strings.
+    pub fn write(
+        &self,
+        value: &str,
+        size: usize,
+        endianness: Endianness,
+    ) -> Result<Vec<u8>, String> {
+        let out_of_range = || format!("\"{}\" is out of range for this field", value.trim());
+        let buffer = match self {
+            FieldKind::U8 | FieldKind::U16 | FieldKind::U32 | FieldKind::U64 => {
+                let parsed = Self::parse_integer(value)?;
+                let bits = self.size().expect("Not a string") * 8;
+                if parsed < 0 || (bits < 128 && parsed >> bits != 0) {
+                    return Err(out_of_range());
+                }
+                (parsed as u64).to_le_bytes()
+            }
+            FieldKind::I8 | FieldKind::I16 | FieldKind::I32 | FieldKind::I64 => {
+                let parsed = Self::parse_integer(value)?;
+                let bits = self.size().expect("Not a string") * 8 - 1;
+                if parsed < -(1 << bits) || parsed >= (1 << bits) {
+                    return Err(out_of_range());
+                }
+                (parsed as i64).to_le_bytes()
+            }
+            FieldKind::F32 => {
+                let parsed = value
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| format!("\"{}\" is not a valid number", value.trim()))?;
+                (parsed.to_bits() as u64).to_le_bytes()
+            }
+            FieldKind::F64 => {
+                let parsed = value
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("\"{}\" is not a valid number", value.trim()))?;
+                parsed.to_bits().to_le_bytes()
+            }
+            FieldKind::Char => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii() => (c as u64).to_le_bytes(),
+                    _ => return Err(format!("\"{}\" is not a single ASCII character", value)),
+                }
+            }
+            FieldKind::String => {
+                if value.len() > size {
+                    return Err(format!(
+                        "The string is {} bytes long but the field is only {} bytes long",
+                        value.len(),
+                        size
+                    ));
+                }
+                let mut bytes = value.as_bytes().to_vec();
+                bytes.resize(size, 0);
+                return Ok(bytes);
+            }
+        };
+        Ok(Self::from_little_endian(
+            buffer,
+            self.size().expect("Not a string"),
+            endianness,
+        ))
+    }
+}
+
+impl Display for FieldKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            FieldKind::U8 => "u8",
+            FieldKind::U16 => "u16",
+            FieldKind::U32 => "u32",
+            FieldKind::U64 => "u64",
+            FieldKind::I8 => "i8",
+            FieldKind::I16 => "i16",
+            FieldKind::I32 => "i32",
+            FieldKind::I64 => "i64",
+            FieldKind::F32 => "f32",
+            FieldKind::F64 => "f64",
+            FieldKind::Char => "char",
+            FieldKind::String => "string",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The value of a field read from the file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    Char(u8),
+    String(String),
+}
+
+impl TemplateValue {
+    /// The value used when the field is referenced inside an expression.
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            TemplateValue::Unsigned(v) => Some(*v as i128),
+            TemplateValue::Signed(v) => Some(*v as i128),
+            TemplateValue::Float(v) => Some(*v as i128),
+            TemplateValue::Char(v) => Some(*v as i128),
+            TemplateValue::String(_) => None,
+        }
+    }
+
+    /// The string shown when the value is edited.
+    pub fn to_editable_string(&self) -> String {
+        match self {
+            TemplateValue::Unsigned(v) => v.to_string(),
+            TemplateValue::Signed(v) => v.to_string(),
+            TemplateValue::Float(v) => v.to_string(),
+            TemplateValue::Char(v) => (*v as char).to_string(),
+            TemplateValue::String(v) => v.clone(),
+        }
+    }
+}
+
+impl Display for TemplateValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateValue::Unsigned(v) => write!(f, "{} ({:#X})", v, v),
+            TemplateValue::Signed(v) => write!(f, "{}", v),
+            TemplateValue::Float(v) => write!(f, "{}", v),
+            TemplateValue::Char(v) => {
+                if v.is_ascii_graphic() || *v == b' ' {
+                    write!(f, "'{}'", *v as char)
+                } else {
+                    write!(f, "'\\x{:02X}'", v)
+                }
+            }
+            TemplateValue::String(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_and_write() {
+        let kind = FieldKind::U16;
+        assert_eq!(
+            kind.read(&[0x34, 0x12], Endianness::Little),
+            TemplateValue::Unsigned(0x1234)
+        );
+        assert_eq!(
+            kind.read(&[0x12, 0x34], Endianness::Big),
+            TemplateValue::Unsigned(0x1234)
+        );
+        assert_eq!(
+            kind.write("0x1234", 2, Endianness::Big),
+            Ok(vec![0x12, 0x34])
+        );
+        assert!(kind.write("0x10000", 2, Endianness::Little).is_err());
+        assert!(kind.write("-1", 2, Endianness::Little).is_err());
+
+        let kind = FieldKind::I32;
+        assert_eq!(
+            kind.read(&[0xFE, 0xFF, 0xFF, 0xFF], Endianness::Little),
+            TemplateValue::Signed(-2)
+        );
+        assert_eq!(
+            kind.write("-2", 4, Endianness::Little),
+            Ok(vec![0xFE, 0xFF, 0xFF, 0xFF])
+        );
+
+        let kind = FieldKind::F32;
+        let bytes = kind.write("1.5", 4, Endianness::Big).unwrap();
+        assert_eq!(
+            kind.read(&bytes, Endianness::Big),
+            TemplateValue::Float(1.5)
+        );
+
+        let kind = FieldKind::String;
+        assert_eq!(
+            kind.read(b"ELF\0", Endianness::Little),
+            TemplateValue::String("ELF".into())
+        );
+        assert_eq!(
+            kind.read(b"__TEXT\0\0\0\0\0\0\0\0\0\0", Endianness::Big),
+            TemplateValue::String("__TEXT".into())
+        );
+        assert_eq!(
+            kind.write("AB", 4, Endianness::Little),
+            Ok(vec![b'A', b'B', 0, 0])
+        );
+        assert!(kind.write("ABCDE", 4, Endianness::Little).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+#![allow(clippy::module_inception)]
+use std::error::Error;
+
+use crate::{
+    app::{hex::TemplateFieldInfo, log::NotificationLevel, popup::popup_state::PopupState, App},
+    fuzzer::fuzzy_search_in_place,
+    get_app_context,
+};
+
+use super::{applied_template::AppliedTemplate, template_node::TemplateNodeContent};
+
+impl App {
+    pub(in crate::app) fn find_templates(&self, filter: &str) -> Vec<String> {
+        let mut names = self
+            .template_manager
+            .templates
+            .iter()
+            .map(|t| t.name.clone())
+            .collect::<Vec<_>>();
+        fuzzy_search_in_place(filter, &mut names);
+        names
+    }
+
+    pub(in crate::app) fn request_popup_select_template(&mut self) {
+        self.popup = Some(PopupState::SelectTemplate {
+            filter: String::new(),
+            cursor: 0,
+            results: self.find_templates(""),
+            scroll: 0,
+        });
+    }
+
+    pub(in crate::app) fn request_popup_template(&mut self) {
+        if self.template_manager.applied.is_some() {
+            self.popup = Some(PopupState::Template {
+                scroll: 0,
+                editing: false,
+                value: String::new(),
+                cursor: 0,
+            });
+        } else {
+            self.log(NotificationLevel::Warning, "No template applied.");
+        }
+    }
+
+    /// Applies the template named `name` at the cursor.
+    /// Returns true if the template was applied.
+    pub(in crate::app) fn apply_template(&mut self, name: &str) -> bool {
+        self.apply_template_at(name, self.get_cursor_position().global_byte_index)
+    }
+
+    /// Applies the template named `name` at the file address `base`.
+    /// Returns true if the template was applied.
+    pub(in crate::app) fn apply_template_at(&mut self, name: &str, base: usize) -> bool {
+        let template = match self.template_manager.get(name) {
+            Some(template) => template.clone(),
+            None => {
+                self.log(
+                    NotificationLevel::Error,
+                    &format!("Template \"{}\" not found", name),
+                );
+                return false;
+            }
+        };
+        match AppliedTemplate::new(template, self.data.bytes(), base, self.header.endianness()) {
+            Ok(applied) => {
+                self.log(
+                    NotificationLevel::Info,
+                    &format!("Template \"{}\" applied at {:#X}", name, base),
+                );
+                self.template_manager.applied = Some(applied);
+                true
+            }
+            Err(e) => {
+                self.log(
+                    NotificationLevel::Error,
+                    &format!("Could not apply template \"{}\": {}", name, e),
+                );
+                false
+            }
+        }
+    }
+
+    /// Applies the built-in template of the file header at the start of the file.
+    /// Returns true if the template was applied.
+    pub(in crate::app) fn apply_header_template(&mut self) -> bool {
+        let template = match self.header.get_header_template() {
+            Some(template) => template,
+            None => {
+                self.log(
+                    NotificationLevel::Warning,
+                    "The header of this file type can't be shown.",
+                );
+                return false;
+            }
+        };
+        match AppliedTemplate::new(template, self.data.bytes(), 0, self.header.endianness()) {
+            Ok(applied) => {
+                self.template_manager.applied = Some(applied);
+                true
+            }
+            Err(e) => {
+                self.log(
+                    NotificationLevel::Error,
+                    &format!("Could not parse the header: {}", e),
+                );
+                false
+            }
+        }
+    }
+
+    pub(in crate::app) fn clear_template(&mut self) {
+        if self.template_manager.applied.take().is_none() {
+            self.log(NotificationLevel::Warning, "No template applied.");
+        }
+    }
+
+    /// Applies the current template again after the data has changed.
+    /// The template is removed if it can no longer be applied.
+    pub(in crate::app) fn reapply_template(&mut self) {
+        if let Some(applied) = &mut self.template_manager.applied {
+            if let Err(e) = applied.reapply(self.data.bytes()) {
+                self.template_manager.applied = None;
+                self.log(
+                    NotificationLevel::Warning,
+                    &format!(
+                        "Template removed because it can no longer be applied: {}",
+                        e
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Returns the fields of the applied template that overlap the bytes from `start_byte` to `end_byte`.
+    pub(in crate::app) fn get_template_fields_info(
+        &self,
+        start_byte: usize,
+        end_byte: usize,
+    ) -> Vec<TemplateFieldInfo> {
+        let applied = match &self.template_manager.applied {
+            Some(applied) => applied,
+            None => return Vec::new(),
+        };
+        let first = applied
+            .leaf_ranges
+            .partition_point(|(offset, size)| offset + size <= start_byte);
+        applied
+            .leaf_ranges
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take_while(|(_, (offset, _))| *offset < end_byte)
+            .map(|(i, (offset, size))| TemplateFieldInfo {
+                offset: *offset as isize - start_byte as isize,
+                length: *size,
+                alternate: i % 2 == 1,
+            })
+            .collect()
+    }
+
+    /// Returns the offset, the size and the path of the visible template row at `index`.
+    pub(in crate::app) fn get_template_row(&self, index: usize) -> Option<(usize, usize, String)> {
+        let applied = self.template_manager.applied.as_ref()?;
+        applied
+            .visible_rows()
+            .get(index)
+            .map(|row| (row.node.offset, row.node.size, row.path.clone()))
+    }
+
+    pub(in crate::app) fn jump_to_template_row(&mut self, index: usize) {
+        if let Some((offset, _, _)) = self.get_template_row(index) {
+            self.jump_to(offset, false);
+        }
+    }
+
+    pub(in crate::app) fn get_template_row_count(&self) -> usize {
+        self.template_manager
+            .applied
+            .as_ref()
+            .map(|applied| applied.visible_rows().len())
+            .unwrap_or(0)
+    }
+
+    /// Expands or collapses the row at `index`, returns false if the row is a value.
+    pub(in crate::app) fn set_template_row_expanded(
+        &mut self,
+        index: usize,
+        expanded: bool,
+    ) -> bool {
+        if let Some(applied) = &mut self.template_manager.applied {
+            let path = match applied.visible_rows().get(index) {
+                Some(row) if !row.node.is_leaf() => row.path.clone(),
+                _ => return false,
+            };
+            applied.set_expanded(&path, expanded);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(in crate::app) fn toggle_template_row(&mut self, index: usize) -> bool {
+        let expanded = self
+            .template_manager
+            .applied
+            .as_ref()
+            .and_then(|applied| applied.visible_rows().get(index).map(|row| row.expanded))
+            .unwrap_or(false);
+        self.set_template_row_expanded(index, !expanded)
+    }
+
+    /// Returns the value of the row at `index` as it should be shown while editing.
+    pub(in crate::app) fn get_template_row_editable_value(&self, index: usize) -> Option<String> {
+        let applied = self.template_manager.applied.as_ref()?;
+        let rows = applied.visible_rows();
+        match &rows.get(index)?.node.content {
+            TemplateNodeContent::Value { value, .. } => Some(value.to_editable_string()),
+            TemplateNodeContent::Children(_) => None,
+        }
+    }
+
+    /// Writes `value` to the field at row `index` and applies the template again.
+    pub(in crate::app) fn edit_template_field(
+        &mut self,
+        index: usize,
+        value: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let applied = self
+            .template_manager
+            .applied
+            .as_ref()
+            .ok_or("No template applied")?;
+        let rows = applied.visible_rows();
+        let row = rows.get(index).ok_or("Field not found")?;
+        let (offset, mut bytes) = match &row.node.content {
+            TemplateNodeContent::Value {
+                kind, endianness, ..
+            } => (
+                row.node.offset,
+                kind.write(value, row.node.size, *endianness)?,
+            ),
+            TemplateNodeContent::Children(_) => return Err("Only values can be edited".into()),
+        };
+
+        self.jump_to(offset, false);
+        let mut app_context = get_app_context!(self);
+        app_context.offset = offset;
+        self.plugin_manager.on_edit(&mut bytes, &mut app_context);
+        let len = bytes.len();
+        let modified_bytes = self.data.push_change(offset, bytes);
+        self.update_after_edit(offset, len, modified_bytes);
+        self.reapply_template();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::app::templates::template_manager::TemplateManager;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_and_edit_template() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let mut app = App::mockup(data);
+        app.template_manager =
+            TemplateManager::load(Some(Path::new("test/templates")), &mut app.logger).unwrap();
+        assert_eq!(app.find_templates("elf")[0], "elf_identification");
+
+        assert!(app.apply_template("elf_identification"));
+        assert!(!app.set_template_row_expanded(1, true));
+        assert!(app.toggle_template_row(0));
+        assert_eq!(app.get_template_row(0).unwrap().2, "e_ident");
+        let (offset, size, path) = app.get_template_row(2).unwrap();
+        assert_eq!((offset, size, path.as_str()), (4, 1, "e_ident.class"));
+        assert_eq!(app.get_template_row_editable_value(2).unwrap(), "2");
+        assert_eq!(app.get_template_row_count(), 7 + 5);
+
+        app.edit_template_field(2, "1").unwrap();
+        assert_eq!(app.data.bytes()[4], 1);
+        assert_eq!(app.get_template_row_editable_value(2).unwrap(), "1");
+        assert!(app.edit_template_field(2, "256").is_err());
+        assert!(app.edit_template_field(0, "1").is_err());
+
+        app.undo();
+        assert_eq!(app.data.bytes()[4], 2);
+        assert_eq!(app.get_template_row_editable_value(2).unwrap(), "2");
+
+        app.clear_template();
+        assert!(app.template_manager.applied.is_none());
+    }
+
+    #[test]
+    fn test_edit_header() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        let mut app = App::mockup(data);
+        assert!(app.apply_header_template());
+        assert_eq!(app.get_template_row(0).unwrap().2, "ehdr");
+        assert!(app.toggle_template_row(0));
+        let index = (0..app.get_template_row_count())
+            .find(|i| app.get_template_row(*i).unwrap().2 == "ehdr.e_entry")
+            .unwrap();
+        let (offset, size, _) = app.get_template_row(index).unwrap();
+        assert_eq!((offset, size), (0x18, 8));
+
+        let entry_point = app.header.entry_point();
+        app.edit_template_field(index, &(entry_point + 4).to_string())
+            .unwrap();
+        assert_eq!(app.header.entry_point(), entry_point + 4);
+
+        let mut app = App::mockup(vec![0; 0x100]);
+        assert!(!app.apply_header_template());
+        assert!(app.template_manager.applied.is_none());
+    }
+}
@@ -20,6 +20,31 @@ pub struct Args {
     pub config: Option<PathBuf>,
     #[arg(short, long, help = "The plugin directory to use")]
     pub plugins: Option<PathBuf>,
+    #[arg(short, long, help = "The template directory to use")]
+    pub templates: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "The architecture of files without a header, e.g. x86_64, arm, aarch64, mips, ppc"
+    )]
+    pub arch: Option<String>,
+    #[arg(
+        long,
+        help = "The mode of the architecture, e.g. thumb for arm, 16 for x86, 32 or 64",
+        requires = "arch"
+    )]
+    pub mode: Option<String>,
+    #[arg(
+        long,
+        help = "The endianness of the architecture, little or big",
+        requires = "arch"
+    )]
+    pub endian: Option<String>,
+    #[arg(
+        long,
+        help = "The address where the file is loaded, decimal or hexadecimal with the 0x prefix",
+        requires = "arch"
+    )]
+    pub base: Option<String>,
     #[arg(
         index = 1,
         help = "The starting path of the editor",
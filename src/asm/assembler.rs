@@ -1,18 +1,268 @@
 use std::error::Error;
 
-use crate::headers::Header;
+use keystone_engine::KeystoneError;
+use object::Architecture;
+
+use crate::{app::settings::app_settings::X86Syntax, headers::Header};
+
+/// Returns why the code can't be assembled if the architecture of `header` can only be disassembled,
+/// the bytes can still be edited in the hex view.
+pub fn read_only_reason(header: &Header) -> Option<String> {
+    match header.get_encoder(X86Syntax::default()) {
+        Err(KeystoneError::Engine(keystone_engine::Error::ARCH)) => Some(format!(
+            "Assembly is read-only for {:?}, edit the bytes in the hex view instead.",
+            header.architecture()
+        )),
+        _ => None,
+    }
+}
+
+/// Keystone reads MASM code as Intel code, but it doesn't know the MASM hexadecimal numbers
+/// (e.g. `0FFh`), they are written with the `0x` prefix.
+fn masm_numbers_to_hex(asm: &str) -> String {
+    let mut result = String::with_capacity(asm.len());
+    let mut word = String::new();
+    let push_word = |word: &mut String, result: &mut String| {
+        let number = word
+            .strip_suffix(['h', 'H'])
+            .filter(|number| number.starts_with(|c: char| c.is_ascii_digit()))
+            .filter(|number| number.chars().all(|c| c.is_ascii_hexdigit()));
+        match number {
+            Some(number) => {
+                result.push_str("0x");
+                result.push_str(number);
+            }
+            None => result.push_str(word),
+        }
+        word.clear();
+    };
+    for c in asm.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c);
+        } else {
+            push_word(&mut word, &mut result);
+            result.push(c);
+        }
+    }
+    push_word(&mut word, &mut result);
+    result
+}
+
+/// The words of the operands that are neither registers nor names, e.g. `dword ptr` or `lsl #2`.
+fn operand_keywords(header: &Header) -> &'static [&'static str] {
+    match header.architecture() {
+        Architecture::Unknown
+        | Architecture::I386
+        | Architecture::X86_64
+        | Architecture::X86_64_X32 => &[
+            "byte", "word", "dword", "fword", "qword", "tbyte", "oword", "xmmword", "ymmword",
+            "zmmword", "ptr", "offset", "short", "near", "far", "rel",
+        ],
+        Architecture::Arm | Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => &[
+            "lsl", "lsr", "asr", "ror", "rrx", "msl", "sxtb", "sxth", "sxtw", "sxtx", "uxtb",
+            "uxth", "uxtw", "uxtx",
+        ],
+        _ => &[],
+    }
+}
+
+/// Splits `asm` in words (names, numbers and registers) and the text between them.
+fn split_words(asm: &str) -> Vec<(bool, String)> {
+    let mut parts: Vec<(bool, String)> = Vec::new();
+    for c in asm.chars() {
+        let is_word = c.is_ascii_alphanumeric() || "_.@!".contains(c);
+        match parts.last_mut() {
+            Some((last_is_word, part)) if *last_is_word == is_word => part.push(c),
+            _ => parts.push((is_word, c.to_string())),
+        }
+    }
+    parts
+}
+
+/// Prepares a patch for keystone, that knows the named labels defined by the patch but neither
+/// the numeric local labels of GNU as (`1:`, referenced as `1b` and `1f`) nor the symbols of the file.
+/// The numeric labels are renamed, the operands that name neither a label of the patch nor a register
+/// or a keyword of the architecture of `header` are replaced with the address returned by `resolve`,
+/// e.g. the address of a symbol or of a label.
+pub fn resolve_names(asm: &str, header: &Header, resolve: impl Fn(&str) -> Option<u64>) -> String {
+    let parts = split_words(asm);
+    let registers = header.register_names();
+    let keywords = operand_keywords(header);
+    let is_reserved = |i: usize, word: &str| {
+        let word = word.to_ascii_lowercase();
+        // `%rax` in the AT&T syntax and `%r1` on SystemZ
+        i.checked_sub(1)
+            .is_some_and(|previous| parts[previous].1.ends_with('%'))
+            || registers.contains(&word)
+            || keywords.contains(&word.as_str())
+    };
+    // the words that start a statement, and the labels defined by the patch
+    let mut statement_starts = Vec::new();
+    let mut labels = Vec::new();
+    let mut at_statement_start = true;
+    for (i, (is_word, part)) in parts.iter().enumerate() {
+        if *is_word {
+            if at_statement_start {
+                statement_starts.push(i);
+                let defines_label = parts
+                    .get(i + 1)
+                    .is_some_and(|(_, next)| next.trim_start().starts_with(':'));
+                if defines_label {
+                    labels.push(i);
+                } else {
+                    at_statement_start = false;
+                }
+            }
+        } else if part.contains(['\n', ';']) {
+            at_statement_start = true;
+        }
+    }
+    let is_number = |word: &str| !word.is_empty() && word.chars().all(|c| c.is_ascii_digit());
+    let numeric_label = |number: &str, count: usize| format!(".Lpatch_{}_{}", number, count);
+    let defined = labels
+        .iter()
+        .map(|&i| parts[i].1.as_str())
+        .collect::<Vec<_>>();
+
+    let mut result = String::with_capacity(asm.len());
+    for (i, (is_word, part)) in parts.iter().enumerate() {
+        if !is_word {
+            result.push_str(part);
+            continue;
+        }
+        // the numeric labels defined before this word, with the same number
+        let defined_before = |number: &str| {
+            labels
+                .iter()
+                .filter(|&&label| label < i && parts[label].1 == number)
+                .count()
+        };
+        if labels.contains(&i) && is_number(part) {
+            result.push_str(&numeric_label(part, defined_before(part)));
+        } else if statement_starts.contains(&i) {
+            result.push_str(part);
+        } else if let Some(number) = part.strip_suffix('b').filter(|number| is_number(number)) {
+            match defined_before(number) {
+                0 => result.push_str(part),
+                count => result.push_str(&numeric_label(number, count - 1)),
+            }
+        } else if let Some(number) = part.strip_suffix('f').filter(|number| is_number(number)) {
+            let count = defined_before(number);
+            if defined.iter().filter(|label| **label == number).count() > count {
+                result.push_str(&numeric_label(number, count));
+            } else {
+                result.push_str(part);
+            }
+        } else if part.starts_with(|c: char| c.is_ascii_digit())
+            || defined.contains(&part.as_str())
+            || is_reserved(i, part)
+        {
+            result.push_str(part);
+        } else {
+            match resolve(part) {
+                Some(address) => result.push_str(&format!("{:#x}", address)),
+                None => result.push_str(part),
+            }
+        }
+    }
+    result
+}
 
 pub fn assemble(
     asm: &str,
     starting_virtual_address: u64,
     header: &Header,
+    syntax: X86Syntax,
 ) -> Result<Vec<u8>, Box<dyn Error>> {
+    if let Some(reason) = read_only_reason(header) {
+        return Err(reason.into());
+    }
+    // the instruction is assembled in the mode of the code it replaces
     let encoder = header
-        .get_encoder()
+        .get_encoder_for_mode(header.cpu_mode_at(starting_virtual_address), syntax)
         .map_err(|e| format!("Failed to get encoder: {}", e))?;
 
+    let asm = match syntax {
+        X86Syntax::Masm => masm_numbers_to_hex(asm),
+        _ => asm.to_string(),
+    };
     let out = encoder
-        .asm(asm.to_string(), starting_virtual_address)
+        .asm(asm, starting_virtual_address)
         .map_err(|e| format!("Failed to assemble: {}", e))?;
     Ok(out.bytes)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_names() {
+        let resolve = |name: &str| match name {
+            "puts" => Some(0x1030),
+            "libc!exit@plt" => Some(0x1040),
+            "loop" => Some(0x2000),
+            _ => None,
+        };
+        let header = Header::None;
+        // the labels of the patch hide the symbols of the file
+        assert_eq!(
+            resolve_names("loop: dec ecx\njnz loop\ncall puts", &header, resolve),
+            "loop: dec ecx\njnz loop\ncall 0x1030"
+        );
+        assert_eq!(
+            resolve_names("jmp libc!exit@plt; mov eax, [rip + puts]", &header, resolve),
+            "jmp 0x1040; mov eax, [rip + 0x1030]"
+        );
+        assert_eq!(
+            resolve_names("1: nop\njmp 1f\njmp 1b\n1: jne 1b", &header, resolve),
+            "\
+.Lpatch_1_0: nop
+jmp .Lpatch_1_1
+jmp .Lpatch_1_0
+.Lpatch_1_1: jne .Lpatch_1_1"
+        );
+        // mnemonics are left alone
+        assert_eq!(resolve_names("loop puts", &header, resolve), "loop 0x1030");
+        assert_eq!(
+            resolve_names("ldr x0, [x1, #8]", &header, resolve),
+            "ldr x0, [x1, #8]"
+        );
+    }
+
+    #[test]
+    fn test_resolve_names_reserved() {
+        // symbols with the names of registers and keywords
+        let resolve = |name: &str| match name {
+            "rax" | "RCX" | "ptr" | "dword" | "r0" | "lsl" | "x1" | "table" => Some(0x1000),
+            _ => None,
+        };
+        let header = Header::None;
+        assert_eq!(
+            resolve_names("mov rax, dword ptr [RCX + table]", &header, resolve),
+            "mov rax, dword ptr [RCX + 0x1000]"
+        );
+        assert_eq!(
+            resolve_names("movq %rax, table(%rip)", &header, resolve),
+            "movq %rax, 0x1000(%rip)"
+        );
+        // the registers of the other architectures are names
+        assert_eq!(
+            resolve_names("mov eax, r0", &header, resolve),
+            "mov eax, 0x1000"
+        );
+
+        let path = "test/elf_arm_thumb.bin";
+        let data = std::fs::read(path).unwrap();
+        let filesystem = crate::app::files::filesystem::FileSystem::new_local(".").unwrap();
+        let header = Header::parse_header(&data, path, &filesystem);
+        assert_eq!(
+            resolve_names("add r0, r0, r1, lsl #2", &header, resolve),
+            "add r0, r0, r1, lsl #2"
+        );
+        assert_eq!(
+            resolve_names("ldr r0, =table", &header, resolve),
+            "ldr r0, =0x1000"
+        );
+    }
+}
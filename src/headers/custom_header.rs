@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use object::{Architecture, Endianness};
 
-use super::{bitness::Bitness, section::Section};
+use super::{bitness::Bitness, section::Section, target::CpuMode};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CustomHeader {
@@ -10,6 +10,7 @@ pub struct CustomHeader {
     pub entry: u64,
     pub endianness: Endianness,
     pub architecture: Architecture,
+    pub mode: CpuMode,
     pub sections: Vec<Section>,
     pub symbols: HashMap<u64, String>,
     pub symbols_by_name: HashMap<String, u64>,
@@ -1,11 +1,22 @@
-use std::{collections::HashMap, io::Write};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+};
 
-use object::{Object, ObjectSection, ObjectSymbol};
+use object::{Object, ObjectSection, ObjectSegment, ObjectSymbol, SectionFlags, SegmentFlags};
 use pdb::FallibleIterator;
 
 use crate::app::files::{filesystem::FileSystem, path};
 
-use super::{bitness::Bitness, section::Section};
+use super::{
+    bitness::Bitness,
+    imports::{self, Export, Import},
+    relocations::{self, Relocation},
+    section::{Section, SectionKind, SectionPermissions},
+    segment::Segment,
+    target::CpuMode,
+    unwind,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
@@ -27,10 +38,27 @@ pub struct GenericHeader {
     pub(super) architecture: object::Architecture,
     pub(super) bitness: Bitness,
     pub(super) endianness: object::Endianness,
+    /// The endianness of the instructions, it differs from `endianness` in ARM BE8 binaries.
+    pub(super) code_endianness: object::Endianness,
     pub(super) entry: u64,
     pub(super) sections: Vec<Section>,
+    pub(super) segments: Vec<Segment>,
     pub(super) symbols: HashMap<u64, String>,
     pub(super) symbols_by_name: HashMap<String, u64>,
+    /// The start and the size of the functions known from the symbols and the unwind tables,
+    /// the size is 0 if it is unknown.
+    pub(super) function_ranges: Vec<(u64, u64)>,
+    /// The addresses where 32-bit ARM code switches between ARM and Thumb, sorted by address.
+    /// It is empty for the other architectures.
+    pub(super) cpu_modes: Vec<(u64, CpuMode)>,
+    /// Sorted by library and name.
+    pub(super) imports: Vec<Import>,
+    /// The addresses of the import slots.
+    pub(super) import_slots: HashSet<u64>,
+    /// Sorted by address.
+    pub(super) exports: Vec<Export>,
+    /// Sorted by file address.
+    pub(super) relocations: Vec<Relocation>,
 }
 
 impl GenericHeader {
@@ -39,6 +67,259 @@ impl GenericHeader {
         name.to_string()
     }
 
+    /// Mach-O sections have no permissions, they are taken from the protection of their segment.
+    /// A Mach-O section is executable only if it also contains instructions.
+    fn section_permissions(section: &object::Section, segments: &[Segment]) -> SectionPermissions {
+        match section.flags() {
+            SectionFlags::Elf { sh_flags } => SectionPermissions {
+                read: sh_flags & object::elf::SHF_ALLOC as u64 != 0,
+                write: sh_flags & object::elf::SHF_WRITE as u64 != 0,
+                execute: sh_flags & object::elf::SHF_EXECINSTR as u64 != 0,
+            },
+            SectionFlags::Coff { characteristics } => SectionPermissions {
+                read: characteristics & object::pe::IMAGE_SCN_MEM_READ != 0,
+                write: characteristics & object::pe::IMAGE_SCN_MEM_WRITE != 0,
+                execute: characteristics
+                    & (object::pe::IMAGE_SCN_MEM_EXECUTE | object::pe::IMAGE_SCN_CNT_CODE)
+                    != 0,
+            },
+            SectionFlags::MachO { flags } => {
+                let protection = section
+                    .segment_name()
+                    .ok()
+                    .flatten()
+                    .and_then(|name| segments.iter().find(|segment| segment.name == name))
+                    .map(|segment| segment.permissions)
+                    .unwrap_or(SectionPermissions {
+                        read: true,
+                        write: false,
+                        execute: false,
+                    });
+                let has_instructions = flags
+                    & (object::macho::S_ATTR_PURE_INSTRUCTIONS
+                        | object::macho::S_ATTR_SOME_INSTRUCTIONS)
+                    != 0;
+                SectionPermissions {
+                    execute: protection.execute && has_instructions,
+                    ..protection
+                }
+            }
+            _ => SectionPermissions {
+                read: true,
+                write: matches!(
+                    section.kind(),
+                    object::SectionKind::Data
+                        | object::SectionKind::UninitializedData
+                        | object::SectionKind::Tls
+                        | object::SectionKind::UninitializedTls
+                ),
+                execute: section.kind() == object::SectionKind::Text,
+            },
+        }
+    }
+
+    fn section_kind(section: &object::Section) -> SectionKind {
+        match section.kind() {
+            object::SectionKind::Text => SectionKind::Code,
+            object::SectionKind::Data
+            | object::SectionKind::ReadOnlyData
+            | object::SectionKind::ReadOnlyDataWithRel
+            | object::SectionKind::ReadOnlyString
+            | object::SectionKind::Tls => SectionKind::Data,
+            object::SectionKind::UninitializedData
+            | object::SectionKind::UninitializedTls
+            | object::SectionKind::Common => SectionKind::Bss,
+            object::SectionKind::Debug | object::SectionKind::DebugString => SectionKind::Debug,
+            _ => SectionKind::Other,
+        }
+    }
+
+    fn segment_permissions(segment: &object::Segment) -> SectionPermissions {
+        match segment.flags() {
+            SegmentFlags::Elf { p_flags } => SectionPermissions {
+                read: p_flags & object::elf::PF_R != 0,
+                write: p_flags & object::elf::PF_W != 0,
+                execute: p_flags & object::elf::PF_X != 0,
+            },
+            SegmentFlags::MachO { initprot, .. } => SectionPermissions {
+                read: initprot & object::macho::VM_PROT_READ != 0,
+                write: initprot & object::macho::VM_PROT_WRITE != 0,
+                execute: initprot & object::macho::VM_PROT_EXECUTE != 0,
+            },
+            SegmentFlags::Coff { characteristics } => SectionPermissions {
+                read: characteristics & object::pe::IMAGE_SCN_MEM_READ != 0,
+                write: characteristics & object::pe::IMAGE_SCN_MEM_WRITE != 0,
+                execute: characteristics & object::pe::IMAGE_SCN_MEM_EXECUTE != 0,
+            },
+            _ => SectionPermissions {
+                read: true,
+                write: false,
+                execute: false,
+            },
+        }
+    }
+
+    /// ELF segments have no name, so they are named after their index among
+    /// the loadable segments (e.g. `LOAD1`). PE segments are the sections themselves.
+    fn segments(header: &object::File) -> Vec<Segment> {
+        header
+            .segments()
+            .enumerate()
+            .map(|(i, segment)| {
+                let name = match segment.name() {
+                    Ok(Some(name)) if !name.is_empty() => name.to_string(),
+                    _ => format!("LOAD{}", i),
+                };
+                let (file_offset, file_size) = segment.file_range();
+                Segment {
+                    name,
+                    virtual_address: segment.address(),
+                    virtual_size: segment.size(),
+                    file_offset,
+                    file_size,
+                    permissions: Self::segment_permissions(&segment),
+                }
+            })
+            .collect()
+    }
+
+    /// Mach-O sections know the name of their segment, ELF sections are looked up
+    /// by address among the loadable segments.
+    fn section_segment(section: &object::Section, segments: &[Segment]) -> Option<String> {
+        if let Ok(Some(name)) = section.segment_name() {
+            return Some(name.to_string());
+        }
+        if section.address() == 0 {
+            return None;
+        }
+        segments
+            .iter()
+            .find(|segment| {
+                segment.virtual_address <= section.address()
+                    && section.address() < segment.virtual_address + segment.virtual_size
+            })
+            .map(|segment| segment.name.clone())
+    }
+
+    /// The ARM and AArch64 mapping symbols (`$a`, `$t`, `$d`, `$x`, optionally followed by a dot
+    /// and a suffix) mark the kind of the code that follows them, they are not shown as symbols.
+    fn is_mapping_symbol(architecture: object::Architecture, name: &str) -> bool {
+        if !matches!(
+            architecture,
+            object::Architecture::Arm | object::Architecture::Aarch64
+        ) {
+            return false;
+        }
+        let Some(kind) = name.strip_prefix('$') else {
+            return false;
+        };
+        let (kind, _) = kind.split_once('.').unwrap_or((kind, ""));
+        matches!(kind, "a" | "t" | "d" | "x")
+    }
+
+    /// The address of a symbol without the lowest bit, that is set in the address of Thumb functions.
+    fn code_address(architecture: object::Architecture, address: u64) -> u64 {
+        if architecture == object::Architecture::Arm {
+            address & !1
+        } else {
+            address
+        }
+    }
+
+    /// Finds where 32-bit ARM code switches between ARM and Thumb. The mapping symbols are used
+    /// when there are any, otherwise the lowest bit of the function symbols and of the entry point.
+    fn cpu_modes(header: &object::File) -> Vec<(u64, CpuMode)> {
+        if header.architecture() != object::Architecture::Arm {
+            return Vec::new();
+        }
+        let mut modes = header
+            .symbols()
+            .filter_map(|symbol| match symbol.name() {
+                Ok("$a") => Some((symbol.address(), CpuMode::Default)),
+                Ok("$t") => Some((symbol.address(), CpuMode::Thumb)),
+                Ok(name) if name.starts_with("$a.") => Some((symbol.address(), CpuMode::Default)),
+                Ok(name) if name.starts_with("$t.") => Some((symbol.address(), CpuMode::Thumb)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if modes.is_empty() {
+            let mode_of = |address: u64| {
+                let mode = if address & 1 != 0 {
+                    CpuMode::Thumb
+                } else {
+                    CpuMode::Default
+                };
+                (address & !1, mode)
+            };
+            modes = header
+                .symbols()
+                .filter(|symbol| symbol.kind() == object::SymbolKind::Text && symbol.address() != 0)
+                .map(|symbol| mode_of(symbol.address()))
+                .collect();
+            if header.entry() != 0 {
+                modes.push(mode_of(header.entry()));
+            }
+        }
+        modes.sort_by_key(|(address, _)| *address);
+        modes.dedup_by_key(|(address, _)| *address);
+        // only the addresses where the mode changes are kept
+        modes.dedup_by_key(|(_, mode)| *mode);
+        modes
+    }
+
+    /// Collects the functions described by the function symbols, by `.eh_frame` and by `.pdata`.
+    fn function_ranges(
+        header: &object::File,
+        bytes: &[u8],
+        pointer_size: usize,
+    ) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = header
+            .symbols()
+            .filter(|symbol| symbol.kind() == object::SymbolKind::Text && symbol.address() != 0)
+            .map(|symbol| {
+                (
+                    Self::code_address(header.architecture(), symbol.address()),
+                    symbol.size(),
+                )
+            })
+            .collect();
+        for section in header.sections() {
+            let (Ok(name), Some((offset, size))) = (section.name(), section.file_range()) else {
+                continue;
+            };
+            let Some(data) = bytes.get(offset as usize..(offset + size) as usize) else {
+                continue;
+            };
+            match name {
+                ".eh_frame" => ranges.extend(unwind::eh_frame_functions(
+                    data,
+                    section.address(),
+                    pointer_size,
+                    header.endianness(),
+                )),
+                ".pdata" => ranges.extend(unwind::pdata_functions(
+                    data,
+                    header.relative_address_base(),
+                    header.architecture(),
+                    |rva| {
+                        let address = header.relative_address_base() + rva;
+                        let offset = header.sections().find_map(|section| {
+                            let (offset, size) = section.file_range()?;
+                            let start = section.address();
+                            (start <= address && address + 4 <= start + size)
+                                .then_some(offset + address - start)
+                        })? as usize;
+                        bytes
+                            .get(offset..offset + 4)
+                            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                    },
+                )),
+                _ => {}
+            }
+        }
+        ranges
+    }
+
     pub fn parse_header(bytes: &[u8], file_path: &str, filesystem: &FileSystem) -> Option<Self> {
         let header = object::File::parse(bytes);
         if let Ok(header) = header {
@@ -65,27 +346,53 @@ impl GenericHeader {
             };
 
             let endianness = header.endianness();
+            let code_endianness = match header.flags() {
+                object::FileFlags::Elf { e_flags, .. }
+                    if architecture == object::Architecture::Arm
+                        && e_flags & object::elf::EF_ARM_BE8 != 0 =>
+                {
+                    object::Endianness::Little
+                }
+                _ => endianness,
+            };
 
-            let entry = header.entry();
+            let entry = Self::code_address(architecture, header.entry());
 
-            let sections = header
+            let segments = Self::segments(&header);
+            // PE and COFF segments are the sections themselves
+            let is_pe = !matches!(
+                file_type,
+                FileType::Elf32 | FileType::Elf64 | FileType::MachO32 | FileType::MachO64
+            );
+            let section_segments: &[Segment] = if is_pe { &[] } else { &segments };
+
+            let sections: Vec<Section> = header
                 .sections()
                 .map(|section| Section {
                     name: section.name().unwrap_or_default().to_string(),
                     file_offset: section.file_range().unwrap_or_default().0,
                     virtual_address: section.address(),
                     size: section.file_range().unwrap_or_default().1,
+                    permissions: Self::section_permissions(&section, &segments),
+                    kind: Self::section_kind(&section),
+                    alignment: section.align(),
+                    segment: Self::section_segment(&section, section_segments),
                 })
                 .filter(|section| section.size > 0)
                 .collect();
 
             let mut symbols: Vec<(u64, String)> = header
                 .symbols()
+                .filter(|symbol| {
+                    !Self::is_mapping_symbol(architecture, symbol.name().unwrap_or_default())
+                })
                 .map(|symbol| {
-                    (
-                        symbol.address(),
-                        symbol.name().unwrap_or_default().to_string(),
-                    )
+                    let address = if symbol.kind() == object::SymbolKind::Text {
+                        Self::code_address(architecture, symbol.address())
+                    } else {
+                        symbol.address()
+                    };
+                    (address, symbol.name().unwrap_or_default().to_string())
                 })
                 .collect();
 
@@ -183,25 +490,98 @@ impl GenericHeader {
                 }
             }
 
-            let symbols: HashMap<u64, String> = symbols
+            let mut symbols: HashMap<u64, String> = symbols
                 .into_iter()
                 .map(|(address, name)| (address, Self::demangle_symbol(&name)))
                 .collect();
 
+            let mut imports = imports::imports(&header, bytes);
+            let stub_suffix = if is_pe {
+                ""
+            } else if matches!(file_type, FileType::MachO32 | FileType::MachO64) {
+                "@stub"
+            } else {
+                "@plt"
+            };
+            // the calls through the slots and the stubs are shown with the name of the import
+            for import in imports.iter_mut() {
+                import.name = Self::demangle_symbol(&import.name);
+                if let Some(slot) = import.slot {
+                    symbols.insert(slot, import.to_string());
+                }
+                if let Some(stub) = import.stub {
+                    symbols.insert(stub, format!("{}{}", import, stub_suffix));
+                }
+            }
+            let mut relocations = relocations::relocations(&header, bytes);
+            // the loader also fills the import slots, the IAT is not in the PE base relocations
+            let pointer_size = if header.is_64() { 8 } else { 4 };
+            for import in imports.iter() {
+                let Some(slot) = import.slot else {
+                    continue;
+                };
+                let Some(file_address) = sections.iter().find_map(|section| {
+                    (section.virtual_address <= slot
+                        && slot < section.virtual_address + section.size)
+                        .then(|| section.file_offset + slot - section.virtual_address)
+                }) else {
+                    continue;
+                };
+                let index = relocations
+                    .partition_point(|relocation| relocation.file_address < file_address);
+                if relocations
+                    .get(index)
+                    .map(|relocation| relocation.file_address)
+                    != Some(file_address)
+                {
+                    relocations.insert(
+                        index,
+                        Relocation {
+                            file_address,
+                            virtual_address: slot,
+                            size: pointer_size,
+                            symbol: Some(import.to_string()),
+                        },
+                    );
+                }
+            }
+            let exports = imports::exports(&header)
+                .into_iter()
+                .map(|export| Export {
+                    name: Self::demangle_symbol(&export.name),
+                    address: Self::code_address(architecture, export.address),
+                })
+                .collect();
+
             let symbols_by_name = symbols
                 .iter()
                 .map(|(address, name)| (name.clone(), *address))
                 .collect();
 
+            let pointer_size = match bitness {
+                Bitness::Bit32 => 4,
+                Bitness::Bit64 => 8,
+            };
+            let function_ranges = Self::function_ranges(&header, bytes, pointer_size);
+            let cpu_modes = Self::cpu_modes(&header);
+
             Some(GenericHeader {
                 file_type,
                 architecture,
                 bitness,
                 endianness,
+                code_endianness,
                 entry,
                 sections,
+                segments,
                 symbols,
                 symbols_by_name,
+                function_ranges,
+                cpu_modes,
+                import_slots: imports.iter().filter_map(|import| import.slot).collect(),
+                imports,
+                exports,
+                relocations,
             })
         } else {
             None
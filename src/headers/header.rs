@@ -1,17 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use capstone::{
-    arch::{self, BuildsCapstone},
-    Capstone, CsResult,
+    arch::{
+        self, arm::ArmReg, arm64::Arm64Reg, m68k::M68kReg, mips::MipsReg, ppc::PpcReg,
+        riscv::RiscVReg, sparc::SparcReg, x86::X86Reg, BuildsCapstone, BuildsCapstoneEndian,
+        BuildsCapstoneSyntax,
+    },
+    Capstone, CsResult, RegId, RegIdInt,
 };
-use keystone_engine::{Arch, Keystone, KeystoneError, Mode};
+use keystone_engine::{Arch, Keystone, KeystoneError, Mode, OptionType, OptionValue};
 use mlua::UserData;
 use object::{Architecture, Endianness};
 
-use crate::app::files::filesystem::FileSystem;
+use crate::app::{
+    files::filesystem::FileSystem, settings::app_settings::X86Syntax, templates::template::Template,
+};
 
 use super::{
-    bitness::Bitness, custom_header::CustomHeader, generic::GenericHeader, section::Section,
+    bitness::Bitness,
+    custom_header::CustomHeader,
+    generic::{FileType, GenericHeader},
+    imports::{Export, Import},
+    relocations::Relocation,
+    section::{Section, SectionPermissions},
+    segment::Segment,
+    target::CpuMode,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -77,6 +90,28 @@ impl Header {
         }
     }
 
+    /// Returns the ranges of the file that are mapped in memory, in the order they appear in the header.
+    pub fn get_segments(&self) -> Vec<Segment> {
+        match self {
+            Header::GenericHeader(header) => header.segments.clone(),
+            Header::CustomHeader(_) | Header::None => Vec::new(),
+        }
+    }
+
+    /// Returns the sections, or the segments if the file has no section headers.
+    pub fn get_sections_or_segments(&self) -> Vec<Section> {
+        let sections = self.get_sections();
+        if sections.is_empty() {
+            self.get_segments()
+                .iter()
+                .filter(|segment| segment.file_size > 0)
+                .map(Segment::to_section)
+                .collect()
+        } else {
+            sections
+        }
+    }
+
     pub fn get_text_section(&self) -> Option<Section> {
         match self {
             Header::GenericHeader(header) => {
@@ -99,6 +134,51 @@ impl Header {
         }
     }
 
+    /// Returns the built-in template that describes the headers of the file, if the format is supported.
+    pub fn get_header_template(&self) -> Option<Template> {
+        let json = match self {
+            Header::GenericHeader(header) => match header.file_type {
+                FileType::Elf32 | FileType::Elf64 => include_str!("templates/elf.json"),
+                FileType::Pe32 | FileType::Pe64 => include_str!("templates/pe.json"),
+                FileType::MachO32 | FileType::MachO64 => include_str!("templates/macho.json"),
+                _ => return None,
+            },
+            Header::CustomHeader(_) | Header::None => return None,
+        };
+        Some(Template::from_json(json).expect("Built-in header templates should be valid"))
+    }
+
+    /// Returns the sorted byte ranges that affect the parsed header: the headers described by the
+    /// header template and the symbol and string tables. Editing them requires parsing the header again.
+    pub fn get_structural_ranges(&self, bytes: &[u8]) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        if let Some(root) = self
+            .get_header_template()
+            .and_then(|template| template.apply(bytes, 0, self.endianness()).ok())
+        {
+            root.collect_leaf_ranges(&mut ranges);
+        }
+        for section in self.get_sections() {
+            if matches!(
+                section.name.as_str(),
+                ".symtab" | ".strtab" | ".dynsym" | ".dynstr" | ".shstrtab"
+            ) {
+                ranges.push((section.file_offset as usize, section.size as usize));
+            }
+        }
+        ranges.sort();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (offset, size) in ranges {
+            match merged.last_mut() {
+                Some((last_offset, last_size)) if offset <= *last_offset + *last_size => {
+                    *last_size = (*last_size).max(offset + size - *last_offset);
+                }
+                _ => merged.push((offset, size)),
+            }
+        }
+        merged
+    }
+
     pub fn get_symbols(&self) -> Option<&HashMap<u64, String>> {
         match self {
             Header::GenericHeader(header) => Some(&header.symbols),
@@ -107,6 +187,60 @@ impl Header {
         }
     }
 
+    /// Returns the start and the size of the functions known from the header,
+    /// the size is 0 if it is unknown.
+    pub fn get_function_ranges(&self) -> &[(u64, u64)] {
+        match self {
+            Header::GenericHeader(header) => &header.function_ranges,
+            Header::CustomHeader(_) | Header::None => &[],
+        }
+    }
+
+    pub fn get_imports(&self) -> &[Import] {
+        match self {
+            Header::GenericHeader(header) => &header.imports,
+            Header::CustomHeader(_) | Header::None => &[],
+        }
+    }
+
+    /// Returns whether the loader writes the address of an import at `virtual_address`.
+    pub fn is_import_slot(&self, virtual_address: u64) -> bool {
+        match self {
+            Header::GenericHeader(header) => header.import_slots.contains(&virtual_address),
+            Header::CustomHeader(_) | Header::None => false,
+        }
+    }
+
+    pub fn get_exports(&self) -> &[Export] {
+        match self {
+            Header::GenericHeader(header) => &header.exports,
+            Header::CustomHeader(_) | Header::None => &[],
+        }
+    }
+
+    pub fn get_relocations(&self) -> &[Relocation] {
+        match self {
+            Header::GenericHeader(header) => &header.relocations,
+            Header::CustomHeader(_) | Header::None => &[],
+        }
+    }
+
+    /// Returns the relocations that rewrite some of the `len` bytes at `file_address`.
+    pub fn relocations_in(
+        &self,
+        file_address: u64,
+        len: u64,
+    ) -> impl Iterator<Item = &Relocation> + '_ {
+        let relocations = self.get_relocations();
+        // no relocation is longer than 8 bytes
+        let first =
+            relocations.partition_point(|relocation| relocation.file_address + 8 <= file_address);
+        relocations[first..]
+            .iter()
+            .take_while(move |relocation| relocation.file_address < file_address + len)
+            .filter(move |relocation| relocation.file_address + relocation.size > file_address)
+    }
+
     pub fn symbol_to_address(&self, symbol: &str) -> Option<u64> {
         match self {
             Header::GenericHeader(header) => header.symbols_by_name.get(symbol).cloned(),
@@ -115,62 +249,115 @@ impl Header {
         }
     }
 
+    /// Sections without any permission (e.g. ELF sections without `SHF_ALLOC`) are not loaded in memory,
+    /// so they are skipped. Addresses that are not in any section are looked up in the segments.
     pub fn virtual_to_physical_address(&self, virtual_address: u64) -> Option<u64> {
         self.get_sections()
             .iter()
+            .filter(|x| x.permissions != SectionPermissions::default())
             .find(|x| {
                 virtual_address >= x.virtual_address && virtual_address < x.virtual_address + x.size
             })
             .map(|x| x.file_offset + virtual_address - x.virtual_address)
+            .or_else(|| {
+                self.get_segments()
+                    .iter()
+                    .find_map(|segment| segment.virtual_to_physical_address(virtual_address))
+            })
     }
 
     pub fn physical_to_virtual_address(&self, physical_address: u64) -> Option<u64> {
         self.get_sections()
             .iter()
+            .filter(|x| x.permissions != SectionPermissions::default())
             .find(|x| {
                 physical_address >= x.file_offset && physical_address < x.file_offset + x.size
             })
             .map(|x| x.virtual_address + physical_address - x.file_offset)
+            .or_else(|| {
+                self.get_segments()
+                    .iter()
+                    .find_map(|segment| segment.physical_to_virtual_address(physical_address))
+            })
     }
 
-    pub(super) fn get_decoder_for_arch(architecture: &Architecture) -> CsResult<Capstone> {
+    /// True if the instruction after a branch, the delay slot, is executed before the branch is taken.
+    pub fn has_delay_slot(&self) -> bool {
+        matches!(
+            self.architecture(),
+            Architecture::Mips
+                | Architecture::Mips64
+                | Architecture::Mips64_N32
+                | Architecture::Sparc
+                | Architecture::Sparc32Plus
+                | Architecture::Sparc64
+        )
+    }
+
+    pub(super) fn get_decoder_for_arch(
+        architecture: &Architecture,
+        mode: CpuMode,
+        endianness: Endianness,
+        bitness: Bitness,
+        syntax: X86Syntax,
+    ) -> CsResult<Capstone> {
+        let endian = match endianness {
+            Endianness::Little => capstone::Endian::Little,
+            Endianness::Big => capstone::Endian::Big,
+        };
+        let x86_syntax = match syntax {
+            X86Syntax::Intel => arch::x86::ArchSyntax::Intel,
+            X86Syntax::Att => arch::x86::ArchSyntax::Att,
+            X86Syntax::Masm => arch::x86::ArchSyntax::Masm,
+        };
         match architecture {
-            Architecture::Aarch64 => Capstone::new()
+            // arm64e and ARM64EC are sub-architectures of Aarch64 and use the same decoder
+            Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => Capstone::new()
                 .arm64()
                 .mode(arch::arm64::ArchMode::Arm)
+                .endian(endian)
                 .build(),
-            Architecture::Aarch64_Ilp32 => Capstone::new()
-                .arm64()
-                .mode(arch::arm64::ArchMode::Arm)
+            Architecture::Arm => Capstone::new()
+                .arm()
+                .mode(match mode {
+                    CpuMode::Thumb => arch::arm::ArchMode::Thumb,
+                    _ => arch::arm::ArchMode::Arm,
+                })
+                .endian(endian)
                 .build(),
-            Architecture::Arm => Capstone::new().arm().mode(arch::arm::ArchMode::Arm).build(),
             Architecture::I386 => Capstone::new()
                 .x86()
-                .mode(arch::x86::ArchMode::Mode32)
-                .build(),
-            Architecture::X86_64 => Capstone::new()
-                .x86()
-                .mode(arch::x86::ArchMode::Mode64)
+                .mode(match mode {
+                    CpuMode::Bit16 => arch::x86::ArchMode::Mode16,
+                    _ => arch::x86::ArchMode::Mode32,
+                })
+                .syntax(x86_syntax)
                 .build(),
-            Architecture::X86_64_X32 => Capstone::new()
+            Architecture::X86_64 | Architecture::X86_64_X32 => Capstone::new()
                 .x86()
                 .mode(arch::x86::ArchMode::Mode64)
+                .syntax(x86_syntax)
                 .build(),
-            Architecture::Mips => Capstone::new()
+            Architecture::Mips if bitness == Bitness::Bit32 => Capstone::new()
                 .mips()
                 .mode(arch::mips::ArchMode::Mips32)
+                .endian(endian)
                 .build(),
-            Architecture::Mips64 => Capstone::new()
+            // the N32 ABI runs 64-bit code in 32-bit files
+            Architecture::Mips | Architecture::Mips64 | Architecture::Mips64_N32 => Capstone::new()
                 .mips()
                 .mode(arch::mips::ArchMode::Mips64)
+                .endian(endian)
                 .build(),
             Architecture::PowerPc => Capstone::new()
                 .ppc()
                 .mode(arch::ppc::ArchMode::Mode32)
+                .endian(endian)
                 .build(),
             Architecture::PowerPc64 => Capstone::new()
                 .ppc()
                 .mode(arch::ppc::ArchMode::Mode64)
+                .endian(endian)
                 .build(),
             Architecture::Riscv32 => Capstone::new()
                 .riscv()
@@ -180,64 +367,198 @@ impl Header {
                 .riscv()
                 .mode(arch::riscv::ArchMode::RiscV64)
                 .build(),
+            Architecture::M68k => Capstone::new()
+                .m68k()
+                .mode(arch::m68k::ArchMode::M68k040)
+                .build(),
             Architecture::S390x => Capstone::new()
                 .sysz()
                 .mode(arch::sysz::ArchMode::Default)
                 .build(),
+            Architecture::Sparc | Architecture::Sparc32Plus => Capstone::new()
+                .sparc()
+                .mode(arch::sparc::ArchMode::Default)
+                .build(),
             Architecture::Sparc64 => Capstone::new()
                 .sparc()
                 .mode(arch::sparc::ArchMode::V9)
                 .build(),
-            _ => Capstone::new()
-                .x86()
-                .mode(arch::x86::ArchMode::Mode64)
-                .build(),
+            _ => Err(capstone::Error::UnsupportedArch),
         }
     }
 
+    /// Keystone has no MASM syntax, MASM code is assembled as Intel code, see [`crate::asm::assembler::assemble`].
+    fn get_x86_encoder(mode: Mode, syntax: X86Syntax) -> Result<Keystone, KeystoneError> {
+        let encoder = Keystone::new(Arch::X86, mode)?;
+        if syntax == X86Syntax::Att {
+            encoder.option(OptionType::SYNTAX, OptionValue::SYNTAX_ATT)?;
+        }
+        Ok(encoder)
+    }
+
     pub(super) fn get_encoder_for_arch(
         architecture: &Architecture,
+        mode: CpuMode,
+        endianness: Endianness,
+        bitness: Bitness,
+        syntax: X86Syntax,
     ) -> Result<Keystone, KeystoneError> {
+        let endian = match endianness {
+            Endianness::Little => Mode::LITTLE_ENDIAN,
+            Endianness::Big => Mode::BIG_ENDIAN,
+        };
         match architecture {
-            Architecture::Aarch64 => Keystone::new(Arch::ARM64, Mode::LITTLE_ENDIAN),
-            Architecture::Aarch64_Ilp32 => Keystone::new(Arch::ARM64, Mode::LITTLE_ENDIAN),
-            Architecture::Arm => Keystone::new(Arch::ARM, Mode::ARM),
-            Architecture::I386 => Keystone::new(Arch::X86, Mode::MODE_32),
-            Architecture::X86_64 => Keystone::new(Arch::X86, Mode::MODE_64),
-            Architecture::X86_64_X32 => Keystone::new(Arch::X86, Mode::MODE_32),
+            Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => {
+                Keystone::new(Arch::ARM64, endian)
+            }
+            Architecture::Arm => match mode {
+                CpuMode::Thumb => Keystone::new(Arch::ARM, Mode::THUMB | endian),
+                _ => Keystone::new(Arch::ARM, Mode::ARM | endian),
+            },
+            Architecture::I386 => match mode {
+                CpuMode::Bit16 => Self::get_x86_encoder(Mode::MODE_16, syntax),
+                _ => Self::get_x86_encoder(Mode::MODE_32, syntax),
+            },
+            Architecture::X86_64 | Architecture::X86_64_X32 => {
+                Self::get_x86_encoder(Mode::MODE_64, syntax)
+            }
             Architecture::Hexagon => Keystone::new(Arch::HEXAGON, Mode::MODE_32),
-            Architecture::Mips => Keystone::new(Arch::MIPS, Mode::MIPS32),
-            Architecture::Mips64 => Keystone::new(Arch::MIPS, Mode::MIPS64),
-            Architecture::PowerPc => Keystone::new(Arch::PPC, Mode::PPC32),
-            Architecture::PowerPc64 => Keystone::new(Arch::PPC, Mode::PPC64),
-            Architecture::S390x => Keystone::new(Arch::SYSTEMZ, Mode::MODE_32),
-            Architecture::Sparc64 => Keystone::new(Arch::SPARC, Mode::SPARC64),
-            _ => Keystone::new(Arch::X86, Mode::MODE_64),
+            Architecture::Mips if bitness == Bitness::Bit32 => {
+                Keystone::new(Arch::MIPS, Mode::MIPS32 | endian)
+            }
+            Architecture::Mips | Architecture::Mips64 | Architecture::Mips64_N32 => {
+                Keystone::new(Arch::MIPS, Mode::MIPS64 | endian)
+            }
+            Architecture::PowerPc => Keystone::new(Arch::PPC, Mode::PPC32 | endian),
+            Architecture::PowerPc64 => Keystone::new(Arch::PPC, Mode::PPC64 | endian),
+            Architecture::S390x => Keystone::new(Arch::SYSTEMZ, Mode::BIG_ENDIAN),
+            Architecture::Sparc | Architecture::Sparc32Plus => {
+                Keystone::new(Arch::SPARC, Mode::SPARC32 | Mode::BIG_ENDIAN)
+            }
+            Architecture::Sparc64 => Keystone::new(Arch::SPARC, Mode::SPARC64 | Mode::BIG_ENDIAN),
+            // M68k and RISC-V can only be disassembled
+            _ => Err(KeystoneError::Engine(keystone_engine::Error::ARCH)),
         }
     }
 
-    pub fn get_decoder(&self) -> CsResult<Capstone> {
-        let ret = match self {
-            Header::GenericHeader(header) => Self::get_decoder_for_arch(&header.architecture),
-            Header::CustomHeader(header) => Self::get_decoder_for_arch(&header.architecture),
-            Header::None => Capstone::new()
-                .x86()
-                .mode(capstone::arch::x86::ArchMode::Mode64)
-                .build(),
-        };
-        ret.map(|mut cs| {
-            cs.set_skipdata(true).expect("Failed to set skipdata");
-            cs
-        })
+    /// The architecture, the mode, the endianness of the instructions and the bitness
+    /// used to build the decoder and the encoder.
+    fn instruction_set(&self) -> (Architecture, CpuMode, Endianness, Bitness) {
+        match self {
+            Header::GenericHeader(header) => (
+                header.architecture,
+                CpuMode::Default,
+                header.code_endianness,
+                header.bitness,
+            ),
+            Header::CustomHeader(header) => (
+                header.architecture,
+                header.mode,
+                header.endianness,
+                header.bitness,
+            ),
+            Header::None => (
+                Architecture::X86_64,
+                CpuMode::Default,
+                Endianness::Little,
+                Bitness::Bit64,
+            ),
+        }
     }
 
-    pub fn get_encoder(&self) -> Result<Keystone, KeystoneError> {
+    /// The instruction set used at `virtual_address`, 32-bit ARM binaries can mix ARM and Thumb code.
+    pub fn cpu_mode_at(&self, virtual_address: u64) -> CpuMode {
+        let (_, mode, _, _) = self.instruction_set();
         match self {
-            Header::GenericHeader(header) => Self::get_encoder_for_arch(&header.architecture),
-            Header::CustomHeader(header) => Self::get_encoder_for_arch(&header.architecture),
-            Header::None => Keystone::new(Arch::X86, Mode::MODE_64),
+            Header::GenericHeader(header) => {
+                let index = header
+                    .cpu_modes
+                    .partition_point(|(address, _)| *address <= virtual_address);
+                match index.checked_sub(1) {
+                    Some(index) => header.cpu_modes[index].1,
+                    None => mode,
+                }
+            }
+            Header::CustomHeader(_) | Header::None => mode,
         }
     }
+
+    /// Splits the code between `start` included and `end` excluded into the ranges
+    /// that use the same instruction set.
+    pub fn cpu_mode_ranges(&self, start: u64, end: u64) -> Vec<(u64, u64, CpuMode)> {
+        let mut ranges = vec![(start, end, self.cpu_mode_at(start))];
+        if let Header::GenericHeader(header) = self {
+            for (address, mode) in header.cpu_modes.iter().copied() {
+                if address <= start || address >= end {
+                    continue;
+                }
+                let last = ranges.last_mut().expect("There is always a range");
+                last.1 = address;
+                ranges.push((address, end, mode));
+            }
+        }
+        ranges
+    }
+
+    /// The names of the registers of the architecture, as printed by Capstone.
+    pub fn register_names(&self) -> HashSet<String> {
+        let Ok(decoder) = self.get_decoder(X86Syntax::Intel) else {
+            return HashSet::new();
+        };
+        let (architecture, _, _, _) = self.instruction_set();
+        let ending = match architecture {
+            Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => Arm64Reg::ARM64_REG_ENDING,
+            Architecture::Arm => ArmReg::ARM_REG_ENDING,
+            Architecture::I386 | Architecture::X86_64 | Architecture::X86_64_X32 => {
+                X86Reg::X86_REG_ENDING
+            }
+            Architecture::M68k => M68kReg::M68K_REG_ENDING,
+            Architecture::Mips | Architecture::Mips64 | Architecture::Mips64_N32 => {
+                MipsReg::MIPS_REG_ENDING
+            }
+            Architecture::PowerPc | Architecture::PowerPc64 => PpcReg::PPC_REG_ENDING,
+            Architecture::Riscv32 | Architecture::Riscv64 => RiscVReg::RISCV_REG_ENDING,
+            Architecture::Sparc | Architecture::Sparc32Plus | Architecture::Sparc64 => {
+                SparcReg::SPARC_REG_ENDING
+            }
+            // the SystemZ registers are written with a `%` prefix
+            _ => 0,
+        };
+        (1..ending)
+            .filter_map(|id| decoder.reg_name(RegId(id as RegIdInt)))
+            .collect()
+    }
+
+    /// `syntax` is used only by x86 code.
+    pub fn get_decoder(&self, syntax: X86Syntax) -> CsResult<Capstone> {
+        let (_, mode, _, _) = self.instruction_set();
+        self.get_decoder_for_mode(mode, syntax)
+    }
+
+    pub fn get_decoder_for_mode(&self, mode: CpuMode, syntax: X86Syntax) -> CsResult<Capstone> {
+        let (architecture, _, endianness, bitness) = self.instruction_set();
+        Self::get_decoder_for_arch(&architecture, mode, endianness, bitness, syntax).map(
+            |mut cs| {
+                cs.set_skipdata(true).expect("Failed to set skipdata");
+                cs
+            },
+        )
+    }
+
+    /// `syntax` is used only by x86 code.
+    pub fn get_encoder(&self, syntax: X86Syntax) -> Result<Keystone, KeystoneError> {
+        let (_, mode, _, _) = self.instruction_set();
+        self.get_encoder_for_mode(mode, syntax)
+    }
+
+    pub fn get_encoder_for_mode(
+        &self,
+        mode: CpuMode,
+        syntax: X86Syntax,
+    ) -> Result<Keystone, KeystoneError> {
+        let (architecture, _, endianness, bitness) = self.instruction_set();
+        Self::get_encoder_for_arch(&architecture, mode, endianness, bitness, syntax)
+    }
 }
 
 impl UserData for Header {
@@ -248,6 +569,7 @@ impl UserData for Header {
             Ok(format!("{:?}", this.architecture()))
         });
         fields.add_field_method_get("sections", |_, this| Ok(this.get_sections()));
+        fields.add_field_method_get("segments", |_, this| Ok(this.get_segments()));
         fields.add_field_method_get("text_section", |_, this| Ok(this.get_text_section()));
         fields.add_field_method_get("symbols", |_, this| {
             Ok(this
@@ -269,9 +591,30 @@ impl UserData for Header {
 
 #[cfg(test)]
 mod test {
-    use crate::headers::generic::FileType;
+    use crate::{
+        app::templates::{template_node::TemplateNode, template_value::TemplateValue},
+        headers::section::SectionKind,
+    };
 
     use super::*;
+
+    fn find_value(node: &TemplateNode, path: &[&str]) -> TemplateValue {
+        let mut node = node;
+        for name in path {
+            node = node
+                .children()
+                .iter()
+                .find(|child| child.name == *name)
+                .unwrap_or_else(|| panic!("Field {} not found", name));
+        }
+        match &node.content {
+            crate::app::templates::template_node::TemplateNodeContent::Value { value, .. } => {
+                value.clone()
+            }
+            _ => panic!("Not a value"),
+        }
+    }
+
     #[test]
     fn test_parse_elf() {
         let data = include_bytes!("../../test/elf.bin");
@@ -281,6 +624,14 @@ mod test {
         } else {
             panic!("Failed to parse ELF header.");
         }
+        let text = header.get_text_section().unwrap();
+        assert_eq!(text.attributes(), "r-x code align 0x10 in LOAD1");
+        let comment = header
+            .get_sections()
+            .into_iter()
+            .find(|section| section.name == ".comment")
+            .unwrap();
+        assert_eq!(comment.attributes(), "--- other");
         assert_eq!(header.architecture(), Architecture::X86_64);
         assert_eq!(header.bitness(), 64);
         assert_eq!(header.endianness(), Endianness::Little);
@@ -310,8 +661,390 @@ mod test {
         } else {
             panic!("Failed to parse Mach-O header.");
         }
+        let text = header.get_text_section().unwrap();
+        assert_eq!(text.segment.as_deref(), Some("__TEXT"));
+        assert_eq!(text.kind, SectionKind::Code);
+        for section in header.get_sections() {
+            assert_eq!(
+                section.permissions.execute,
+                matches!(
+                    section.name.as_str(),
+                    "__text" | "__stubs" | "__stub_helper"
+                ),
+                "{}",
+                section.name
+            );
+        }
         assert_eq!(header.architecture(), Architecture::X86_64);
         assert_eq!(header.bitness(), 64);
         assert_eq!(header.endianness(), Endianness::Little);
     }
+
+    #[test]
+    fn test_big_endian() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        // the instruction at `assembled` is assembled again and compared with the file
+        for (path, architecture, expected, assembled) in [
+            (
+                "test/elf_mips_be.bin",
+                Architecture::Mips,
+                vec!["addiu $sp, $sp, -8", "jr $ra", "nop"],
+                0,
+            ),
+            (
+                "test/elf_ppc_be.bin",
+                Architecture::PowerPc,
+                vec!["li r3, 0", "blr"],
+                1,
+            ),
+            (
+                "test/elf_arm_be8.bin",
+                Architecture::Arm,
+                vec!["mov r0, #0", "bx lr"],
+                0,
+            ),
+            (
+                "test/elf_sparc.bin",
+                Architecture::Sparc,
+                vec!["retl", "nop"],
+                0,
+            ),
+        ] {
+            let data = std::fs::read(path).unwrap();
+            let header = Header::parse_header(&data, path, &filesystem);
+            assert_eq!(header.architecture(), architecture, "{}", path);
+            assert_eq!(header.endianness(), Endianness::Big, "{}", path);
+            assert_eq!(header.bitness(), 32, "{}", path);
+
+            let text = header.get_text_section().unwrap();
+            let code = &data[text.file_offset as usize..(text.file_offset + text.size) as usize];
+            let decoder = header.get_decoder(X86Syntax::Intel).unwrap();
+            let instructions = decoder.disasm_all(code, text.virtual_address).unwrap();
+            let decoded = instructions
+                .iter()
+                .map(|instruction| {
+                    format!(
+                        "{} {}",
+                        instruction.mnemonic().unwrap(),
+                        instruction.op_str().unwrap()
+                    )
+                    .trim()
+                    .to_string()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(decoded, expected, "{}", path);
+
+            let offset = assembled * 4;
+            let encoded = header
+                .get_encoder(X86Syntax::Intel)
+                .unwrap()
+                .asm(
+                    expected[assembled].to_string(),
+                    text.virtual_address + offset as u64,
+                )
+                .unwrap()
+                .bytes;
+            assert_eq!(encoded, code[offset..offset + 4], "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_arm_thumb() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        // with the mapping symbols and with only the lowest bit of the function symbols
+        for path in ["test/elf_arm_thumb.bin", "test/elf_arm_thumb_nomap.bin"] {
+            let data = std::fs::read(path).unwrap();
+            let header = Header::parse_header(&data, path, &filesystem);
+            assert_eq!(header.architecture(), Architecture::Arm, "{}", path);
+            assert_eq!(header.cpu_mode_at(0x10060), CpuMode::Default, "{}", path);
+            assert_eq!(header.cpu_mode_at(0x10068), CpuMode::Thumb, "{}", path);
+            assert_eq!(
+                header.cpu_mode_ranges(0x10060, 0x10070),
+                vec![
+                    (0x10060, 0x10068, CpuMode::Default),
+                    (0x10068, 0x10070, CpuMode::Thumb)
+                ],
+                "{}",
+                path
+            );
+            // the lowest bit of the Thumb addresses is not part of the address
+            assert_eq!(header.entry_point(), 0x10068, "{}", path);
+            assert_eq!(header.symbol_to_address("thumb"), Some(0x10068), "{}", path);
+            assert!(header.symbol_to_address("$t").is_none(), "{}", path);
+            assert!(header.get_function_ranges().contains(&(0x10068, 4)));
+        }
+    }
+
+    #[test]
+    fn test_header_templates() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        let data = include_bytes!("../../test/elf.bin");
+        let header = Header::parse_header(data, "./elf.bin", &filesystem);
+        let root = header
+            .get_header_template()
+            .unwrap()
+            .apply(data, 0, header.endianness())
+            .unwrap();
+        assert_eq!(
+            find_value(&root, &["ehdr", "e_ident", "EI_MAG"]),
+            TemplateValue::String("\x7FELF".into())
+        );
+        assert_eq!(
+            find_value(&root, &["ehdr", "e_entry"]),
+            TemplateValue::Unsigned(header.entry_point())
+        );
+        let shnum = find_value(&root, &["ehdr", "e_shnum"]);
+        let shdrs = root.children().iter().find(|c| c.name == "shdrs").unwrap();
+        assert_eq!(
+            shnum,
+            TemplateValue::Unsigned(shdrs.children().len() as u64)
+        );
+        assert!(root.children().iter().any(|c| c.name == "phdrs"));
+
+        let data = include_bytes!("../../test/pe.bin");
+        let header = Header::parse_header(data, "./pe.bin", &filesystem);
+        let root = header
+            .get_header_template()
+            .unwrap()
+            .apply(data, 0, header.endianness())
+            .unwrap();
+        assert_eq!(
+            find_value(&root, &["nt_headers", "Signature"]),
+            TemplateValue::String("PE".into())
+        );
+        assert_eq!(
+            find_value(&root, &["nt_headers", "OptionalHeader", "Magic"]),
+            TemplateValue::Unsigned(0x20B)
+        );
+        let sections = root
+            .children()
+            .iter()
+            .find(|c| c.name == "section_headers")
+            .unwrap();
+        assert_eq!(sections.children().len(), header.get_sections().len());
+
+        let data = include_bytes!("../../test/macho.bin");
+        let header = Header::parse_header(data, "./macho.bin", &filesystem);
+        let root = header
+            .get_header_template()
+            .unwrap()
+            .apply(data, 0, header.endianness())
+            .unwrap();
+        assert_eq!(
+            find_value(&root, &["header", "magic"]),
+            TemplateValue::Unsigned(0xFEEDFACF)
+        );
+        let load_commands = root
+            .children()
+            .iter()
+            .find(|c| c.name == "load_commands")
+            .unwrap();
+        assert_eq!(
+            find_value(&root, &["header", "sizeofcmds"]),
+            TemplateValue::Unsigned(load_commands.size as u64)
+        );
+
+        assert!(Header::None.get_header_template().is_none());
+    }
+
+    #[test]
+    fn test_structural_ranges() {
+        let data = include_bytes!("../../test/elf.bin");
+        let header = Header::parse_header(data, "./elf.bin", &FileSystem::new_local(".").unwrap());
+        let ranges = header.get_structural_ranges(data);
+        assert_eq!(ranges[0], (0, 0x40 + 0x38 * 14));
+        assert!(ranges.windows(2).all(|w| w[0].0 + w[0].1 < w[1].0));
+        let text = header.get_text_section().unwrap();
+        assert!(!ranges.iter().any(|(offset, size)| {
+            *offset < (text.file_offset + text.size) as usize
+                && (text.file_offset as usize) < offset + size
+        }));
+        assert!(Header::None.get_structural_ranges(data).is_empty());
+    }
+
+    #[test]
+    fn test_segments() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        let data = include_bytes!("../../test/elf.bin");
+        let header = Header::parse_header(data, "./elf.bin", &filesystem);
+        let segments = header.get_segments();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[1].name, "LOAD1");
+        assert_eq!(segments[1].permissions.to_string(), "r-x");
+        assert_eq!(segments[3].virtual_address, 0x22CB08);
+        assert_eq!(segments[3].file_offset, 0x22BB08);
+        // the ELF header is not in any section, but it is mapped by the first segment
+        assert_eq!(header.virtual_to_physical_address(0x10), Some(0x10));
+        assert_eq!(header.physical_to_virtual_address(0x10), Some(0x10));
+        // .bss is in memory only
+        assert_eq!(header.virtual_to_physical_address(0x23C900), None);
+
+        let data = include_bytes!("../../test/pe.bin");
+        let header = Header::parse_header(data, "./pe.bin", &filesystem);
+        let segments = header.get_segments();
+        assert_eq!(segments.len(), header.get_sections().len());
+        assert_eq!(segments[0].name, ".text");
+        assert_eq!(segments[2].permissions.to_string(), "rw-");
+
+        let data = include_bytes!("../../test/macho.bin");
+        let header = Header::parse_header(data, "./macho.bin", &filesystem);
+        let segments = header.get_segments();
+        assert_eq!(
+            segments
+                .iter()
+                .map(|segment| segment.name.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "__PAGEZERO",
+                "__TEXT",
+                "__DATA_CONST",
+                "__DATA",
+                "__LINKEDIT"
+            ]
+        );
+        assert_eq!(segments[1].permissions.to_string(), "r-x");
+        // the load commands are mapped by __TEXT
+        assert_eq!(header.virtual_to_physical_address(0x100000020), Some(0x20));
+
+        assert!(Header::None.get_segments().is_empty());
+    }
+
+    #[test]
+    fn test_imports() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        let find = |header: &Header, name: &str| {
+            header
+                .get_imports()
+                .iter()
+                .find(|import| import.name == name)
+                .cloned()
+                .unwrap_or_else(|| panic!("Import {} not found", name))
+        };
+
+        let data = include_bytes!("../../test/elf.bin");
+        let header = Header::parse_header(data, "./elf.bin", &filesystem);
+        assert_eq!(header.get_imports().len(), 120);
+        let import = find(&header, "__cxa_finalize");
+        assert_eq!(import.to_string(), "libc!__cxa_finalize");
+        assert_eq!(import.slot, Some(0x23A2F0));
+        assert!(header.is_import_slot(0x23A2F0));
+        assert!(!header.is_import_slot(0x18050));
+        assert_eq!(import.stub, Some(0x18050));
+        assert_eq!(
+            header.symbol_to_address("libc!__cxa_finalize@plt"),
+            Some(0x18050)
+        );
+        assert_eq!(find(&header, "__tls_get_addr").stub, Some(0x18030));
+
+        let data = include_bytes!("../../test/pe.bin");
+        let header = Header::parse_header(data, "./pe.bin", &filesystem);
+        let import = find(&header, "SystemFunction036");
+        assert_eq!(import.library.as_deref(), Some("advapi32"));
+        assert_eq!(import.slot, Some(0x140190000));
+        assert_eq!(import.stub, None);
+        // a `call qword ptr [rip + X]` through the IAT is named after the import
+        assert_eq!(
+            header.get_symbols().unwrap().get(&0x140190000).unwrap(),
+            "advapi32!SystemFunction036"
+        );
+
+        let data = include_bytes!("../../test/macho.bin");
+        let header = Header::parse_header(data, "./macho.bin", &filesystem);
+        let import = find(&header, "__NSGetArgc");
+        assert_eq!(import.library.as_deref(), Some("libSystem"));
+        assert_eq!(import.slot, Some(0x100200000));
+        assert_eq!(import.stub, Some(0x100199440));
+        assert!(header
+            .get_exports()
+            .iter()
+            .any(|export| export.name == "_main" && export.address == 0x100012910));
+
+        assert!(Header::None.get_imports().is_empty());
+    }
+
+    #[test]
+    fn test_relocations() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        let data = include_bytes!("../../test/elf.bin");
+        let header = Header::parse_header(data, "./elf.bin", &filesystem);
+        assert_eq!(header.get_relocations().len(), 3789);
+        let relocations = header.relocations_in(0x22BB0C, 1).collect::<Vec<_>>();
+        assert_eq!(relocations.len(), 1);
+        assert_eq!(relocations[0].file_address, 0x22BB08);
+        assert_eq!(relocations[0].virtual_address, 0x22CB08);
+        assert_eq!(relocations[0].size, 8);
+        assert_eq!(relocations[0].symbol, None);
+        assert_eq!(header.relocations_in(0x22BB00, 8).count(), 0);
+        assert_eq!(
+            header
+                .relocations_in(0x2389D8, 8)
+                .next()
+                .unwrap()
+                .symbol
+                .as_deref(),
+            Some("__tls_get_addr")
+        );
+
+        // the base relocations and the IAT
+        let data = include_bytes!("../../test/pe.bin");
+        let header = Header::parse_header(data, "./pe.bin", &filesystem);
+        let iat = header.virtual_to_physical_address(0x140190000).unwrap();
+        assert_eq!(
+            header
+                .relocations_in(iat, 8)
+                .next()
+                .unwrap()
+                .symbol
+                .as_deref(),
+            Some("advapi32!SystemFunction036")
+        );
+        assert!(header
+            .get_relocations()
+            .iter()
+            .any(|relocation| relocation.symbol.is_none() && relocation.size == 8));
+
+        // the rebases and the lazy binds
+        let data = include_bytes!("../../test/macho.bin");
+        let header = Header::parse_header(data, "./macho.bin", &filesystem);
+        let got = header.virtual_to_physical_address(0x1001F0000).unwrap();
+        assert!(header.relocations_in(got, 8).next().is_some());
+        let lazy_pointer = header.virtual_to_physical_address(0x100200000).unwrap();
+        assert_eq!(
+            header
+                .relocations_in(lazy_pointer, 8)
+                .next()
+                .unwrap()
+                .symbol
+                .as_deref(),
+            Some("__NSGetArgc")
+        );
+
+        assert_eq!(Header::None.relocations_in(0, 0x100).count(), 0);
+    }
+
+    #[test]
+    fn test_unsupported_architecture() {
+        for architecture in [Architecture::Avr, Architecture::Unknown] {
+            assert!(matches!(
+                Header::get_decoder_for_arch(
+                    &architecture,
+                    CpuMode::Default,
+                    Endianness::Little,
+                    Bitness::Bit32,
+                    X86Syntax::Intel
+                ),
+                Err(capstone::Error::UnsupportedArch)
+            ));
+            assert!(matches!(
+                Header::get_encoder_for_arch(
+                    &architecture,
+                    CpuMode::Default,
+                    Endianness::Little,
+                    Bitness::Bit32,
+                    X86Syntax::Intel
+                ),
+                Err(KeystoneError::Engine(keystone_engine::Error::ARCH))
+            ));
+        }
+    }
 }
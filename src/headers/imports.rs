@@ -0,0 +1,350 @@
+use std::{collections::HashMap, fmt::Display};
+
+use object::{
+    macho,
+    read::{
+        macho::{MachHeader, MachOFile, Nlist},
+        pe::{ImageNtHeaders, ImageThunkData, PeFile},
+    },
+    Architecture, Endianness, Object, ObjectSection, ObjectSymbol, ObjectSymbolTable,
+    RelocationTarget,
+};
+
+/// A function or a variable imported from a library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    /// The short name of the library, e.g. `kernel32`, if the file tells it.
+    pub library: Option<String>,
+    pub name: String,
+    /// The address of the slot filled by the loader with the address of the import:
+    /// the GOT entry, the IAT entry or the Mach-O symbol pointer.
+    pub slot: Option<u64>,
+    /// The address of the PLT entry or of the Mach-O stub that jumps through the slot.
+    pub stub: Option<u64>,
+}
+
+impl Display for Import {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.library {
+            Some(library) => write!(f, "{}!{}", library, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// A symbol that the file makes available to the other modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    pub name: String,
+    pub address: u64,
+}
+
+/// The short name of a library: the file name without the extensions,
+/// e.g. `kernel32` for `KERNEL32.dll` and `libc` for `libc.so.6`.
+fn library_name(path: &[u8]) -> Option<String> {
+    let path = String::from_utf8_lossy(path);
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or_default();
+    if file_name.is_empty() {
+        return None;
+    }
+    let name = file_name.split('.').next().unwrap_or(file_name);
+    if file_name.to_lowercase().ends_with(".dll") {
+        Some(name.to_lowercase())
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Collects the imports of ELF, PE and Mach-O files, with the slots and the stubs used to call them.
+pub(super) fn imports(header: &object::File, bytes: &[u8]) -> Vec<Import> {
+    let mut imports = match header {
+        object::File::Elf32(_) | object::File::Elf64(_) => elf_imports(header, bytes),
+        object::File::Pe32(pe) => pe_imports(pe).unwrap_or_default(),
+        object::File::Pe64(pe) => pe_imports(pe).unwrap_or_default(),
+        object::File::MachO32(macho) => macho_imports(macho, bytes).unwrap_or_default(),
+        object::File::MachO64(macho) => macho_imports(macho, bytes).unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    imports.sort_by(|a, b| (&a.library, &a.name).cmp(&(&b.library, &b.name)));
+    imports
+}
+
+pub(super) fn exports(header: &object::File) -> Vec<Export> {
+    let mut exports = header
+        .exports()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|export| export.address() != 0)
+        .map(|export| Export {
+            name: String::from_utf8_lossy(export.name()).to_string(),
+            address: export.address(),
+        })
+        .collect::<Vec<_>>();
+    exports.sort_by_key(|export| export.address);
+    exports.dedup();
+    exports
+}
+
+/// The libraries of the imports by name, as far as the file tells them.
+fn libraries(header: &object::File) -> HashMap<Vec<u8>, Option<String>> {
+    header
+        .imports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|import| (import.name().to_vec(), library_name(import.library())))
+        .collect()
+}
+
+/// The slots of ELF imports are the targets of the dynamic relocations of undefined symbols.
+/// The stubs are found in the PLT sections by decoding the jumps through the slots.
+fn elf_imports(header: &object::File, bytes: &[u8]) -> Vec<Import> {
+    let (Some(symbols), Some(relocations)) =
+        (header.dynamic_symbol_table(), header.dynamic_relocations())
+    else {
+        return Vec::new();
+    };
+    let libraries = libraries(header);
+    let mut imports: Vec<Import> = Vec::new();
+    // the index of the import of every slot
+    let mut slots = HashMap::new();
+    for (slot, relocation) in relocations {
+        let RelocationTarget::Symbol(index) = relocation.target() else {
+            continue;
+        };
+        let Ok(symbol) = symbols.symbol_by_index(index) else {
+            continue;
+        };
+        let name = symbol.name_bytes().unwrap_or_default();
+        if !symbol.is_undefined() || name.is_empty() || slots.contains_key(&slot) {
+            continue;
+        }
+        slots.insert(slot, imports.len());
+        imports.push(Import {
+            library: libraries.get(name).cloned().flatten(),
+            name: String::from_utf8_lossy(name).to_string(),
+            slot: Some(slot),
+            stub: None,
+        });
+    }
+
+    let got_plt = header
+        .section_by_name(".got.plt")
+        .map(|section| section.address());
+    for section in header.sections() {
+        let name = section.name().unwrap_or_default();
+        if !name.starts_with(".plt") {
+            continue;
+        }
+        let Some(data) = section
+            .file_range()
+            .and_then(|(offset, size)| bytes.get(offset as usize..(offset + size) as usize))
+        else {
+            continue;
+        };
+        let entry_size = if name == ".plt.got" { 8 } else { 16 };
+        for (stub, slot) in plt_stubs(header.architecture(), data, section.address(), got_plt) {
+            // with IBT the jumps through the slots are in `.plt.sec`, `.plt` only has the lazy binding
+            let stub = stub - (stub - section.address()) % entry_size;
+            if let Some(import) = slots
+                .get(&slot)
+                .map(|index| &mut imports[*index])
+                .filter(|import| import.stub.is_none())
+            {
+                import.stub = Some(stub);
+            }
+        }
+    }
+    imports
+}
+
+/// Finds the jumps through a slot in a PLT section, returns the address of every jump with its slot.
+/// x86 PLT entries jump with `jmp [rip + disp]`, `jmp [disp]` or `jmp [ebx + disp]`,
+/// AArch64 entries load the slot with `adrp x16, page` followed by `ldr x17, [x16, #offset]`.
+fn plt_stubs(
+    architecture: Architecture,
+    data: &[u8],
+    address: u64,
+    got_plt: Option<u64>,
+) -> Vec<(u64, u64)> {
+    let mut stubs = Vec::new();
+    match architecture {
+        Architecture::X86_64 | Architecture::I386 => {
+            for i in 0..data.len().saturating_sub(5) {
+                if data[i] != 0xFF || !matches!(data[i + 1], 0x25 | 0xA3) {
+                    continue;
+                }
+                let displacement = i32::from_le_bytes(data[i + 2..i + 6].try_into().unwrap());
+                let jump = address + i as u64;
+                let slot = match (architecture, data[i + 1]) {
+                    (Architecture::X86_64, 0x25) => {
+                        (jump + 6).wrapping_add_signed(displacement as i64)
+                    }
+                    (Architecture::I386, 0x25) => displacement as u32 as u64,
+                    (Architecture::I386, 0xA3) => match got_plt {
+                        Some(got_plt) => got_plt.wrapping_add_signed(displacement as i64),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                stubs.push((jump, slot));
+            }
+        }
+        Architecture::Aarch64 => {
+            let words = data
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                .collect::<Vec<_>>();
+            for (i, pair) in words.windows(2).enumerate() {
+                let (adrp, ldr) = (pair[0], pair[1]);
+                if adrp & 0x9F00_0000 != 0x9000_0000 || ldr & 0xFFC0_0000 != 0xF940_0000 {
+                    continue;
+                }
+                let pc = address + i as u64 * 4;
+                let immediate = (((adrp >> 5) & 0x7FFFF) << 2 | (adrp >> 29) & 0x3) as i64;
+                // the immediate is a signed 21-bit number of pages
+                let pages = (immediate << 43) >> 43;
+                let page = (pc & !0xFFF).wrapping_add_signed(pages << 12);
+                let offset = ((ldr >> 10) & 0xFFF) as u64 * 8;
+                stubs.push((pc, page + offset));
+            }
+        }
+        _ => {}
+    }
+    stubs
+}
+
+fn pe_imports<Pe: ImageNtHeaders>(pe: &PeFile<Pe>) -> object::Result<Vec<Import>> {
+    let mut imports = Vec::new();
+    let Some(import_table) = pe.import_table()? else {
+        return Ok(imports);
+    };
+    let image_base = pe.relative_address_base();
+    let mut descriptors = import_table.descriptors()?;
+    while let Some(descriptor) = descriptors.next()? {
+        let library = library_name(import_table.name(descriptor.name.get(object::LittleEndian))?);
+        let first_thunk = descriptor.first_thunk.get(object::LittleEndian);
+        let names = match descriptor.original_first_thunk.get(object::LittleEndian) {
+            0 => first_thunk,
+            original_first_thunk => original_first_thunk,
+        };
+        let mut thunks = import_table.thunks(names)?;
+        let mut index = 0;
+        while let Some(thunk) = thunks.next::<Pe>()? {
+            let name = if thunk.is_ordinal() {
+                format!("#{}", thunk.ordinal())
+            } else {
+                let (_hint, name) = import_table.hint_name(thunk.address())?;
+                String::from_utf8_lossy(name).to_string()
+            };
+            let slot = image_base
+                + first_thunk as u64
+                + (index * std::mem::size_of::<Pe::ImageThunkData>()) as u64;
+            imports.push(Import {
+                library: library.clone(),
+                name,
+                slot: Some(slot),
+                stub: None,
+            });
+            index += 1;
+        }
+    }
+    Ok(imports)
+}
+
+/// The symbol pointers and the stubs of Mach-O files are described by the indirect symbol table,
+/// every section of pointers or stubs starts from the entry in `reserved1`.
+fn macho_imports<Mach: MachHeader<Endian = Endianness>>(
+    macho: &MachOFile<Mach>,
+    bytes: &[u8],
+) -> object::Result<Vec<Import>> {
+    let endian = macho.endianness();
+    let mut indirect_symbols = Vec::new();
+    let mut commands = macho.macho_load_commands()?;
+    while let Some(command) = commands.next()? {
+        if let Some(dysymtab) = command.dysymtab()? {
+            let offset = dysymtab.indirectsymoff.get(endian) as usize;
+            let count = dysymtab.nindirectsyms.get(endian) as usize;
+            if let Some(table) = bytes.get(offset..offset + count * 4) {
+                indirect_symbols = table
+                    .chunks_exact(4)
+                    .map(|entry| match endian {
+                        Endianness::Little => u32::from_le_bytes(entry.try_into().unwrap()),
+                        Endianness::Big => u32::from_be_bytes(entry.try_into().unwrap()),
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    let libraries = libraries(&object::File::parse(bytes)?);
+    let symbol_table = macho.macho_symbol_table();
+    let pointer_size = if macho.is_64() { 8 } else { 4 };
+    let mut imports: Vec<Import> = Vec::new();
+    for section in macho.sections() {
+        let object::SectionFlags::MachO { flags } = section.flags() else {
+            continue;
+        };
+        let kind = flags & macho::SECTION_TYPE;
+        let (first, stub_size) = macho_reserved(section.macho_section(), endian, macho.is_64());
+        let entry_size = match kind {
+            macho::S_NON_LAZY_SYMBOL_POINTERS | macho::S_LAZY_SYMBOL_POINTERS => pointer_size,
+            macho::S_SYMBOL_STUBS if stub_size > 0 => stub_size as u64,
+            _ => continue,
+        };
+        for i in 0..section.size() / entry_size {
+            let Some(&index) = indirect_symbols.get(first as usize + i as usize) else {
+                break;
+            };
+            if index & (macho::INDIRECT_SYMBOL_LOCAL | macho::INDIRECT_SYMBOL_ABS) != 0 {
+                continue;
+            }
+            let Ok(symbol) = symbol_table.symbol(object::SymbolIndex(index as usize)) else {
+                continue;
+            };
+            let Ok(name) = symbol.name(endian, symbol_table.strings()) else {
+                continue;
+            };
+            let address = section.address() + i * entry_size;
+            let import = match imports
+                .iter_mut()
+                .find(|import| import.name.as_bytes() == name)
+            {
+                Some(import) => import,
+                None => {
+                    imports.push(Import {
+                        library: libraries.get(name).cloned().flatten(),
+                        name: String::from_utf8_lossy(name).to_string(),
+                        slot: None,
+                        stub: None,
+                    });
+                    imports.last_mut().unwrap()
+                }
+            };
+            if kind == macho::S_SYMBOL_STUBS {
+                import.stub.get_or_insert(address);
+            } else {
+                import.slot.get_or_insert(address);
+            }
+        }
+    }
+    Ok(imports)
+}
+
+/// The `reserved1` and `reserved2` fields of a Mach-O section, they follow the flags
+/// in both the 32-bit and the 64-bit section headers.
+fn macho_reserved<S: object::read::macho::Section>(
+    section: &S,
+    endian: Endianness,
+    is_64: bool,
+) -> (u32, u32) {
+    let raw = object::pod::bytes_of(section);
+    let offset = if is_64 { 68 } else { 60 };
+    let read = |offset: usize| {
+        let field: [u8; 4] = raw[offset..offset + 4].try_into().unwrap();
+        match endian {
+            Endianness::Little => u32::from_le_bytes(field),
+            Endianness::Big => u32::from_be_bytes(field),
+        }
+    };
+    (read(offset), read(offset + 4))
+}
@@ -4,4 +4,9 @@ pub use header::*;
 pub mod bitness;
 pub mod custom_header;
 pub mod generic;
+pub mod imports;
+pub mod relocations;
 pub mod section;
+pub mod segment;
+pub mod target;
+pub mod unwind;
@@ -0,0 +1,542 @@
+use object::{
+    macho, pe,
+    read::{
+        macho::{MachHeader, MachOFile},
+        pe::{ImageNtHeaders, PeFile},
+    },
+    Endianness, Object, ObjectKind, ObjectSection, ObjectSegment, ObjectSymbol, ObjectSymbolTable,
+    RelocationTarget,
+};
+
+/// Bytes that the loader rewrites when the file is loaded: patching them has no effect
+/// or corrupts the patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    pub file_address: u64,
+    pub virtual_address: u64,
+    /// The number of rewritten bytes.
+    pub size: u64,
+    /// The symbol whose address is written, `None` if the loader only adds the load address.
+    pub symbol: Option<String>,
+}
+
+/// A relocation found by virtual address, before it is placed in the file.
+struct Fixup {
+    virtual_address: u64,
+    size: u64,
+    symbol: Option<String>,
+}
+
+/// Collects the ELF dynamic relocations, the PE base relocations, the Mach-O rebases and binds
+/// (opcodes or chained fixups) and the relocations of the object files. Sorted by file address.
+pub(super) fn relocations(header: &object::File, bytes: &[u8]) -> Vec<Relocation> {
+    let mut relocations = if header.kind() == ObjectKind::Relocatable {
+        section_relocations(header)
+    } else {
+        let fixups = match header {
+            object::File::Elf32(_) | object::File::Elf64(_) => elf_fixups(header),
+            object::File::Pe32(pe) => pe_fixups(pe).unwrap_or_default(),
+            object::File::Pe64(pe) => pe_fixups(pe).unwrap_or_default(),
+            object::File::MachO32(macho) => macho_fixups(macho, bytes).unwrap_or_default(),
+            object::File::MachO64(macho) => macho_fixups(macho, bytes).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        place_fixups(header, fixups)
+    };
+    // a pointer that is both rebased and bound keeps its symbol
+    relocations.sort_by_key(|relocation| (relocation.file_address, relocation.symbol.is_none()));
+    relocations.dedup_by_key(|relocation| relocation.file_address);
+    relocations
+}
+
+fn pointer_size(header: &object::File) -> u64 {
+    if header.is_64() {
+        8
+    } else {
+        4
+    }
+}
+
+/// Finds the file address of the fixups, the fixups of the bytes that are not in the file are dropped.
+fn place_fixups(header: &object::File, fixups: Vec<Fixup>) -> Vec<Relocation> {
+    let sections = header
+        .sections()
+        .filter_map(|section| {
+            section
+                .file_range()
+                .map(|(offset, size)| (section.address(), offset, size))
+        })
+        .collect::<Vec<_>>();
+    fixups
+        .into_iter()
+        .filter_map(|fixup| {
+            let (address, offset, _) = sections.iter().find(|(address, _, size)| {
+                *address <= fixup.virtual_address && fixup.virtual_address < address + size
+            })?;
+            Some(Relocation {
+                file_address: offset + fixup.virtual_address - address,
+                virtual_address: fixup.virtual_address,
+                size: fixup.size,
+                symbol: fixup.symbol,
+            })
+        })
+        .collect()
+}
+
+/// The relocations of the sections of object files, that are not loaded yet.
+fn section_relocations(header: &object::File) -> Vec<Relocation> {
+    let mut relocations = Vec::new();
+    for section in header.sections() {
+        let Some((file_offset, _)) = section.file_range() else {
+            continue;
+        };
+        for (offset, relocation) in section.relocations() {
+            let symbol = match relocation.target() {
+                RelocationTarget::Symbol(index) => header
+                    .symbol_by_index(index)
+                    .ok()
+                    .and_then(|symbol| symbol.name().ok().map(str::to_string))
+                    .filter(|name| !name.is_empty()),
+                _ => None,
+            };
+            relocations.push(Relocation {
+                file_address: file_offset + offset,
+                virtual_address: section.address() + offset,
+                size: match relocation.size() as u64 / 8 {
+                    0 => pointer_size(header),
+                    size => size,
+                },
+                symbol,
+            });
+        }
+    }
+    relocations
+}
+
+fn elf_fixups(header: &object::File) -> Vec<Fixup> {
+    let Some(relocations) = header.dynamic_relocations() else {
+        return Vec::new();
+    };
+    let symbols = header.dynamic_symbol_table();
+    relocations
+        .map(|(address, relocation)| {
+            let symbol = match (relocation.target(), &symbols) {
+                (RelocationTarget::Symbol(index), Some(symbols)) => symbols
+                    .symbol_by_index(index)
+                    .ok()
+                    .and_then(|symbol| symbol.name().ok().map(str::to_string))
+                    .filter(|name| !name.is_empty()),
+                _ => None,
+            };
+            Fixup {
+                virtual_address: address,
+                size: match relocation.size() as u64 / 8 {
+                    0 => pointer_size(header),
+                    size => size,
+                },
+                symbol,
+            }
+        })
+        .collect()
+}
+
+fn pe_fixups<Pe: ImageNtHeaders>(pe: &PeFile<Pe>) -> object::Result<Vec<Fixup>> {
+    let mut fixups = Vec::new();
+    let Some(mut blocks) = pe
+        .data_directories()
+        .relocation_blocks(pe.data(), &pe.section_table())?
+    else {
+        return Ok(fixups);
+    };
+    let image_base = pe.relative_address_base();
+    while let Some(block) = blocks.next()? {
+        for relocation in block {
+            let size = match relocation.typ {
+                pe::IMAGE_REL_BASED_ABSOLUTE => continue,
+                pe::IMAGE_REL_BASED_HIGH | pe::IMAGE_REL_BASED_LOW => 2,
+                pe::IMAGE_REL_BASED_HIGHLOW => 4,
+                pe::IMAGE_REL_BASED_DIR64 => 8,
+                // the pairs of `movw` and `movt` of ARM and Thumb
+                _ => 8,
+            };
+            fixups.push(Fixup {
+                virtual_address: image_base + relocation.virtual_address as u64,
+                size,
+                symbol: None,
+            });
+        }
+    }
+    Ok(fixups)
+}
+
+/// Reads the unsigned LEB128 number at `*index` and moves `*index` after it.
+fn read_uleb(data: &[u8], index: &mut usize) -> u64 {
+    let mut value = 0u64;
+    let mut shift = 0;
+    while let Some(&byte) = data.get(*index) {
+        *index += 1;
+        if shift < 64 {
+            value |= ((byte & 0x7F) as u64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    value
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// The addresses and the file offsets of the segments of a Mach-O file, by segment index.
+fn macho_segments<Mach: MachHeader<Endian = Endianness>>(
+    macho: &MachOFile<Mach>,
+) -> Vec<(u64, u64)> {
+    macho
+        .segments()
+        .map(|segment| (segment.address(), segment.file_range().0))
+        .collect()
+}
+
+/// Mach-O executables describe the pointers to rebase and to bind either with the opcodes
+/// of `LC_DYLD_INFO` or with the chains of `LC_DYLD_CHAINED_FIXUPS`.
+fn macho_fixups<Mach: MachHeader<Endian = Endianness>>(
+    macho: &MachOFile<Mach>,
+    bytes: &[u8],
+) -> object::Result<Vec<Fixup>> {
+    let endian = macho.endianness();
+    let segments = macho_segments(macho);
+    let pointer_size = if macho.is_64() { 8 } else { 4 };
+    let mut fixups = Vec::new();
+    let mut commands = macho.macho_load_commands()?;
+    while let Some(command) = commands.next()? {
+        if let Some(dyld_info) = command.dyld_info()? {
+            let stream = |offset: &object::U32<Endianness>, size: &object::U32<Endianness>| {
+                let offset = offset.get(endian) as usize;
+                bytes
+                    .get(offset..offset + size.get(endian) as usize)
+                    .unwrap_or_default()
+            };
+            fixups.extend(macho_rebases(
+                stream(&dyld_info.rebase_off, &dyld_info.rebase_size),
+                &segments,
+                pointer_size,
+            ));
+            for (offset, size) in [
+                (&dyld_info.bind_off, &dyld_info.bind_size),
+                (&dyld_info.weak_bind_off, &dyld_info.weak_bind_size),
+                (&dyld_info.lazy_bind_off, &dyld_info.lazy_bind_size),
+            ] {
+                fixups.extend(macho_binds(stream(offset, size), &segments, pointer_size));
+            }
+        } else if command.cmd() == macho::LC_DYLD_CHAINED_FIXUPS {
+            let linkedit = command.data::<macho::LinkeditDataCommand<Endianness>>()?;
+            let offset = linkedit.dataoff.get(endian) as usize;
+            if let Some(data) = bytes.get(offset..offset + linkedit.datasize.get(endian) as usize) {
+                fixups.extend(macho_chained_fixups(data, bytes, &segments).unwrap_or_default());
+            }
+        }
+    }
+    Ok(fixups)
+}
+
+/// Runs the `REBASE_OPCODE_*` stream, every rebased location is a pointer.
+fn macho_rebases(opcodes: &[u8], segments: &[(u64, u64)], pointer_size: u64) -> Vec<Fixup> {
+    let mut fixups = Vec::new();
+    let (mut segment, mut offset) = (0usize, 0u64);
+    let mut rebase = |segment: usize, offset: u64| {
+        if let Some((address, _)) = segments.get(segment) {
+            fixups.push(Fixup {
+                virtual_address: address.wrapping_add(offset),
+                size: pointer_size,
+                symbol: None,
+            });
+        }
+    };
+    let mut index = 0;
+    while let Some(&byte) = opcodes.get(index) {
+        index += 1;
+        let immediate = (byte & macho::REBASE_IMMEDIATE_MASK) as u64;
+        match byte & macho::REBASE_OPCODE_MASK {
+            macho::REBASE_OPCODE_DONE => break,
+            macho::REBASE_OPCODE_SET_TYPE_IMM => {}
+            macho::REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                segment = immediate as usize;
+                offset = read_uleb(opcodes, &mut index);
+            }
+            macho::REBASE_OPCODE_ADD_ADDR_ULEB => {
+                offset = offset.wrapping_add(read_uleb(opcodes, &mut index));
+            }
+            macho::REBASE_OPCODE_ADD_ADDR_IMM_SCALED => {
+                offset = offset.wrapping_add(immediate * pointer_size);
+            }
+            macho::REBASE_OPCODE_DO_REBASE_IMM_TIMES
+            | macho::REBASE_OPCODE_DO_REBASE_ULEB_TIMES => {
+                let count = if byte & macho::REBASE_OPCODE_MASK
+                    == macho::REBASE_OPCODE_DO_REBASE_IMM_TIMES
+                {
+                    immediate
+                } else {
+                    read_uleb(opcodes, &mut index)
+                };
+                for _ in 0..count.min(opcodes.len() as u64 * 0x80) {
+                    rebase(segment, offset);
+                    offset = offset.wrapping_add(pointer_size);
+                }
+            }
+            macho::REBASE_OPCODE_DO_REBASE_ADD_ADDR_ULEB => {
+                rebase(segment, offset);
+                offset = offset
+                    .wrapping_add(read_uleb(opcodes, &mut index))
+                    .wrapping_add(pointer_size);
+            }
+            macho::REBASE_OPCODE_DO_REBASE_ULEB_TIMES_SKIPPING_ULEB => {
+                let count = read_uleb(opcodes, &mut index);
+                let skip = read_uleb(opcodes, &mut index);
+                for _ in 0..count.min(opcodes.len() as u64 * 0x80) {
+                    rebase(segment, offset);
+                    offset = offset.wrapping_add(skip).wrapping_add(pointer_size);
+                }
+            }
+            _ => break,
+        }
+    }
+    fixups
+}
+
+/// Runs a `BIND_OPCODE_*` stream, the lazy binds are separated by `BIND_OPCODE_DONE`.
+fn macho_binds(opcodes: &[u8], segments: &[(u64, u64)], pointer_size: u64) -> Vec<Fixup> {
+    let mut fixups = Vec::new();
+    let (mut segment, mut offset) = (0usize, 0u64);
+    let mut symbol = String::new();
+    let mut bind = |segment: usize, offset: u64, symbol: &str| {
+        if let Some((address, _)) = segments.get(segment) {
+            fixups.push(Fixup {
+                virtual_address: address.wrapping_add(offset),
+                size: pointer_size,
+                symbol: Some(symbol.to_string()).filter(|symbol| !symbol.is_empty()),
+            });
+        }
+    };
+    let mut index = 0;
+    while let Some(&byte) = opcodes.get(index) {
+        index += 1;
+        let immediate = (byte & macho::BIND_IMMEDIATE_MASK) as u64;
+        match byte & macho::BIND_OPCODE_MASK {
+            macho::BIND_OPCODE_DONE
+            | macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM
+            | macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM
+            | macho::BIND_OPCODE_SET_TYPE_IMM => {}
+            macho::BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB | macho::BIND_OPCODE_SET_ADDEND_SLEB => {
+                read_uleb(opcodes, &mut index);
+            }
+            macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
+                let end = opcodes[index..]
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .map_or(opcodes.len(), |length| index + length);
+                symbol = String::from_utf8_lossy(&opcodes[index..end]).to_string();
+                index = end + 1;
+            }
+            macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                segment = immediate as usize;
+                offset = read_uleb(opcodes, &mut index);
+            }
+            macho::BIND_OPCODE_ADD_ADDR_ULEB => {
+                offset = offset.wrapping_add(read_uleb(opcodes, &mut index));
+            }
+            macho::BIND_OPCODE_DO_BIND => {
+                bind(segment, offset, &symbol);
+                offset = offset.wrapping_add(pointer_size);
+            }
+            macho::BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                bind(segment, offset, &symbol);
+                offset = offset
+                    .wrapping_add(read_uleb(opcodes, &mut index))
+                    .wrapping_add(pointer_size);
+            }
+            macho::BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
+                bind(segment, offset, &symbol);
+                offset = offset.wrapping_add(immediate * pointer_size + pointer_size);
+            }
+            macho::BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                let count = read_uleb(opcodes, &mut index);
+                let skip = read_uleb(opcodes, &mut index);
+                for _ in 0..count.min(opcodes.len() as u64 * 0x80) {
+                    bind(segment, offset, &symbol);
+                    offset = offset.wrapping_add(skip).wrapping_add(pointer_size);
+                }
+            }
+            _ => break,
+        }
+    }
+    fixups
+}
+
+/// Follows the chains of `LC_DYLD_CHAINED_FIXUPS`: every page of a segment tells where its first
+/// pointer is, and every pointer tells how far the next one is and if it is a rebase or a bind.
+/// Only the 64-bit pointer formats are read.
+fn macho_chained_fixups(data: &[u8], bytes: &[u8], segments: &[(u64, u64)]) -> Option<Vec<Fixup>> {
+    let starts_offset = read_u32(data, 4)? as usize;
+    let imports_offset = read_u32(data, 8)? as usize;
+    let symbols_offset = read_u32(data, 12)? as usize;
+    let imports_count = read_u32(data, 16)? as usize;
+    let imports_format = read_u32(data, 20)?;
+    let import_name = |ordinal: usize| -> Option<String> {
+        if ordinal >= imports_count {
+            return None;
+        }
+        let name_offset = match imports_format {
+            // DYLD_CHAINED_IMPORT and DYLD_CHAINED_IMPORT_ADDEND
+            1 => read_u32(data, imports_offset + ordinal * 4)? >> 9,
+            2 => read_u32(data, imports_offset + ordinal * 8)? >> 9,
+            // DYLD_CHAINED_IMPORT_ADDEND64
+            3 => (read_u64(data, imports_offset + ordinal * 16)? >> 32) as u32,
+            _ => return None,
+        } as usize;
+        let name = data.get(symbols_offset + name_offset..)?;
+        let end = name.iter().position(|&byte| byte == 0)?;
+        Some(String::from_utf8_lossy(&name[..end]).to_string())
+    };
+
+    let mut fixups = Vec::new();
+    let segment_count = read_u32(data, starts_offset)? as usize;
+    for (segment_index, (address, file_offset)) in segments.iter().enumerate().take(segment_count) {
+        let segment_starts = read_u32(data, starts_offset + 4 + segment_index * 4)? as usize;
+        if segment_starts == 0 {
+            continue;
+        }
+        let starts = starts_offset + segment_starts;
+        let page_size = read_u16(data, starts + 4)? as u64;
+        let pointer_format = read_u16(data, starts + 6)?;
+        let page_count = read_u16(data, starts + 20)? as u64;
+        // the stride of the offsets to the next pointer, the mask of the offset,
+        // the bind bit and the mask of the import ordinal
+        let (stride, next_mask, bind_bit, ordinal_mask) = match pointer_format {
+            // DYLD_CHAINED_PTR_ARM64E, DYLD_CHAINED_PTR_ARM64E_USERLAND
+            1 | 12 => (8, 0x7FF, 62, 0xFFFF),
+            // DYLD_CHAINED_PTR_ARM64E_USERLAND24
+            13 => (8, 0x7FF, 62, 0xFF_FFFF),
+            // DYLD_CHAINED_PTR_64, DYLD_CHAINED_PTR_64_OFFSET
+            2 | 6 => (4, 0xFFF, 63, 0xFF_FFFF),
+            _ => continue,
+        };
+        for page in 0..page_count {
+            let start = read_u16(data, starts + 22 + page as usize * 2)?;
+            // DYLD_CHAINED_PTR_START_NONE
+            if start == 0xFFFF {
+                continue;
+            }
+            let mut offset = page * page_size + start as u64;
+            while let Some(value) = read_u64(bytes, (file_offset + offset) as usize) {
+                let symbol = if value >> bind_bit & 1 == 1 {
+                    import_name((value & ordinal_mask) as usize)
+                } else {
+                    None
+                };
+                fixups.push(Fixup {
+                    virtual_address: address + offset,
+                    size: 8,
+                    symbol,
+                });
+                let next = (value >> 51) & next_mask;
+                if next == 0 {
+                    break;
+                }
+                offset += next * stride;
+            }
+        }
+    }
+    Some(fixups)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_macho_opcodes() {
+        let segments = [(0x1000, 0), (0x4000, 0x3000)];
+        // segment 1 + 0x10, rebase 2 times, skip 0x8, rebase once
+        let rebases = macho_rebases(&[0x21, 0x10, 0x52, 0x30, 0x08, 0x51, 0x00], &segments, 8);
+        assert_eq!(
+            rebases
+                .iter()
+                .map(|fixup| fixup.virtual_address)
+                .collect::<Vec<_>>(),
+            vec![0x4010, 0x4018, 0x4028]
+        );
+        // _puts at segment 1 + 0x20, bind, bind and skip 0x8
+        let binds = macho_binds(
+            &[
+                0x40, b'_', b'p', b'u', b't', b's', 0x00, 0x71, 0x20, 0x90, 0xA0, 0x08, 0x90, 0x00,
+            ],
+            &segments,
+            8,
+        );
+        assert_eq!(
+            binds
+                .iter()
+                .map(|fixup| (fixup.virtual_address, fixup.symbol.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0x4020, Some("_puts")),
+                (0x4028, Some("_puts")),
+                (0x4038, Some("_puts"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_macho_chained_fixups() {
+        let mut data = Vec::new();
+        // dyld_chained_fixups_header: starts at 28, imports at 64, symbols at 68, 1 import
+        for field in [0u32, 28, 64, 68, 1, 1, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        // dyld_chained_starts_in_image: 2 segments, only the second one has fixups
+        for field in [2u32, 0, 12] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        // dyld_chained_starts_in_segment: DYLD_CHAINED_PTR_64_OFFSET, one page starting at 0x10
+        data.extend_from_slice(&24u32.to_le_bytes());
+        data.extend_from_slice(&0x4000u16.to_le_bytes());
+        data.extend_from_slice(&6u16.to_le_bytes());
+        data.extend_from_slice(&0x3000u64.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0x10u16.to_le_bytes());
+        // the import of `_puts` from the first library
+        data.extend_from_slice(&(1u32 << 9 | 1).to_le_bytes());
+        data.extend_from_slice(b"\0_puts\0");
+
+        let mut bytes = vec![0; 0x120];
+        // a rebase followed 8 bytes later by a bind
+        bytes[0x110..0x118].copy_from_slice(&(2u64 << 51 | 0x1234).to_le_bytes());
+        bytes[0x118..0x120].copy_from_slice(&(1u64 << 63).to_le_bytes());
+        let fixups = macho_chained_fixups(&data, &bytes, &[(0x1000, 0), (0x4000, 0x100)]).unwrap();
+        assert_eq!(
+            fixups
+                .iter()
+                .map(|fixup| (fixup.virtual_address, fixup.symbol.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![(0x4010, None), (0x4018, Some("_puts"))]
+        );
+    }
+}
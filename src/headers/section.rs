@@ -2,22 +2,142 @@ use std::fmt::Display;
 
 use mlua::UserData;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SectionPermissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl SectionPermissions {
+    /// Used when the file format doesn't specify the permissions of a section.
+    pub fn from_name(name: &str) -> Self {
+        Self {
+            read: true,
+            write: false,
+            execute: matches!(name, ".text" | "__text"),
+        }
+    }
+
+    /// Parses permissions in the form `"rwx"`, missing permissions can be replaced by `'-'`.
+    pub fn parse(permissions: &str) -> Option<Self> {
+        let mut ret = Self::default();
+        for c in permissions.chars() {
+            match c {
+                'r' if !ret.read => ret.read = true,
+                'w' if !ret.write => ret.write = true,
+                'x' if !ret.execute => ret.execute = true,
+                '-' => {}
+                _ => return None,
+            }
+        }
+        Some(ret)
+    }
+}
+
+impl Display for SectionPermissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.execute { 'x' } else { '-' }
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SectionKind {
+    Code,
+    Data,
+    Bss,
+    Debug,
+    Other,
+}
+
+impl SectionKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        Some(match kind {
+            "code" => SectionKind::Code,
+            "data" => SectionKind::Data,
+            "bss" => SectionKind::Bss,
+            "debug" => SectionKind::Debug,
+            "other" => SectionKind::Other,
+            _ => return None,
+        })
+    }
+}
+
+impl Display for SectionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            SectionKind::Code => "code",
+            SectionKind::Data => "data",
+            SectionKind::Bss => "bss",
+            SectionKind::Debug => "debug",
+            SectionKind::Other => "other",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Section {
     pub name: String,
     pub virtual_address: u64,
     pub file_offset: u64,
     pub size: u64,
+    pub permissions: SectionPermissions,
+    pub kind: SectionKind,
+    pub alignment: u64,
+    /// The name of the segment that contains the section, if any.
+    pub segment: Option<String>,
+}
+
+impl Section {
+    /// Creates a section with the default attributes for its name,
+    /// used when the file format doesn't specify them.
+    pub fn new(name: String, virtual_address: u64, file_offset: u64, size: u64) -> Self {
+        let permissions = SectionPermissions::from_name(&name);
+        Self {
+            name,
+            virtual_address,
+            file_offset,
+            size,
+            permissions,
+            kind: if permissions.execute {
+                SectionKind::Code
+            } else {
+                SectionKind::Data
+            },
+            alignment: 1,
+            segment: None,
+        }
+    }
+
+    /// A short description of the attributes of the section, e.g. `r-x code align 0x10 in __TEXT`.
+    pub fn attributes(&self) -> String {
+        let mut attributes = format!("{} {}", self.permissions, self.kind);
+        if self.alignment > 1 {
+            attributes.push_str(&format!(" align {:#X}", self.alignment));
+        }
+        if let Some(segment) = &self.segment {
+            attributes.push_str(&format!(" in {}", segment));
+        }
+        attributes
+    }
 }
 
 impl Display for Section {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "{}: [{:X} - {:X})",
+            "{}: [{:X} - {:X}) {}",
             self.name,
             self.file_offset,
-            self.file_offset + self.size
+            self.file_offset + self.size,
+            self.attributes()
         )
     }
 }
@@ -28,5 +148,101 @@ impl UserData for Section {
         fields.add_field_method_get("virtual_address", |_, this| Ok(this.virtual_address));
         fields.add_field_method_get("file_offset", |_, this| Ok(this.file_offset));
         fields.add_field_method_get("size", |_, this| Ok(this.size));
+        fields.add_field_method_get("permissions", |_, this| Ok(this.permissions.to_string()));
+        fields.add_field_method_get("kind", |_, this| Ok(this.kind.to_string()));
+        fields.add_field_method_get("alignment", |_, this| Ok(this.alignment));
+        fields.add_field_method_get("segment", |_, this| Ok(this.segment.clone()));
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{app::files::filesystem::FileSystem, headers::Header};
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            SectionPermissions::parse("r-x"),
+            Some(SectionPermissions {
+                read: true,
+                write: false,
+                execute: true
+            })
+        );
+        assert_eq!(
+            SectionPermissions::parse("rw"),
+            SectionPermissions::parse("rw-")
+        );
+        assert!(SectionPermissions::parse("rwz").is_none());
+        assert!(SectionPermissions::parse("rr").is_none());
+        assert_eq!(SectionKind::parse("bss"), Some(SectionKind::Bss));
+        assert!(SectionKind::parse("text").is_none());
+    }
+
+    #[test]
+    fn test_attributes_from_header() {
+        let filesystem = FileSystem::new_local(".").unwrap();
+        let section = |path: &str, name: &str| {
+            let data = std::fs::read(path).unwrap();
+            Header::parse_header(&data, path, &filesystem)
+                .get_sections()
+                .into_iter()
+                .find(|section| section.name == name)
+        };
+
+        // ELF: SHF_ALLOC, SHF_WRITE and SHF_EXECINSTR, the segment is found by address
+        let text = section("test/elf.bin", ".text").unwrap();
+        assert_eq!(text.attributes(), "r-x code align 0x10 in LOAD1");
+        let data = section("test/elf.bin", ".data").unwrap();
+        assert_eq!(data.attributes(), "rw- data align 0x8 in LOAD3");
+        let symtab = section("test/elf.bin", ".symtab").unwrap();
+        assert_eq!(symtab.attributes(), "--- other align 0x8");
+        // .bss takes no space in the file
+        assert!(section("test/elf.bin", ".bss").is_none());
+
+        // PE: the characteristics, PE sections are not in a segment
+        let text = section("test/pe.bin", ".text").unwrap();
+        assert_eq!(text.attributes(), "r-x code align 0x1000");
+        let data = section("test/pe.bin", ".data").unwrap();
+        assert_eq!(data.attributes(), "rw- data align 0x1000");
+
+        // Mach-O: the protection of the segment, executable only with instructions
+        let text = section("test/macho.bin", "__text").unwrap();
+        assert_eq!(text.attributes(), "r-x code align 0x10 in __TEXT");
+        let constants = section("test/macho.bin", "__const").unwrap();
+        assert_eq!(constants.attributes(), "r-- data align 0x10 in __TEXT");
+        let data = section("test/macho.bin", "__data").unwrap();
+        assert_eq!(data.attributes(), "rw- data align 0x8 in __DATA");
+    }
+
+    #[test]
+    fn test_user_data() {
+        let section = Section {
+            permissions: SectionPermissions::parse("rw-").unwrap(),
+            kind: SectionKind::Bss,
+            alignment: 8,
+            segment: Some("DATA".to_string()),
+            ..Section::new(".bss".to_string(), 0x1000, 0x200, 0x10)
+        };
+        let lua = mlua::Lua::new();
+        lua.globals().set("section", section).unwrap();
+        let fields = lua
+            .load("return section.permissions, section.kind, section.alignment, section.segment")
+            .eval::<(String, String, u64, Option<String>)>()
+            .unwrap();
+        assert_eq!(
+            fields,
+            (
+                "rw-".to_string(),
+                "bss".to_string(),
+                8,
+                Some("DATA".to_string())
+            )
+        );
+        let assignment = lua
+            .load("section.segment = nil; return section.name")
+            .eval::<String>();
+        assert!(assignment.is_err(), "the fields are read-only");
     }
 }
@@ -0,0 +1,98 @@
+use std::fmt::Display;
+
+use mlua::UserData;
+
+use super::section::{Section, SectionKind, SectionPermissions};
+
+/// A range of the file that is mapped in memory when the file is loaded,
+/// e.g. an ELF program header, a Mach-O segment or a PE section.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Segment {
+    pub name: String,
+    pub virtual_address: u64,
+    pub virtual_size: u64,
+    pub file_offset: u64,
+    pub file_size: u64,
+    pub permissions: SectionPermissions,
+}
+
+impl Segment {
+    /// Returns the file offset of `virtual_address` if it is mapped from the file.
+    pub fn virtual_to_physical_address(&self, virtual_address: u64) -> Option<u64> {
+        let offset = virtual_address.checked_sub(self.virtual_address)?;
+        (offset < self.file_size).then_some(self.file_offset + offset)
+    }
+
+    /// Returns the virtual address at which `physical_address` is mapped, if any.
+    pub fn physical_to_virtual_address(&self, physical_address: u64) -> Option<u64> {
+        let offset = physical_address.checked_sub(self.file_offset)?;
+        (offset < self.file_size.min(self.virtual_size)).then_some(self.virtual_address + offset)
+    }
+
+    /// A section that covers the bytes of the segment in the file,
+    /// used for the files whose section headers are stripped.
+    pub fn to_section(&self) -> Section {
+        Section {
+            name: self.name.clone(),
+            virtual_address: self.virtual_address,
+            file_offset: self.file_offset,
+            size: self.file_size,
+            permissions: self.permissions,
+            kind: if self.permissions.execute {
+                SectionKind::Code
+            } else {
+                SectionKind::Data
+            },
+            alignment: 1,
+            segment: None,
+        }
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: [{:X} - {:X}) @[{:X} - {:X}) {}",
+            self.name,
+            self.file_offset,
+            self.file_offset + self.file_size,
+            self.virtual_address,
+            self.virtual_address + self.virtual_size,
+            self.permissions
+        )
+    }
+}
+
+impl UserData for Segment {
+    fn add_fields<'lua, F: mlua::UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this| Ok(this.name.clone()));
+        fields.add_field_method_get("virtual_address", |_, this| Ok(this.virtual_address));
+        fields.add_field_method_get("virtual_size", |_, this| Ok(this.virtual_size));
+        fields.add_field_method_get("file_offset", |_, this| Ok(this.file_offset));
+        fields.add_field_method_get("file_size", |_, this| Ok(this.file_size));
+        fields.add_field_method_get("permissions", |_, this| Ok(this.permissions.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_address_conversion() {
+        let segment = Segment {
+            name: "LOAD0".to_string(),
+            virtual_address: 0x1000,
+            virtual_size: 0x300,
+            file_offset: 0x200,
+            file_size: 0x100,
+            permissions: SectionPermissions::default(),
+        };
+        assert_eq!(segment.virtual_to_physical_address(0x1010), Some(0x210));
+        assert_eq!(segment.virtual_to_physical_address(0x1200), None);
+        assert_eq!(segment.virtual_to_physical_address(0xFFF), None);
+        assert_eq!(segment.physical_to_virtual_address(0x2FF), Some(0x10FF));
+        assert_eq!(segment.physical_to_virtual_address(0x300), None);
+    }
+}
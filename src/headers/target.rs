@@ -0,0 +1,303 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use object::{Architecture, Endianness};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bitness::Bitness,
+    custom_header::CustomHeader,
+    section::{Section, SectionKind, SectionPermissions},
+};
+
+/// The names of the architectures that can be chosen, the first name of each one is used to print it.
+const ARCHITECTURE_NAMES: &[(&str, Architecture)] = &[
+    ("x86_64", Architecture::X86_64),
+    ("x64", Architecture::X86_64),
+    ("amd64", Architecture::X86_64),
+    ("x86", Architecture::I386),
+    ("i386", Architecture::I386),
+    ("arm", Architecture::Arm),
+    ("aarch64", Architecture::Aarch64),
+    ("arm64", Architecture::Aarch64),
+    ("m68k", Architecture::M68k),
+    ("mips", Architecture::Mips),
+    ("mips64", Architecture::Mips64),
+    ("ppc", Architecture::PowerPc),
+    ("powerpc", Architecture::PowerPc),
+    ("ppc64", Architecture::PowerPc64),
+    ("powerpc64", Architecture::PowerPc64),
+    ("riscv32", Architecture::Riscv32),
+    ("riscv64", Architecture::Riscv64),
+    ("sparc64", Architecture::Sparc64),
+    ("s390x", Architecture::S390x),
+];
+
+/// The instruction set used by the decoder and the encoder, for the architectures that have more than one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CpuMode {
+    #[default]
+    Default,
+    /// Thumb and Thumb-2 on 32-bit ARM.
+    Thumb,
+    /// Real mode on x86.
+    Bit16,
+}
+
+/// The architecture, the mode, the endianness and the load address chosen by the user for a file without a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Target {
+    pub architecture: Architecture,
+    pub mode: CpuMode,
+    pub endianness: Endianness,
+    pub base: u64,
+}
+
+impl Target {
+    pub fn architecture_names() -> impl Iterator<Item = &'static str> {
+        ARCHITECTURE_NAMES.iter().map(|(name, _)| *name)
+    }
+
+    fn architecture_name(architecture: Architecture) -> &'static str {
+        ARCHITECTURE_NAMES
+            .iter()
+            .find(|(_, arch)| *arch == architecture)
+            .map(|(name, _)| *name)
+            .expect("Every target architecture has a name")
+    }
+
+    /// Builds a target from the options of the command line, `mode`, `endian` and `base`
+    /// are the same words accepted by [`Target::from_str`].
+    pub fn new(
+        arch: &str,
+        mode: Option<&str>,
+        endian: Option<&str>,
+        base: Option<&str>,
+    ) -> Result<Self, String> {
+        let architecture = ARCHITECTURE_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(arch))
+            .map(|(_, architecture)| *architecture)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown architecture \"{}\", expected one of: {}",
+                    arch,
+                    Self::architecture_names().collect::<Vec<_>>().join(", ")
+                )
+            })?;
+        let mut target = Target {
+            architecture,
+            mode: CpuMode::Default,
+            endianness: match architecture {
+                Architecture::M68k | Architecture::Sparc64 | Architecture::S390x => Endianness::Big,
+                _ => Endianness::Little,
+            },
+            base: 0,
+        };
+        if let Some(mode) = mode {
+            target.set_mode(mode)?;
+        }
+        if let Some(endian) = endian {
+            target.set_endianness(endian)?;
+        }
+        if let Some(base) = base {
+            target.base = Self::parse_base(base)?;
+        }
+        Ok(target)
+    }
+
+    fn set_mode(&mut self, mode: &str) -> Result<(), String> {
+        use Architecture::*;
+        let (architecture, mode) = match (self.architecture, mode.to_ascii_lowercase().as_str()) {
+            (architecture, "default") => (architecture, CpuMode::Default),
+            (Arm, "arm") => (Arm, CpuMode::Default),
+            (Arm, "thumb") => (Arm, CpuMode::Thumb),
+            (I386 | X86_64, "16") => (I386, CpuMode::Bit16),
+            (I386 | X86_64, "32") => (I386, CpuMode::Default),
+            (I386 | X86_64, "64") => (X86_64, CpuMode::Default),
+            (Arm | Aarch64, "32") => (Arm, CpuMode::Default),
+            (Arm | Aarch64, "64") => (Aarch64, CpuMode::Default),
+            (Mips | Mips64, "32") => (Mips, CpuMode::Default),
+            (Mips | Mips64, "64") => (Mips64, CpuMode::Default),
+            (PowerPc | PowerPc64, "32") => (PowerPc, CpuMode::Default),
+            (PowerPc | PowerPc64, "64") => (PowerPc64, CpuMode::Default),
+            (Riscv32 | Riscv64, "32") => (Riscv32, CpuMode::Default),
+            (Riscv32 | Riscv64, "64") => (Riscv64, CpuMode::Default),
+            (Sparc64, "64") | (S390x, "64") => (self.architecture, CpuMode::Default),
+            (architecture, mode) => {
+                return Err(format!(
+                    "Mode \"{}\" is not supported by {}",
+                    mode,
+                    Self::architecture_name(architecture)
+                ))
+            }
+        };
+        self.architecture = architecture;
+        self.mode = mode;
+        Ok(())
+    }
+
+    fn set_endianness(&mut self, endian: &str) -> Result<(), String> {
+        let endianness = match endian.to_ascii_lowercase().as_str() {
+            "little" | "le" => Endianness::Little,
+            "big" | "be" => Endianness::Big,
+            _ => return Err(format!("Unknown endianness \"{}\"", endian)),
+        };
+        let supported = match self.architecture {
+            Architecture::Arm
+            | Architecture::Aarch64
+            | Architecture::Mips
+            | Architecture::Mips64
+            | Architecture::PowerPc
+            | Architecture::PowerPc64 => true,
+            Architecture::M68k | Architecture::Sparc64 | Architecture::S390x => {
+                endianness == Endianness::Big
+            }
+            _ => endianness == Endianness::Little,
+        };
+        if !supported {
+            return Err(format!(
+                "{} can't be {} endian",
+                Self::architecture_name(self.architecture),
+                Self::endianness_name(endianness)
+            ));
+        }
+        self.endianness = endianness;
+        Ok(())
+    }
+
+    fn endianness_name(endianness: Endianness) -> &'static str {
+        match endianness {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        }
+    }
+
+    /// `base` can be either decimal or hexadecimal with the `0x` prefix.
+    fn parse_base(base: &str) -> Result<u64, String> {
+        let parsed = match base.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => base.parse::<u64>(),
+        };
+        parsed.map_err(|_| format!("Invalid base address \"{}\"", base))
+    }
+
+    /// The header of a file of `size` bytes loaded at the base address: a single
+    /// executable section that covers the whole file.
+    pub fn to_header(&self, size: usize) -> CustomHeader {
+        let bitness = match self.architecture.address_size() {
+            Some(object::AddressSize::U64) => Bitness::Bit64,
+            _ => Bitness::Bit32,
+        };
+        CustomHeader {
+            bitness,
+            entry: self.base,
+            endianness: self.endianness,
+            architecture: self.architecture,
+            mode: self.mode,
+            sections: vec![Section {
+                name: ".text".into(),
+                virtual_address: self.base,
+                file_offset: 0,
+                size: size as u64,
+                permissions: SectionPermissions {
+                    read: true,
+                    write: true,
+                    execute: true,
+                },
+                kind: SectionKind::Code,
+                alignment: 1,
+                segment: None,
+            }],
+            symbols: HashMap::new(),
+            symbols_by_name: HashMap::new(),
+        }
+    }
+}
+
+/// Parses `ARCH [MODE] [ENDIAN] [BASE]`, e.g. `arm thumb big 0x8000000`.
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let arch = words
+            .next()
+            .ok_or_else(|| "Missing architecture".to_string())?;
+        let (mut mode, mut endian, mut base) = (None, None, None);
+        for word in words {
+            match word.to_ascii_lowercase().as_str() {
+                "little" | "le" | "big" | "be" => endian = Some(word),
+                "16" | "32" | "64" => mode = Some(word),
+                _ if word.starts_with(|c: char| c.is_ascii_digit()) => base = Some(word),
+                _ => mode = Some(word),
+            }
+        }
+        Self::new(arch, mode, endian, base)
+    }
+}
+
+impl TryFrom<String> for Target {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Target> for String {
+    fn from(value: Target) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::architecture_name(self.architecture))?;
+        match self.mode {
+            CpuMode::Default => {}
+            CpuMode::Thumb => write!(f, " thumb")?,
+            CpuMode::Bit16 => write!(f, " 16")?,
+        }
+        write!(
+            f,
+            " {} {:#x}",
+            Self::endianness_name(self.endianness),
+            self.base
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_target() {
+        let target = "arm thumb big 0x8000000".parse::<Target>().unwrap();
+        assert_eq!(target.architecture, Architecture::Arm);
+        assert_eq!(target.mode, CpuMode::Thumb);
+        assert_eq!(target.endianness, Endianness::Big);
+        assert_eq!(target.base, 0x8000000);
+        assert_eq!(target.to_string(), "arm thumb big 0x8000000");
+        assert_eq!(target.to_string().parse::<Target>(), Ok(target));
+
+        let target = Target::new("x86_64", Some("16"), None, Some("4096")).unwrap();
+        assert_eq!(target.architecture, Architecture::I386);
+        assert_eq!(target.mode, CpuMode::Bit16);
+        assert_eq!(target.base, 0x1000);
+        let target = Target::new("mips", Some("64"), Some("be"), None).unwrap();
+        assert_eq!(target.architecture, Architecture::Mips64);
+        assert_eq!(target.endianness, Endianness::Big);
+
+        assert!("avr".parse::<Target>().is_err());
+        assert!("x86 big".parse::<Target>().is_err());
+        assert!("mips thumb".parse::<Target>().is_err());
+        assert!("ppc 0xZZ".parse::<Target>().is_err());
+
+        let header = "ppc big 0x100".parse::<Target>().unwrap().to_header(0x20);
+        assert_eq!(header.bitness, Bitness::Bit32);
+        assert_eq!(header.sections[0].virtual_address, 0x100);
+        assert_eq!(header.sections[0].size, 0x20);
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+
+use object::{Architecture, Endianness};
+
+// DW_EH_PE pointer encodings, see the LSB specification of .eh_frame
+const DW_EH_PE_OMIT: u8 = 0xFF;
+const DW_EH_PE_FORMAT_MASK: u8 = 0x0F;
+const DW_EH_PE_APPLICATION_MASK: u8 = 0x70;
+const DW_EH_PE_PCREL: u8 = 0x10;
+
+/// A little reader of the fields of an unwind table.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    endianness: Endianness,
+}
+
+impl Reader<'_> {
+    fn read<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.bytes.get(self.offset..self.offset + N)?;
+        self.offset += N;
+        let mut value: [u8; N] = bytes.try_into().ok()?;
+        if self.endianness == Endianness::Big {
+            value.reverse();
+        }
+        Some(value)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.read::<1>().map(|value| value[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.read().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.read().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.read().map(u64::from_le_bytes)
+    }
+
+    fn uleb128(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7F) as u64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+    }
+
+    fn sleb128(&mut self) -> Option<i64> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7F) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Some(value);
+            }
+        }
+    }
+
+    fn c_string(&mut self) -> Option<&[u8]> {
+        let length = self
+            .bytes
+            .get(self.offset..)?
+            .iter()
+            .position(|b| *b == 0)?;
+        let string = &self.bytes[self.offset..self.offset + length];
+        self.offset += length + 1;
+        Some(string)
+    }
+
+    /// Reads a pointer with the given DW_EH_PE encoding,
+    /// `address` is the virtual address of the start of the table.
+    fn pointer(&mut self, encoding: u8, pointer_size: usize, address: u64) -> Option<u64> {
+        let field_address = address + self.offset as u64;
+        let value = match encoding & DW_EH_PE_FORMAT_MASK {
+            0x00 if pointer_size == 8 => self.u64()?,
+            0x00 => self.u32()? as u64,
+            0x01 => self.uleb128()?,
+            0x02 => self.u16()? as u64,
+            0x03 => self.u32()? as u64,
+            0x04 => self.u64()?,
+            0x09 => self.sleb128()? as u64,
+            0x0A => self.u16()? as i16 as u64,
+            0x0B => self.u32()? as i32 as u64,
+            0x0C => self.u64()?,
+            _ => return None,
+        };
+        match encoding & DW_EH_PE_APPLICATION_MASK {
+            0 => Some(value),
+            DW_EH_PE_PCREL => Some(field_address.wrapping_add(value)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the start and the size of the functions described by the FDEs of an `.eh_frame` section.
+/// `address` is the virtual address of the section.
+pub fn eh_frame_functions(
+    bytes: &[u8],
+    address: u64,
+    pointer_size: usize,
+    endianness: Endianness,
+) -> Vec<(u64, u64)> {
+    let mut functions = Vec::new();
+    // the pointer encoding of the FDEs of every CIE
+    let mut encodings: HashMap<usize, u8> = HashMap::new();
+    let mut reader = Reader {
+        bytes,
+        offset: 0,
+        endianness,
+    };
+    while let Some(length) = reader.u32() {
+        if length == 0 {
+            break;
+        }
+        let (length, header_size) = if length == u32::MAX {
+            match reader.u64() {
+                Some(length) => (length as usize, 12),
+                None => break,
+            }
+        } else {
+            (length as usize, 4)
+        };
+        let record_start = reader.offset - header_size;
+        let Some(next_record) = reader.offset.checked_add(length) else {
+            break;
+        };
+        if next_record <= record_start || next_record > bytes.len() {
+            break;
+        }
+        let id_offset = reader.offset;
+        let Some(id) = reader.u32() else {
+            break;
+        };
+        if id == 0 {
+            encodings.insert(
+                record_start,
+                cie_pointer_encoding(&mut reader, pointer_size).unwrap_or(DW_EH_PE_OMIT),
+            );
+        } else if let Some(encoding) = id_offset
+            .checked_sub(id as usize)
+            .and_then(|cie| encodings.get(&cie))
+        {
+            let start = reader.pointer(*encoding, pointer_size, address);
+            // the range has the format of the encoding, but it is not relative
+            let size = reader.pointer(*encoding & DW_EH_PE_FORMAT_MASK, pointer_size, address);
+            if let (Some(start), Some(size)) = (start, size) {
+                if start != 0 && size != 0 {
+                    functions.push((start, size));
+                }
+            }
+        }
+        reader.offset = next_record;
+    }
+    functions
+}
+
+/// Reads the encoding of the FDE pointers from the augmentation of a CIE.
+fn cie_pointer_encoding(reader: &mut Reader, pointer_size: usize) -> Option<u8> {
+    let version = reader.u8()?;
+    let augmentation = reader.c_string()?.to_vec();
+    if !augmentation.starts_with(b"z") {
+        return Some(0);
+    }
+    reader.uleb128()?; // code alignment factor
+    reader.sleb128()?; // data alignment factor
+    if version == 1 {
+        reader.u8()?;
+    } else {
+        reader.uleb128()?;
+    }
+    reader.uleb128()?; // augmentation length
+    for character in &augmentation[1..] {
+        match character {
+            b'R' => return reader.u8(),
+            b'L' => {
+                reader.u8()?;
+            }
+            b'P' => {
+                let encoding = reader.u8()?;
+                // the personality is never relative to anything that matters here
+                reader.pointer(encoding & DW_EH_PE_FORMAT_MASK, pointer_size, 0)?;
+            }
+            _ => {}
+        }
+    }
+    Some(0)
+}
+
+/// Returns the start and the size of the functions described by the `RUNTIME_FUNCTION`
+/// entries of a `.pdata` section, the addresses in the entries are relative to `image_base`.
+/// The x64 entries hold the end of the function, the ARM and ARM64 entries hold its length
+/// either packed in the entry or in the first word of the `.xdata` record at the RVA
+/// that `read_u32` reads.
+pub fn pdata_functions(
+    bytes: &[u8],
+    image_base: u64,
+    architecture: Architecture,
+    read_u32: impl Fn(u64) -> Option<u32>,
+) -> Vec<(u64, u64)> {
+    // the function lengths of ARM are counted in halfwords, those of ARM64 in words
+    let instruction_size = match architecture {
+        Architecture::Arm => 2,
+        Architecture::Aarch64 => 4,
+        _ => 0,
+    };
+    if instruction_size == 0 {
+        return bytes
+            .chunks_exact(12)
+            .filter_map(|entry| {
+                let begin = u32::from_le_bytes(entry[0..4].try_into().unwrap()) as u64;
+                let end = u32::from_le_bytes(entry[4..8].try_into().unwrap()) as u64;
+                (begin != 0 && end > begin).then_some((image_base + begin, end - begin))
+            })
+            .collect();
+    }
+    bytes
+        .chunks_exact(8)
+        .filter_map(|entry| {
+            let begin = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+            let unwind = u32::from_le_bytes(entry[4..8].try_into().unwrap());
+            // ARM sets the Thumb bit in the start of the functions
+            let begin = (begin & !1) as u64;
+            let length = match unwind & 3 {
+                0 => read_u32(unwind as u64)? & 0x3FFFF,
+                _ => (unwind >> 2) & 0x7FF,
+            } as u64
+                * instruction_size;
+            (begin != 0 && length != 0).then_some((image_base + begin, length))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eh_frame() {
+        let data = std::fs::read("test/elf.bin").unwrap();
+        // .eh_frame is at the same file offset and virtual address
+        let start = 0x1f0eb0;
+        let functions = eh_frame_functions(&data[start..], start as u64, 8, Endianness::Little);
+        assert_eq!(functions.len(), 3320);
+        assert_eq!(functions[0], (0x2b110, 0x26));
+        assert_eq!(functions[1], (0x18020, 0x30));
+    }
+
+    #[test]
+    fn test_pdata() {
+        let entries = [
+            0x00, 0x10, 0x00, 0x00, 0x20, 0x10, 0x00, 0x00, 0x00, 0x20, 0x00,
+            0x00, // 0x1000..0x1020
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // padding
+        ];
+        assert_eq!(
+            pdata_functions(&entries, 0x140000000, Architecture::X86_64, |_| None),
+            vec![(0x140001000, 0x20)]
+        );
+
+        let entries = [
+            0x00, 0x10, 0x00, 0x00, 0x21, 0x00, 0x00, 0x00, // packed, 8 instructions
+            0x40, 0x10, 0x00, 0x00, 0x00, 0x30, 0x00, 0x00, // .xdata at 0x3000
+        ];
+        let xdata = |rva| (rva == 0x3000).then_some(0x0800_0010);
+        assert_eq!(
+            pdata_functions(&entries, 0x140000000, Architecture::Aarch64, xdata),
+            vec![(0x140001000, 0x20), (0x140001040, 0x40)]
+        );
+    }
+
+    #[test]
+    fn test_eh_frame_malformed() {
+        // a 64-bit length that would wrap around the address space
+        let mut data = vec![0xFF; 4];
+        data.extend((u64::MAX - 11).to_le_bytes());
+        data.extend([0; 8]);
+        assert!(eh_frame_functions(&data, 0, 8, Endianness::Little).is_empty());
+        // a record longer than the section
+        let mut data = 0x100u32.to_le_bytes().to_vec();
+        data.extend([0; 8]);
+        assert!(eh_frame_functions(&data, 0, 8, Endianness::Little).is_empty());
+    }
+
+    #[test]
+    fn test_eh_frame_32_bit_personality() {
+        let mut cie = vec![
+            0, 0, 0, 0, // CIE id
+            1, b'z', b'P', b'R', 0, // version and augmentation
+            1, 0x7C, 8,    // code and data alignment, return register
+            6,    // augmentation length
+            0x00, // absptr personality encoding
+            0x78, 0x56, 0x34, 0x12, // 32-bit personality
+            0x1B, // FDE pointers: pcrel sdata4
+        ];
+        cie.resize(24, 0);
+        let mut data = (cie.len() as u32).to_le_bytes().to_vec();
+        data.extend(&cie);
+        let fde_start = data.len();
+        let mut fde = ((fde_start + 4) as u32).to_le_bytes().to_vec(); // CIE pointer
+        fde.extend(0x100i32.to_le_bytes()); // start, relative to the field
+        fde.extend(0x20u32.to_le_bytes());
+        data.extend((fde.len() as u32).to_le_bytes());
+        data.extend(&fde);
+        let start_field = fde_start + 8;
+        assert_eq!(
+            eh_frame_functions(&data, 0x1000, 4, Endianness::Little),
+            vec![(0x1000 + start_field as u64 + 0x100, 0x20)]
+        );
+    }
+}
@@ -29,6 +29,7 @@ function test(header_context, context)
         text_size = context.data:get(16) + context.data:get(17) * 0x100 
             + context.data:get(18) * 0x10000 + context.data:get(19) * 0x1000000
         header_context:add_section(".text", text_start, text_start, text_size)
+        header_context:add_section(".rodata", 0x20, 0x20, text_start - 0x20, "r--", nil, 8, "HEADER")
         header_context:add_symbol(entry, "_start")
     end
 end